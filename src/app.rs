@@ -1,6 +1,11 @@
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use anyhow::Result;
+use std::sync::OnceLock;
+use std::path::PathBuf;
+use std::io::IsTerminal;
+use colored::*;
+use crate::util;
 
 use crate::commands::*;
 
@@ -32,7 +37,133 @@ pub trait Run {
     infer_subcommands = true,
     version,
 )]
+pub struct App {
+    #[command(flatten)]
+    options: GlobalOptions,
 
+    #[command(subcommand)]
+    command: Commands,
+}
+
+//  Options that may be given with any command.
+#[derive(Debug, Clone, Default, Args)]
+pub struct GlobalOptions {
+    /// Do not run any configured hooks.
+    #[arg(long, global = true)]
+    pub no_hooks: bool,
+
+    /// Display dates in this time zone.
+    ///
+    /// ZONE may be `local`, `UTC`, a fixed offset such as +05:30, or a named
+    /// zone such as Europe/Paris.  Defaults to the `display.timezone`
+    /// configuration value or the local time zone.
+    #[arg(long, global = true, value_name = "ZONE")]
+    pub timezone: Option<String>,
+
+    /// Pad revision numbers to a fixed width so that columns line up.
+    ///
+    /// WIDTH is a number of characters to right align revisions within, or
+    /// `head` to zero pad revisions to the number of digits in the HEAD revision.
+    #[arg(long, global = true, value_name = "WIDTH", value_parser = parse_abbrev)]
+    pub abbrev: Option<String>,
+
+    /// Do not process svn:externals definitions.
+    ///
+    /// Honored by the commands that update, switch, export or check the
+    /// status of the working copy: bisect, stash, export and status.
+    /// May also be enabled by setting the `svn.ignore-externals`
+    /// configuration value to true.
+    #[arg(long, global = true)]
+    pub ignore_externals: bool,
+
+    /// Display each author in a color chosen from their name.
+    ///
+    /// A given author is always displayed in the same color which makes it
+    /// easier to follow the work of individual contributors.
+    #[arg(long, global = true)]
+    pub color_authors: bool,
+
+    /// Never prompt for credentials.
+    ///
+    /// Fail instead when the repository requires credentials that are not
+    /// available from the SVU_USERNAME and SVU_PASSWORD environment
+    /// variables or the svn credential cache.
+    #[arg(long, global = true)]
+    pub non_interactive: bool,
+
+    /// Append each svn command that is run to FILE as a shell script.
+    ///
+    /// The script can be run to reproduce the operation.  Credentials are
+    /// replaced by references to the SVU_USERNAME and SVU_PASSWORD
+    /// environment variables.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Display all dates as RFC3339 timestamps in UTC.
+    ///
+    /// This gives consistent, sortable dates from every command which is
+    /// useful when the output is processed by scripts.
+    #[arg(long, global = true)]
+    pub machine_date: bool,
+
+    /// Do not use color in the output.
+    ///
+    /// Color is also disabled when the NO_COLOR environment variable is set
+    /// or when the output is not a terminal, unless CLICOLOR_FORCE is set.
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Pass --config-dir DIR to every svn command.
+    ///
+    /// svn then reads its configuration and caches credentials in DIR rather
+    /// than in ~/.subversion.  May also be set with the SVU_SVN_CONFIG_DIR
+    /// environment variable.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub svn_config_dir: Option<PathBuf>,
+
+    /// Run at most N svn commands at once.
+    ///
+    /// Limits the number of simultaneous connections made to the server by the
+    /// commands that work in parallel.  Defaults to the `svn.jobs` configuration
+    /// value or the number of CPUs, up to 16.
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..=MAX_JOBS as i64),
+    )]
+    pub jobs: Option<u32>,
+}
+
+//  The upper limit for the --jobs option.
+pub const MAX_JOBS: u32 = 64;
+
+//  The default number of jobs is capped so that a machine with
+//  many CPUs does not overwhelm the server.
+const DEFAULT_MAX_JOBS: usize = 16;
+
+fn parse_abbrev(arg: &str) -> std::result::Result<String, String> {
+    if arg == "head" || arg.parse::<usize>().is_ok() {
+        Ok(arg.to_string())
+    } else {
+        Err("must be a number or 'head'".to_string())
+    }
+}
+
+static GLOBAL_OPTIONS: OnceLock<GlobalOptions> = OnceLock::new();
+
+//  Returns the global options from the command line.
+//  The defaults are used if the command line has not been parsed.
+//  svn may be run while the command line is being parsed (to find the
+//  bisect terms) so the defaults must not be stored in GLOBAL_OPTIONS.
+pub fn global_options() -> &'static GlobalOptions {
+    static DEFAULT_OPTIONS: OnceLock<GlobalOptions> = OnceLock::new();
+    GLOBAL_OPTIONS
+        .get()
+        .unwrap_or_else(|| DEFAULT_OPTIONS.get_or_init(GlobalOptions::default))
+}
+
+#[derive(Debug, Subcommand)]
 pub enum Commands {
     Log(log::Log),
     Branch(branch::Branch),
@@ -42,11 +173,85 @@ pub enum Commands {
     Bisect(bisect::Bisect),
     Prefix(prefix::Prefix),
     Ignore(ignore::Ignore),
+    Config(config::Config),
+    Doctor(doctor::Doctor),
+    Export(export::Export),
+    Tail(tail::Tail),
+    Size(size::Size),
+    RevList(rev_list::RevList),
+    Status(status::Status),
+    Compare(compare::Compare),
+    Blame(blame::Blame),
+    Grep(grep::Grep),
+    Migrate(migrate::Migrate),
+    Shortlog(shortlog::Shortlog),
+    Search(search::Search),
     Completions(completions::Completions),
+    Version(version::Version),
 }
 
 use Commands::*;
 
+impl Run for App {
+    fn run(&mut self) -> Result<()> {
+        let mut options = self.options.clone();
+        options.ignore_externals = options.ignore_externals
+            || crate::config::peek_value("svn.ignore-externals").as_deref() == Some("true");
+        let _ = GLOBAL_OPTIONS.set(options);
+
+        if !use_color(self.options.no_color) {
+            colored::control::set_override(false);
+        }
+
+        //  All parallel operations share the global thread pool so its
+        //  size bounds the number of svn commands running at once.
+        let jobs = match self.options.jobs {
+            Some(jobs) => jobs as usize,
+            None => crate::config::peek_value("svn.jobs")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_else(|| {
+                    std::thread::available_parallelism()
+                        .map(|n| n.get())
+                        .unwrap_or(1)
+                        .min(DEFAULT_MAX_JOBS)
+                }),
+        };
+        let _ = rayon::ThreadPoolBuilder::new().num_threads(jobs).build_global();
+
+        //  The time zone must be installed before any dates are parsed.
+        //  An invalid configured zone only warrants a warning so that the
+        //  config command can still be used to correct it.
+        if let Some(zone) = &self.options.timezone {
+            util::set_display_timezone(zone)?;
+        } else if let Some(zone) = crate::config::peek_value("display.timezone") {
+            if let Err(e) = util::set_display_timezone(&zone) {
+                eprintln!("{} display.timezone: {}", "warning:".yellow(), e);
+            }
+        }
+        if let Some(abbrev) = &self.options.abbrev {
+            let padding = match abbrev.parse::<usize>() {
+                Ok(width) => util::RevPadding::Spaces(width),
+                Err(_) => util::RevPadding::Head,
+            };
+            util::set_revision_padding(padding);
+        }
+
+        self.command.run()
+    }
+}
+
+//  Color is used only when writing to a terminal unless it has
+//  been forced on or off through the environment.
+fn use_color(no_color: bool) -> bool {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let force_color = std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0");
+    if no_color || no_color_env {
+        false
+    } else {
+        force_color || std::io::stdout().is_terminal()
+    }
+}
+
 impl Run for Commands{
     fn run(&mut self) -> Result<()> {
         match self {
@@ -58,7 +263,21 @@ impl Run for Commands{
             Bisect(cmd) => cmd.run(),
             Prefix(cmd) => cmd.run(),
             Ignore(cmd) => cmd.run(),
+            Config(cmd) => cmd.run(),
+            Doctor(cmd) => cmd.run(),
+            Export(cmd) => cmd.run(),
+            Tail(cmd) => cmd.run(),
+            Size(cmd) => cmd.run(),
+            RevList(cmd) => cmd.run(),
+            Status(cmd) => cmd.run(),
+            Compare(cmd) => cmd.run(),
+            Blame(cmd) => cmd.run(),
+            Grep(cmd) => cmd.run(),
+            Migrate(cmd) => cmd.run(),
+            Shortlog(cmd) => cmd.run(),
+            Search(cmd) => cmd.run(),
             Completions(cmd) => cmd.run(),
+            Version(cmd) => cmd.run(),
         }
     }
 }
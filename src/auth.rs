@@ -4,6 +4,8 @@ use std::env;
 use std::io::Write;
 use crate::util::SvError::*;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use crate::svn;
 
 #[derive(Debug, Clone)]
@@ -20,9 +22,49 @@ pub struct Credentials(pub String, pub String);   // username and password
 //  the user has cached subversion credentials.
 //
 //  Finally, if authentication is needed, we prompt the user for their credentials.
+//  When the global --non-interactive option is given we fail instead.
 
+//  Set once the initial credentials have been established.
+static ACQUIRED: AtomicBool = AtomicBool::new(false);
+//  Only one attempt is made to re-acquire credentials.
+static REAUTH_ATTEMPTED: AtomicBool = AtomicBool::new(false);
+//  The credentials that replace the originals after re-authentication.
+static RENEWED: Mutex<Option<Option<Credentials>>> = Mutex::new(None);
 
 pub fn get_credentials() -> Result<Option<Credentials>> {
+    let creds = acquire_credentials()?;
+    ACQUIRED.store(true, Ordering::SeqCst);
+    Ok(creds)
+}
+
+//  Called when svn rejects our credentials part way through an operation.
+//  Returns true if new credentials were acquired and the command should be retried.
+pub fn reacquire_credentials() -> Result<bool> {
+    if !ACQUIRED.load(Ordering::SeqCst) || REAUTH_ATTEMPTED.swap(true, Ordering::SeqCst) {
+        return Ok(false);
+    }
+    eprintln!("The subversion repository rejected the credentials, re-authenticating.");
+    let creds = acquire_credentials()?;
+    *RENEWED.lock().unwrap() = Some(creds);
+    Ok(true)
+}
+
+//  Returns the credentials acquired by reacquire_credentials() if any.
+pub fn renewed_credentials() -> Option<Option<Credentials>> {
+    RENEWED.lock().unwrap().clone()
+}
+
+
+//  Return the credentials from the SVU_USERNAME and SVU_PASSWORD environment
+//  variables.  Used by commands that can run outside of a working copy.
+pub fn env_credentials() -> Option<Credentials> {
+    match (env::var("SVU_USERNAME"), env::var("SVU_PASSWORD")) {
+        (Ok(u), Ok(p)) => Some(Credentials(u, p)),
+        _ => None,
+    }
+}
+
+fn acquire_credentials() -> Result<Option<Credentials>> {
     let wc_info = svn::workingcopy_info()?;  // Ensure we are in working copy directory
     let wc_root = PathBuf::from(wc_info.wc_path.unwrap());
 
@@ -48,6 +90,11 @@ pub fn get_credentials() -> Result<Option<Credentials>> {
             //  First attempt to access the repo without credentials
             if access_repo(None,&wc_root)? {
                 Ok(None)  // No credentials needed
+            } else if crate::app::global_options().non_interactive {
+                Err(General(
+                    "Authentication is required but --non-interactive was given.  \
+                     Set the SVU_USERNAME and SVU_PASSWORD environment variables.".to_string()
+                ).into())
             } else {
                 //  Prompt for username and password.
                 let mut username: Option<String> = None;
@@ -76,24 +123,65 @@ pub fn get_credentials() -> Result<Option<Credentials>> {
     }
 }
 
+//  Determine the svn username of the current user.
+//  We try the following in order:
+//  The credentials that were used to access the repository.
+//  A username embedded in the repository url (eg. svn+ssh://joe@host/repo)
+//  The svn credential cache entry whose realm matches the repository host.
+pub fn current_username(credentials: &Option<Credentials>) -> Result<String> {
+    if let Some(Credentials(username, _)) = credentials {
+        return Ok(username.clone());
+    }
+
+    let root_url = svn::workingcopy_info()?.root_url;
+    let authority = root_url
+        .split_once("://")
+        .map(|(_, rest)| rest.split('/').next().unwrap_or(""))
+        .unwrap_or("");
+
+    if let Some((user, _)) = authority.split_once('@') {
+        return Ok(user.to_string());
+    }
+
+    let host = authority.split(':').next().unwrap_or("");
+    let realm_matches = |realm: &str| -> bool {
+        realm
+            .strip_prefix('<')
+            .and_then(|r| r.split_once('>'))
+            .and_then(|(r, _)| r.split_once("://"))
+            .map(|(_, h)| h.split(':').next() == Some(host))
+            .unwrap_or(false)
+    };
+
+    if !host.is_empty() {
+        if let Some((_, user)) = svn::cached_usernames()?.into_iter().find(|(realm, _)| realm_matches(realm)) {
+            return Ok(user);
+        }
+    }
+
+    Err(General(
+        "Cannot determine your svn username.  Set the SVU_USERNAME and SVU_PASSWORD \
+         environment variables or access the repository once with svn so that your \
+         credentials are cached.".to_string()
+    ).into())
+}
+
 //  Check to see if we can access the repository by
 //  running svn info ^/
 fn access_repo(credentials: Option<Credentials>, wc_root: &Path) -> Result<bool> {
     let output = svn::SvnCmd::new("info")
         .with_creds(&credentials)
+        .with_timeout()
         .with_cwd(Some(wc_root))
         .arg("^/")
         .run()?;
 
     if output.status.success() {
         Ok(true)
+    } else if svn::is_auth_failure(&output) {
+        Ok(false)
     } else {
-        let text = String::from_utf8_lossy(&output.stderr);
-        if text.contains("Authentication failed") {
-            Ok(false)
-        } else {
-            Err(SvnError(output).into())
-        }
+        Err(SvnError(output).into())
     }
 }
 
@@ -8,4 +8,18 @@ pub(crate) mod stash;
 pub(crate) mod bisect;
 pub(crate) mod prefix;
 pub(crate) mod ignore;
+pub(crate) mod config;
+pub(crate) mod doctor;
+pub(crate) mod export;
+pub(crate) mod tail;
+pub(crate) mod size;
+pub(crate) mod rev_list;
+pub(crate) mod status;
+pub(crate) mod compare;
+pub(crate) mod blame;
+pub(crate) mod grep;
+pub(crate) mod migrate;
+pub(crate) mod shortlog;
+pub(crate) mod search;
 pub(crate) mod completions;
+pub(crate) mod version;
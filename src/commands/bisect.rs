@@ -14,7 +14,9 @@ use serde::{Deserialize, Serialize};
 use regex::Regex;
 use std::env::current_dir;
 use std::collections::HashSet;
+use std::sync::Mutex;
 use std::fmt::Display;
+use crate::hooks::{self, HookContext};
 
 mod start;
 mod good;
@@ -105,7 +107,7 @@ fn parse_term(arg: &str) -> Result<String> {
 // Common structures and functions used by all of the bisect commands.
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct BisectData {
+pub(crate) struct BisectData {
     #[serde(rename(serialize = "originalRev", deserialize = "originalRev"))]
     original_rev: String,
     #[serde(rename(serialize = "headRev", deserialize = "headRev"))]
@@ -121,6 +123,10 @@ struct BisectData {
     term_good: Option<String>,
     #[serde(rename(serialize = "termBad", deserialize = "termBad"))]
     term_bad: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default)]
+    strict: bool,
 }
 
 impl BisectData {
@@ -144,16 +150,17 @@ impl BisectData {
 }
 
 
-fn bisect_data_file() -> Result<PathBuf> {
+pub(crate) fn bisect_data_file() -> Result<PathBuf> {
     Ok(util::data_directory()?.join("bisect_data.json"))
 }
 
 
-fn bisect_log_file() -> Result<PathBuf> {
+pub(crate) fn bisect_log_file() -> Result<PathBuf> {
     Ok(util::data_directory()?.join("bisect_log"))
 }
 
 fn load_bisect_data() -> Result<Option<BisectData>> {
+    let _lock = util::lock_data()?;
     let path = bisect_data_file()?;
     if path.is_file() {
         let reader = File::open(path)?;
@@ -165,6 +172,7 @@ fn load_bisect_data() -> Result<Option<BisectData>> {
 }
 
 fn save_bisect_data(data: &BisectData) -> Result<()> {
+    let _lock = util::lock_data()?;
     let writer = File::create(bisect_data_file()?)?;
     Ok(serde_json::to_writer_pretty(writer, data)?)
 }
@@ -182,6 +190,7 @@ fn append_to_log<S>(msg: S) -> Result<()>
 where
     S: AsRef<str> + Display
 {
+    let _lock = util::lock_data()?;
     let mut writer = OpenOptions::new()
         .append(true)
         .create(true)
@@ -190,7 +199,23 @@ where
     Ok(())
 }
 
+//  The shell lines at the top of the log allow it to be run as a
+//  script.  They are skipped when the log is replayed.
+const LOG_PREAMBLE: [&str; 2] = ["#! /usr/bin/env sh", "set -e"];
+
+//  Return the lines written at the start of a new log.
+fn log_header(cmd_name: &str, started: &str, initiated_from: &str) -> Vec<String> {
+    vec![
+        format!("{}\n", LOG_PREAMBLE[0]),
+        format!("# {} bisect log file {}", cmd_name, started),
+        format!("# Initiated from: {}", initiated_from),
+        format!("# {}", util::divider(72)),
+        format!("{}\n", LOG_PREAMBLE[1]),
+    ]
+}
+
 fn display_log() -> Result<()> {
+    let _lock = util::lock_data()?;
     let path = bisect_log_file()?;
     if path.is_file() {
         let reader = BufReader::new(File::open(path)?);
@@ -229,6 +254,18 @@ fn log_bisect_revision(revision: &str, term: &str) -> Result<()> {
     append_to_log(line)
 }
 
+//  While a log is being replayed the replayed command line is
+//  logged in place of the actual command line.
+static REPLAY_COMMAND_LINE: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+fn command_line() -> Vec<String> {
+    REPLAY_COMMAND_LINE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| std::env::args().collect())
+}
+
 fn log_bisect_command(cmd_line: &[String]) -> Result<()> {
     let line = cmd_line.join(" ").to_string();
     append_to_log(line)
@@ -256,21 +293,74 @@ fn get_workingcopy_bounds() -> Result<(String, String)> {
     Ok((first.clone(), last.clone()))
 }
 
-fn get_extant_revisions(rev1: &str, rev2: &str) -> Result<Vec<String>> {
+//  When a path is given only the revisions that modified that path are returned.
+//  The path is stored as a URL so we use the upper revision as the peg revision
+//  since the path may not exist in the working copy at every revision we visit.
+fn get_extant_revisions(rev1: &str, rev2: &str, path: Option<&str>) -> Result<Vec<String>> {
     let mut revisions = Vec::new();
     let range = format!("{}:{}", rev1, rev2);
-    println!(
-        "Fetching history from revisions {} to {}",
-        rev1.yellow(),
-        rev2.yellow()
-    );
-    let logs = svn::log(&None, &[], &[range], false, None, false, false)?;
+    let paths: Vec<String> = match path {
+        Some(url) => {
+            println!(
+                "Fetching history of {} from revisions {} to {}",
+                url.green(),
+                rev1.yellow(),
+                rev2.yellow()
+            );
+            vec![format!("{}@{}", url, rev1)]
+        }
+        None => {
+            println!(
+                "Fetching history from revisions {} to {}",
+                rev1.yellow(),
+                rev2.yellow()
+            );
+            vec![]
+        }
+    };
+    let logs = svn::log(&None, &paths, &[range], false, None, false, false)?;
     for log in &logs {
         revisions.push(log.revision.clone());
     }
     Ok(revisions)
 }
 
+//  In strict mode every revision given to start, good, bad or skip must
+//  be one that modified the bisect target: the working copy or the --path.
+//  All of the invalid revisions are reported before anything is changed.
+fn check_strict_revisions(data: &BisectData, revisions: &[String]) -> Result<()> {
+    if !data.strict || revisions.is_empty() {
+        return Ok(());
+    }
+
+    let max = revisions.iter().map(|r| to_rev_num(r)).max().unwrap();
+    let min = revisions.iter().map(|r| to_rev_num(r)).min().unwrap();
+    let target = match &data.path {
+        Some(url) => format!("{}@{}", url, max),
+        None => svn::workingcopy_info()?.wc_path.unwrap_or(".".to_string()),
+    };
+    let range = format!("{}:{}", max, min);
+    let extant: HashSet<String> = svn::log(&None, &[target.as_str()], &[range.as_str()], false, None, false, false)?
+        .into_iter()
+        .map(|e| e.revision)
+        .collect();
+
+    let mut invalid: Vec<&String> = revisions.iter().filter(|r| !extant.contains(*r)).collect();
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        invalid.sort_by_key(|r| to_rev_num(r));
+        invalid.dedup();
+        let revs: Vec<&str> = invalid.iter().map(|r| r.as_str()).collect();
+        let msg = format!(
+            "The following revisions did not modify {}: {}",
+            data.path.as_deref().unwrap_or("the working copy"),
+            revs.join(", ")
+        );
+        Err(General(msg).into())
+    }
+}
+
 fn get_waiting_status(data: &BisectData) -> Option<String> {
     let good = data.good_name();
     let bad  = data.bad_name();
@@ -288,15 +378,18 @@ fn get_log_entry(revision: &str, with_paths: bool) -> Result<Option<LogEntry>> {
     Ok(log.first().cloned())
 }
 
-fn perform_bisect(data: &BisectData) -> Result<bool> {
-    if !data.is_ready() {
-        return Err(General("fatal: peform_bisect() called when data not ready".to_string()).into());
-    }
-
+//  Return the revisions between the good and bad revisions
+//  along with those that have not been skipped.
+fn candidate_revisions(data: &BisectData) -> Result<(Vec<String>, Vec<String>)> {
     let max_rev = data.max_rev.as_ref().unwrap();
     let min_rev = data.min_rev.as_ref().unwrap();
-    let extant_revs = get_extant_revisions(max_rev, min_rev)?;
-    let candidate_revs = &extant_revs[1..extant_revs.len() - 1];
+    let (max_num, min_num) = (to_rev_num(max_rev), to_rev_num(min_rev));
+    //  The bounds themselves are not candidates and when bisecting a path
+    //  they may not appear in its history at all.
+    let candidate_revs: Vec<String> = get_extant_revisions(max_rev, min_rev, data.path.as_deref())?
+        .into_iter()
+        .filter(|r| to_rev_num(r) < max_num && to_rev_num(r) > min_num)
+        .collect();
     let non_skipped_revs: Vec<String> = candidate_revs
         .iter()
         .filter_map(|r| {
@@ -307,13 +400,23 @@ fn perform_bisect(data: &BisectData) -> Result<bool> {
             }
         })
         .collect();
+    Ok((candidate_revs, non_skipped_revs))
+}
+
+fn perform_bisect(data: &BisectData) -> Result<bool> {
+    if !data.is_ready() {
+        return Err(General("fatal: peform_bisect() called when data not ready".to_string()).into());
+    }
+
+    let max_rev = data.max_rev.as_ref().unwrap();
+    let (candidate_revs, non_skipped_revs) = candidate_revisions(data)?;
 
     if non_skipped_revs.is_empty() {
         if !candidate_revs.is_empty() {
             println!("\nThere are only skipped revisions left to test.");
             println!("The first {} commit could be any of:", data.bad_name());
             println!("{} {}", max_rev.yellow(), get_1st_log_message(max_rev)?);
-            for rev in candidate_revs {
+            for rev in &candidate_revs {
                 println!("{} {}", rev.yellow(), get_1st_log_message(rev)?);
             }
             println!("We cannot bisect more!");
@@ -321,7 +424,7 @@ fn perform_bisect(data: &BisectData) -> Result<bool> {
         } else {
             println!("\nThe first '{}' revision is: {}", data.bad_name(), max_rev.yellow());
             if let Some(log_entry) = get_log_entry(max_rev, true)? {
-                show_commit(&log_entry, true, true);
+                show_commit(&log_entry, &util::HeaderFields::default(), util::MessageStyle::Full, util::PathStyle::Full, None, false);
             }
             Ok(true)
         }
@@ -335,18 +438,90 @@ fn perform_bisect(data: &BisectData) -> Result<bool> {
         let next_rev = &non_skipped_revs[non_skipped_revs.len() / 2];
 
         println!("Bisecting: {} revisions left to test after this (roughly {}) ", num, steps);
-        update_workingcopy(next_rev)?;
-        Ok(false)
+        checkout_revision(next_rev)
+    }
+}
+
+//  How to proceed when the working copy cannot be updated.
+enum UpdateRecovery {
+    Retry,
+    Skip,
+    Abort,
+}
+
+//  Abort when there is nobody to ask.
+fn prompt_update_recovery(revision: &str) -> Result<UpdateRecovery> {
+    use std::io::IsTerminal;
+    if crate::app::global_options().non_interactive || !std::io::stdin().is_terminal() {
+        return Ok(UpdateRecovery::Abort);
+    }
+    loop {
+        print!("Update to revision {} failed: [r]etry, [s]kip this revision or [a]bort? ", revision);
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line)? == 0 {
+            return Ok(UpdateRecovery::Abort);
+        }
+        match line.trim().to_lowercase().as_str() {
+            "r" | "retry" => return Ok(UpdateRecovery::Retry),
+            "s" | "skip"  => return Ok(UpdateRecovery::Skip),
+            "a" | "abort" => return Ok(UpdateRecovery::Abort),
+            _ => (),
+        }
     }
 }
 
-fn update_workingcopy(revision: &String) -> Result<()> {
+//  Update the working copy to the next revision to be tested.
+//  If the update fails the working copy is returned to its previous
+//  revision and the user may retry, skip the revision or abort.
+//  Returns true if skipping the revision completed the bisect.
+fn checkout_revision(revision: &str) -> Result<bool> {
+    let wc_info = svn::workingcopy_info()?;
+    let wc_root = PathBuf::from(wc_info.wc_path.clone().unwrap());
+    loop {
+        let err = match update_workingcopy(revision) {
+            Ok(()) => return Ok(false),
+            Err(e) => e,
+        };
+        eprintln!("{} {}", "error:".red(), err.to_string().trim());
+        println!("Restoring working copy to revision {}", wc_info.repo_rev.yellow());
+        svn::cleanup(Some(&wc_root))?;
+        svn::update(&wc_info.repo_rev, "infinity", Some(&wc_root))?;
+
+        match prompt_update_recovery(revision)? {
+            UpdateRecovery::Retry => append_to_log(format!("# update to {} failed, retrying", revision))?,
+            UpdateRecovery::Skip => {
+                append_to_log(format!("# update to {} failed, skipping it", revision))?;
+                let complete = mark_skipped_revisions(&HashSet::from([revision.to_string()]))?;
+                let program = std::env::args().next().unwrap_or("svu".to_string());
+                log_bisect_command(&[program, "bisect".to_string(), "skip".to_string(), revision.to_string()])?;
+                return Ok(complete);
+            }
+            UpdateRecovery::Abort => {
+                append_to_log(format!("# update to {} failed, bisect stopped", revision))?;
+                let msg = format!(
+                    "Unable to update the working copy to revision {}.  The bisect session is still active.",
+                    revision
+                );
+                return Err(General(msg).into());
+            }
+        }
+    }
+}
+
+fn update_workingcopy(revision: &str) -> Result<()> {
     let msg = get_1st_log_message(revision)?;
     let wc_info = svn::workingcopy_info()?;
     let wc_root = PathBuf::from(wc_info.wc_path.unwrap());
+    let context = HookContext {
+        revision: Some(revision.to_string()),
+        ..HookContext::default()
+    };
     println!("Updating working copy: [{}] {}", revision.yellow(), msg);
-    svn::update(revision, "infinity", Some(&wc_root))?;
-    Ok(())
+    hooks::with_hooks("update", &context, || {
+        svn::update(revision, "infinity", Some(&wc_root))?;
+        Ok(())
+    })
 }
 
 //  Returns true if perform_bisect() reports that that session
@@ -448,17 +623,41 @@ fn mark_unskipped_revisions(incoming_unskipped: &HashSet<String>) -> Result<bool
 //  These value should be resovlved to be well formed (see: resolved_revision_range())
 //  This function gathers the actual revision numbers that lie within each range
 //  for the given path.
+//  An open ended range extends to HEAD (REV:) or from the lower
+//  bound of the session (:REV) and only includes the revisions
+//  that are within the bounds of the session.
 fn gather_revisions(
     creds: &Option<Credentials>,
+    data: &BisectData,
     rev_str: &str,
      path: &str,
 ) -> Result<HashSet<String>> {
     let mut revisions = HashSet::new();
 
-    if rev_str.contains(':') {
-        let resolved = svn::resolve_revision_range(creds, rev_str, path)?;
+    if let Some((start, end)) = rev_str.split_once(':') {
+        let open_ended = start.is_empty() || end.is_empty();
+        let resolved = if open_ended {
+            let lower = data.min_rev.as_ref().or(data.first_rev.as_ref()).map(|r| r.as_str()).unwrap_or("0");
+            let start = svn::resolve_revision(creds, if start.is_empty() { lower } else { start }, path)?;
+            let end = svn::resolve_revision(creds, if end.is_empty() { "HEAD" } else { end }, path)?;
+            let head = svn::resolve_revision(creds, "HEAD", path)?;
+            for rev in [&start, &end] {
+                if to_rev_num(rev) > to_rev_num(&head) {
+                    let msg = format!("Revision {} in '{}' is beyond the HEAD revision ({})", rev, rev_str, head);
+                    return Err(General(msg).into());
+                }
+            }
+            format!("{}:{}", start, end)
+        } else {
+            svn::resolve_revision_range(creds, rev_str, path)?
+        };
         let entries = svn::log(&None, &[path], &[&resolved], false, None, false, false)?;
-        revisions.extend(entries.iter().map(|e| e.revision.clone()));
+        let within_bounds = |rev: &str| -> bool {
+            !open_ended
+                || (data.min_rev.as_ref().is_none_or(|min| to_rev_num(rev) >= to_rev_num(min))
+                    && data.max_rev.as_ref().is_none_or(|max| to_rev_num(rev) <= to_rev_num(max)))
+        };
+        revisions.extend(entries.iter().map(|e| e.revision.clone()).filter(|r| within_bounds(r)));
     } else {
         revisions.insert(svn::resolve_revision(creds, rev_str, path)?);
     }
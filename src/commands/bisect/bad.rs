@@ -48,6 +48,7 @@ impl Bad {
             Some(rev) => svn::resolve_revision(&creds, rev, wc_root.to_string_lossy().as_ref())?,
             None      => wc_info.commit_rev,
         };
+        check_strict_revisions(&data, std::slice::from_ref(&revision))?;
     
         // The new bad revision can come after the existing maxRev
         // This allows the user to recheck a range of commits.
@@ -60,8 +61,8 @@ impl Bad {
                 data.good_name()
             );
         } else {
-            let _ = mark_bad_revision(&revision);
-            log_bisect_command(&std::env::args().collect::<Vec<String>>())?;
+            mark_bad_revision(&revision)?;
+            log_bisect_command(&command_line())?;
         }
 
         let data = get_bisect_data()?; // Fresh copy of data
@@ -49,6 +49,7 @@ impl Good {
             Some(rev) => svn::resolve_revision(&creds, rev, wc_root.to_string_lossy().as_ref())?,
             None      => wc_info.commit_rev,
         };
+        check_strict_revisions(&data, std::slice::from_ref(&revision))?;
 
         // The new good revision can come before the exisiing minRev
         // This allow the user to recheck a range of commits.
@@ -61,8 +62,8 @@ impl Good {
                 data.bad_name()
             );
         } else {
-            let _ = mark_good_revision(&revision);
-            log_bisect_command(&std::env::args().collect::<Vec<String>>())?;
+            mark_good_revision(&revision)?;
+            log_bisect_command(&command_line())?;
         }
 
         let data = get_bisect_data()?; // Fresh copy of data
@@ -2,7 +2,6 @@
 use clap::Parser;
 use super::*;
 use anyhow::Result;
-use std::process;
 
 /// Replay a bisect session from a log file.
 ///
@@ -13,6 +12,10 @@ use std::process;
 #[command(
     author,
     help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    Each line of the log is a recorded `svu bisect` command.  Blank lines, lines\n\
+    beginning with '#' and the `set -e` shell preamble are ignored.  The commands are run by svu itself rather than\n\
+    by a shell so the log cannot run anything other than bisect commands."
 )]
 pub struct Replay {
     /// Path to log file.
@@ -22,20 +25,104 @@ pub struct Replay {
 
 impl Replay {
     pub fn run(&mut self) -> Result<()> {
-        let wc_info = svn::workingcopy_info()?;  // Make sure we are in a working copy.
-        let wc_root = PathBuf::from(wc_info.wc_path.unwrap());
-    
-        let cmd = process::Command::new("/bin/sh")
-            .current_dir(wc_root)
-            .args([self.log_fiie.clone()])
-            .stdout(process::Stdio::inherit())
-            .stderr(process::Stdio::inherit())
-            .output()?;
-    
-        if cmd.status.success() {
-            Ok(())
-        } else {
-            Err(General("Log replay did not finish successfully".to_string()).into())
+        svn::workingcopy_info()?;  // Make sure we are in a working copy.
+
+        let reader = BufReader::new(File::open(&self.log_fiie)?);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            let location = format!("{}:{}", self.log_fiie, index + 1);
+            let Some((words, args)) = parse_log_line(line, &location)? else {
+                continue;
+            };
+            let args = replay_args(args)?;
+            if let Some(name) = args.first() {
+                if name == "replay" || name == "run" {
+                    let msg = format!("{}: 'bisect {}' cannot be replayed", location, name);
+                    return Err(General(msg).into());
+                }
+            }
+
+            let mut bisect = Bisect::try_parse_from(std::iter::once("bisect".to_string()).chain(args))
+                .map_err(|e| General(format!("{}: {}", location, e.to_string().trim())))?;
+
+            println!("{}", line);
+            *REPLAY_COMMAND_LINE.lock().unwrap_or_else(|e| e.into_inner()) = Some(words);
+            let result = bisect.run();
+            *REPLAY_COMMAND_LINE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            result?;
+        }
+        Ok(())
+    }
+}
+
+//  Return the words of a recorded command line along with the arguments
+//  that follow `bisect`.  Returns None for blank lines, comments and the
+//  shell preamble.
+fn parse_log_line(line: &str, location: &str) -> Result<Option<(Vec<String>, Vec<String>)>> {
+    if line.is_empty() || line.starts_with('#') || LOG_PREAMBLE.contains(&line) {
+        return Ok(None);
+    }
+    let words: Vec<String> = line.split_whitespace().map(|w| w.to_string()).collect();
+
+    //  Skip the program name and any global options.
+    match words.iter().position(|w| w == "bisect") {
+        Some(pos) => {
+            let args = words[pos + 1..].to_vec();
+            Ok(Some((words, args)))
         }
+        None => Err(General(format!("{}: not a bisect command: {}", location, line)).into()),
+    }
+}
+
+//  Custom terms are only known to the command line parser if they were
+//  in effect when svu started, so translate them to `good` and `bad`
+//  using the terms of the session being replayed.
+fn replay_args(mut args: Vec<String>) -> Result<Vec<String>> {
+    if let (Some(name), Some(data)) = (args.first(), load_bisect_data()?) {
+        if data.term_good.as_ref() == Some(name) {
+            args[0] = "good".to_string();
+        } else if data.term_bad.as_ref() == Some(name) {
+            args[0] = "bad".to_string();
+        }
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  Parse each line of a log the way that `run` does.
+    fn parse_log(log: &str) -> Result<Vec<Vec<String>>> {
+        let mut commands = Vec::new();
+        for (index, line) in log.lines().enumerate() {
+            if let Some((_, args)) = parse_log_line(line.trim(), &format!("log:{}", index + 1))? {
+                commands.push(args);
+            }
+        }
+        Ok(commands)
+    }
+
+    #[test]
+    fn replays_log_written_by_start() {
+        let mut log = log_header("svu", "2024-03-01 12:00:00", "/home/user/wc").join("\n");
+        log.push_str("\n# bad: [12] Fix the widget\n");
+        log.push_str("# good: [7] Add the widget\n");
+        log.push_str("svu bisect start --bad 12 --good 7\n");
+        log.push_str("# good: [9] Refactor the widget\n");
+        log.push_str("svu --jobs 2 bisect good 9\n");
+
+        let commands = parse_log(&log).unwrap();
+        assert_eq!(commands, vec![
+            vec!["start", "--bad", "12", "--good", "7"],
+            vec!["good", "9"],
+        ]);
+    }
+
+    #[test]
+    fn rejects_lines_that_are_not_bisect_commands() {
+        let err = parse_log("set -e\nrm -rf /tmp/wc\n").unwrap_err();
+        assert_eq!(err.to_string(), "log:2: not a bisect command: rm -rf /tmp/wc");
     }
 }
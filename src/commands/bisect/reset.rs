@@ -45,11 +45,14 @@ impl Reset {
                 println!("Working copy: [{}] {}", revision.yellow(), msg);
             }
     
+            let _lock = util::lock_data()?;
             remove_file(bisect_data_file()?)?;
             let path = bisect_log_file()?;
             if path.is_file() {
                 remove_file(path)?;
             }
+        } else {
+            eprintln!("{} there is no bisect session to reset", "warning:".yellow());
         }
         Ok(())
     }
@@ -26,6 +26,14 @@ pub struct Run {
     #[arg(value_name = "CMD", num_args = 1..=1, required = true)]
     cmd: String,
 
+    /// Stop after running CMD this many times
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_steps: Option<u32>,
+
+    /// Stop once no more than K revisions are left to test
+    #[arg(long, value_name = "K")]
+    stop_at_window: Option<usize>,
+
     /// Command line arguments passed to CMD
     #[arg(value_name = "ARG")]
     args: Vec<String>,
@@ -46,8 +54,20 @@ impl Run {
                 data.good_name(), data.bad_name());
             Err(General(msg).into())
         } else {
-            
+
+            let mut steps = 0;
             loop {
+                if let Some(reason) = self.stop_reason(steps)? {
+                    let msg = format!("bisect run stopped: {}", reason);
+                    println!("\n{}", msg.yellow());
+                    println!("The bisect session remains active and may be continued manually.");
+                    append_to_log(format!("# {}", msg))?;
+                    break;
+                }
+                steps += 1;
+
+                //  Each step updates the working copy so its revision must be refreshed.
+                let wc_info = svn::workingcopy_info()?;
                 let data    = get_bisect_data()?;
                 let cmd     = process::Command::new(self.cmd.as_str())
                     .current_dir(&wc_root)
@@ -55,7 +75,9 @@ impl Run {
                     .stdout(process::Stdio::inherit())
                     .stderr(process::Stdio::inherit())
                     .output()?;
-        
+                //  The command may have changed the working copy.
+                svn::clear_info_cache();
+
                 let exit_code = match cmd.status.code() {
                     Some(code) => code,
                     None => {
@@ -102,6 +124,23 @@ impl Run {
             Ok(())
         }
     }
+
+    //  Returns the reason for stopping before running CMD again, if any.
+    fn stop_reason(&self, steps: u32) -> Result<Option<String>> {
+        if let Some(max_steps) = self.max_steps {
+            if steps >= max_steps {
+                return Ok(Some(format!("reached the maximum of {} steps", max_steps)));
+            }
+        }
+        if let Some(window) = self.stop_at_window {
+            let data = get_bisect_data()?;
+            let (_, remaining) = candidate_revisions(&data)?;
+            if remaining.len() <= window {
+                return Ok(Some(format!("{} revisions left to test (window of {})", remaining.len(), window)));
+            }
+        }
+        Ok(None)
+    }
 }
 
 
@@ -12,12 +12,22 @@ use std::collections::HashSet;
 #[command(
     author,
     help_template = crate::app::HELP_TEMPLATE,
-    after_help = "If no revision is specified, the current working copy revision is skipped."
+    after_help = "\
+    If no revision is specified, the current working copy revision is skipped.\n\
+    A range may be open ended: REV: extends to HEAD and :REV starts from the\n\
+    lower bound of the session.  Only revisions within the session bounds are included.\n\
+    A revisions file contains one revision or range per line.\n\
+    Blank lines and text following a '#' are ignored.\n\
+    Lines that cannot be parsed are reported and the remaining lines are still skipped."
 )]
 pub struct Skip {
     /// Revision or range of revisions to skip.
     #[arg(value_name = "REV|REV:REV")]
     revisions: Vec<String>,
+
+    /// Read revisions or ranges of revisions to skip from FILE.
+    #[arg(long, value_name = "FILE")]
+    revs_file: Option<PathBuf>,
 }
 
 impl Skip {
@@ -26,19 +36,62 @@ impl Skip {
         let wc_info = svn::workingcopy_info()?;  // Make sure we are in a working copy.
         let wc_root = PathBuf::from(wc_info.wc_path.unwrap());
         let wc_root_str = wc_root.to_string_lossy();
-        let _ = get_bisect_data()?;  // Ensure a bisect session has started
+        let data = get_bisect_data()?;  // Ensure a bisect session has started
 
         let mut skipped = HashSet::<String>::new();
         for rev in &self.revisions {
-            skipped.extend(gather_revisions(&creds, rev, &wc_root_str)?);
+            skipped.extend(gather_revisions(&creds, &data, rev, &wc_root_str)?);
+        }
+
+        let mut file_revs = Vec::new();
+        if let Some(path) = &self.revs_file {
+            let reader = BufReader::new(File::open(path)?);
+            for (index, line) in reader.lines().enumerate() {
+                let line = line?;
+                let rev = line.split('#').next().unwrap_or("").trim();
+                if rev.is_empty() {
+                    continue;
+                }
+                match gather_revisions(&creds, &data, rev, &wc_root_str) {
+                    Ok(revs) => {
+                        skipped.extend(revs);
+                        file_revs.push(rev.to_string());
+                    }
+                    Err(e) => eprintln!(
+                        "{} {}:{}: '{}': {}",
+                        "warning:".yellow(),
+                        path.display(),
+                        index + 1,
+                        rev,
+                        e.to_string().trim()
+                    ),
+                }
+            }
+            if skipped.is_empty() {
+                let msg = format!("No valid revisions found in {}", path.display());
+                return Err(General(msg).into());
+            }
         }
+
         //  If not revisions specified, use the working copy rev
         if skipped.is_empty() {
             skipped.insert(wc_info.commit_rev.clone());
         }
+        check_strict_revisions(&data, &skipped.iter().cloned().collect::<Vec<String>>())?;
 
         mark_skipped_revisions(&skipped)?;
-        log_bisect_command(&std::env::args().collect::<Vec<String>>())?;
+
+        //  When a revisions file is used we log all of the revisions on a single
+        //  command line so that the log can be replayed without the file.
+        if self.revs_file.is_some() {
+            let program = std::env::args().next().unwrap_or("svu".to_string());
+            let mut cmd_line = vec![program, "bisect".to_string(), "skip".to_string()];
+            cmd_line.extend(self.revisions.iter().cloned());
+            cmd_line.extend(file_revs);
+            log_bisect_command(&cmd_line)?;
+        } else {
+            log_bisect_command(&command_line())?;
+        }
 
         let data = get_bisect_data()?; // Fresh copy of data
         if let Some(status) = get_waiting_status(&data) {
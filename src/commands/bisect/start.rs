@@ -29,12 +29,31 @@ pub struct Start {
     bad_rev: Option<String>,
 
     /// Specify an alternate name for the `svu bisect good` subcommand.
-    #[arg(long, value_name = "TERM", value_parser = parse_term)]
+    ///
+    /// --term-old is accepted as an alias for compatibility with git.
+    #[arg(long, visible_alias = "term-old", value_name = "TERM", value_parser = parse_term)]
     term_good: Option<String>,
 
     /// Specify an alternate name for the `svu bisect bad` subcommand.
-    #[arg(long, value_name = "TERM", value_parser = parse_term)]
+    ///
+    /// --term-new is accepted as an alias for compatibility with git.
+    #[arg(long, visible_alias = "term-new", value_name = "TERM", value_parser = parse_term)]
     term_bad: Option<String>,
+
+    /// Only consider revisions that modified this path.
+    ///
+    /// Revisions that did not touch the path are never tested which
+    /// can greatly reduce the number of steps needed.
+    #[arg(long, value_name = "PATH")]
+    path: Option<String>,
+
+    /// Reject revisions that did not modify the bisect target.
+    ///
+    /// For the rest of the session every revision given to the good, bad
+    /// and skip subcommands must have modified the working copy, or the
+    /// --path if one was given.
+    #[arg(long)]
+    strict: bool,
 }
 
 impl Start {
@@ -90,6 +109,12 @@ impl Start {
                     ).into());
                 }
 
+                let path = self
+                    .path
+                    .as_ref()
+                    .map(|p| svn::info(&creds, p, None).map(|info| info.url))
+                    .transpose()?;
+
                 let (head_rev, first_rev) = get_workingcopy_bounds()?;
                 let data = BisectData {
                     original_rev: wc_info.commit_rev.clone(),
@@ -100,27 +125,24 @@ impl Start {
                     skipped:      HashSet::new(),
                     term_good:    self.term_good.clone(),
                     term_bad:     self.term_bad.clone(),
+                    path,
+                    strict:       self.strict,
                 };
+                let given: Vec<String> = data.max_rev.iter().chain(data.min_rev.iter()).cloned().collect();
+                check_strict_revisions(&data, &given)?;
 
                 save_bisect_data(&data)?;
+                let _lock = util::lock_data()?;
                 if let Ok(log_file) = bisect_log_file() {
                     if log_file.exists() {
                         remove_file(log_file)?;  // Remove any existing log file
                     }
                 }
 
-                append_to_log("#! /usr/bin/env sh\n")?;
-                append_to_log(format!(
-                    "# {} bisect log file {}",
-                    cmd_name,
-                    display_svn_datetime(&Local::now())
-                ))?;
-                append_to_log(format!(
-                    "# Initiated from: {}",
-                    current_dir()?.to_string_lossy()
-                ))?;
-                append_to_log(format!("# {}", util::divider(72)))?;
-                append_to_log("set -e\n")?;
+                let started = display_svn_datetime(&Local::now());
+                for line in log_header(&cmd_name, &started, &current_dir()?.to_string_lossy()) {
+                    append_to_log(line)?;
+                }
                 if let Some(rev) = &data.max_rev {
                     log_bisect_revision(rev, data.bad_name())?;
                 }
@@ -135,7 +157,7 @@ impl Start {
                 if data.is_ready() {
                     perform_bisect(&data)?;
                 }
-                log_bisect_command(&std::env::args().collect::<Vec<String>>())?;
+                log_bisect_command(&command_line())?;
                 Ok(())
             }
         }
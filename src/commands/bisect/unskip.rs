@@ -23,11 +23,11 @@ impl Unskip {
         let wc_info = svn::workingcopy_info()?;  // Make sure we are in a working copy.
         let wc_root = PathBuf::from(wc_info.wc_path.unwrap());
         let wc_root_str = wc_root.to_string_lossy();
-        let _ = get_bisect_data()?;  // Ensure a bisect session has started
+        let data = get_bisect_data()?;  // Ensure a bisect session has started
 
         let mut skipped = HashSet::<String>::new();
         for rev in &self.revisions {
-            skipped.extend(gather_revisions(&creds, rev, &wc_root_str)?);
+            skipped.extend(gather_revisions(&creds, &data, rev, &wc_root_str)?);
         }
         //  If not revisions specified, use the working copy rev
         if skipped.is_empty() {
@@ -35,7 +35,7 @@ impl Unskip {
         }
 
         mark_unskipped_revisions(&skipped)?;
-        log_bisect_command(&std::env::args().collect::<Vec<String>>())?;
+        log_bisect_command(&command_line())?;
 
         let data = get_bisect_data()?; // Fresh copy of data
         if let Some(status) = get_waiting_status(&data) {
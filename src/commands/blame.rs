@@ -0,0 +1,229 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use crate::svn;
+use serde::Serialize;
+use crate::util::{self, SvError::*};
+
+/// Show the revision and author that last changed each line of a file.
+///
+/// Each revision is displayed in its own color so that the lines
+/// changed by a single commit are easy to pick out.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    A range is given as START,END or START,+COUNT where lines are numbered from 1.\n\
+    eg. -L 100,120 -L 300,+5"
+)]
+pub struct Blame {
+    /// The revision of the file to annotate.
+    ///
+    /// Defaults to BASE for a working copy file and HEAD for a URL.
+    #[arg(short, long, value_name = "REV")]
+    revision: Option<String>,
+
+    /// Only display the lines within RANGE.
+    ///
+    /// May be given more than once.
+    #[arg(short = 'L', long = "lines", value_name = "RANGE", value_parser = parse_line_range)]
+    ranges: Vec<LineRange>,
+
+    /// Write the lines as a JSON array for use by other tools.
+    ///
+    /// Each line is an object with line, revision, author, date and content fields.
+    /// The revision and date are null for lines that have been changed locally.
+    #[arg(long)]
+    json: bool,
+
+    /// Path to the file or its URL.
+    #[arg(value_name = "PATH")]
+    path: String,
+}
+
+#[derive(Serialize)]
+struct JsonBlameLine<'a> {
+    line: usize,
+    revision: Option<&'a str>,
+    author: &'a str,
+    date: Option<String>,
+    content: &'a str,
+}
+
+//  The end of the range is inclusive.
+#[derive(Debug, Clone, Copy)]
+struct LineRange {
+    start: usize,
+    end: usize,
+}
+
+fn parse_line_range(arg: &str) -> Result<LineRange> {
+    let invalid = || General(format!("Invalid line range '{}' (eg. 100,120 or 100,+20)", arg));
+    let (start, end) = arg.split_once(',').ok_or_else(invalid)?;
+    let start: usize = start.trim().parse().map_err(|_| invalid())?;
+    let end = end.trim();
+    let end = match end.strip_prefix('+') {
+        Some(count) => {
+            let count: usize = count.parse().map_err(|_| invalid())?;
+            (start + count).saturating_sub(1)
+        }
+        None => end.parse().map_err(|_| invalid())?,
+    };
+    Ok(LineRange { start, end })
+}
+
+impl Blame {
+    pub fn run(&mut self) -> Result<()> {
+        let creds = crate::auth::get_credentials()?;
+        let revision = match &self.revision {
+            Some(rev) => svn::resolve_revision(&creds, rev, &self.path)?,
+            None if self.path.contains("://") => "HEAD".to_string(),
+            None => "BASE".to_string(),
+        };
+
+        if svn::info(&creds, &self.path, Some(&revision))?.kind != "file" {
+            let msg = format!("{} is not a file", self.path);
+            return Err(General(msg).into());
+        }
+
+        let blame = svn::blame(&creds, &self.path, Some(&revision))?;
+        let content = svn::cat(&creds, &self.path, Some(&revision))?;
+        let content = String::from_utf8_lossy(&content);
+        let lines: Vec<&str> = content.lines().collect();
+        self.check_ranges(lines.len())?;
+
+        let included = |line_number: usize| -> bool {
+            self.ranges.is_empty() || self.ranges.iter().any(|r| r.start <= line_number && line_number <= r.end)
+        };
+
+        if self.json {
+            let json_lines: Vec<JsonBlameLine> = blame
+                .iter()
+                .zip(lines.iter())
+                .filter(|(b, _)| included(b.line_number))
+                .map(|(b, text)| JsonBlameLine {
+                    line: b.line_number,
+                    revision: b.revision.as_deref(),
+                    author: &b.author,
+                    date: (b.date != *util::null_date()).then(|| util::svn_date_to_rfc3339_string(&b.date)),
+                    content: text,
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&json_lines)?);
+            return Ok(());
+        }
+
+        let rev_width = blame.iter()
+            .filter_map(|b| b.revision.as_ref())
+            .map(|r| util::display_revision(r).len())
+            .max()
+            .unwrap_or(1);
+        let author_width = blame.iter().map(|b| b.author.len()).max().unwrap_or(0);
+        let number_width = lines.len().to_string().len();
+
+        let mut last_shown = None;
+        for (b, text) in blame.iter().zip(lines.iter()) {
+            if !included(b.line_number) {
+                continue;
+            }
+            //  Separate the ranges when more than one is displayed.
+            if last_shown.is_some_and(|n| n + 1 != b.line_number) {
+                println!("{}", "--".dimmed());
+            }
+            last_shown = Some(b.line_number);
+
+            let (rev, color) = match &b.revision {
+                Some(rev) => (util::display_revision(rev), util::palette_color(rev)),
+                None => ("-".to_string(), "white"),
+            };
+            println!(
+                "{:>rev_width$} {:author_width$} {} {:>number_width$} {}",
+                rev.color(color),
+                b.author.color(util::author_color(&b.author)),
+                util::display_svn_date(&b.date).magenta(),
+                b.line_number,
+                text,
+            );
+        }
+        Ok(())
+    }
+
+    //  All of the invalid ranges are reported together.
+    fn check_ranges(&self, line_count: usize) -> Result<()> {
+        let invalid: Vec<String> = self.ranges
+            .iter()
+            .filter(|r| r.start == 0 || r.end < r.start || r.end > line_count)
+            .map(|r| format!("{},{}", r.start, r.end))
+            .collect();
+
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            let msg = format!(
+                "Invalid line range(s) {} for {} which has {} lines",
+                invalid.join(" "),
+                self.path,
+                line_count
+            );
+            Err(General(msg).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blame_with_ranges(ranges: &[&str]) -> Result<Blame, clap::Error> {
+        let mut args = vec!["blame"];
+        for range in ranges {
+            args.extend(["-L", range]);
+        }
+        args.push("file.rs");
+        Blame::try_parse_from(args)
+    }
+
+    #[test]
+    fn ranges_within_the_file_are_accepted() {
+        let cases: &[(&[&str], usize, usize)] = &[
+            (&["1,10"], 1, 10),
+            (&["5,5"], 5, 5),
+            (&["3,+4"], 3, 6),
+            (&["10,+1"], 10, 10),
+            (&[" 2 , 8 "], 2, 8),
+            (&["1,+10"], 1, 10),
+        ];
+        for (ranges, start, end) in cases {
+            let blame = blame_with_ranges(ranges).unwrap();
+            assert_eq!((blame.ranges[0].start, blame.ranges[0].end), (*start, *end), "{:?}", ranges);
+            assert!(blame.check_ranges(10).is_ok(), "{:?}", ranges);
+        }
+    }
+
+    #[test]
+    fn malformed_ranges_are_rejected() {
+        for range in ["10", "10,", ",20", "a,b", "1,+x", "-1,5", ""] {
+            assert!(blame_with_ranges(&[range]).is_err(), "{:?}", range);
+        }
+    }
+
+    #[test]
+    fn ranges_outside_the_file_are_rejected() {
+        let cases: &[(&[&str], &str)] = &[
+            (&["0,5"], "0,5"),
+            (&["8,3"], "8,3"),
+            (&["4,+0"], "4,3"),
+            (&["5,11"], "5,11"),
+            (&["8,+5"], "8,12"),
+            (&["11,12"], "11,12"),
+            (&["1,5", "0,2", "9,20"], "0,2 9,20"),
+        ];
+        for (ranges, invalid) in cases {
+            let blame = blame_with_ranges(ranges).unwrap();
+            let msg = blame.check_ranges(10).unwrap_err().to_string();
+            assert_eq!(msg, format!("Invalid line range(s) {} for file.rs which has 10 lines", invalid));
+        }
+    }
+}
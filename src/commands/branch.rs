@@ -10,6 +10,10 @@ use crate::util::SvError::*;
 use crate::svn;
 use colored::*;
 use std::fmt::Display;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+use chrono::{DateTime, Duration, Local};
 
 /// Display current branch or list branches and tags.
 ///
@@ -48,6 +52,29 @@ pub struct Branch {
     #[arg(short = 'T', long, conflicts_with = "tag_regexes")]
     all_tags: bool,
 
+    /// Display the date and age of the most recent commit on each branch.
+    ///
+    /// Branches are listed oldest first.  All branches are included unless
+    /// --branch or --tag options are given.
+    #[arg(short, long)]
+    age: bool,
+
+    /// Only display branches with no commits within DURATION.
+    ///
+    /// DURATION is a number followed by h, d, w, m or y. (eg. 90d)
+    /// Implies --age.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+    older_than: Option<Duration>,
+
+    /// Display how each branch compares with the trunk.
+    ///
+    /// For each branch the number of its revisions that have not been merged
+    /// to the trunk (ahead) and the number of trunk revisions that have not been
+    /// merged to the branch (behind) are shown with the date of its most recent
+    /// commit.  All branches are included unless --branch options are given.
+    #[arg(long, conflicts_with_all = ["age", "older_than", "tag_regexes", "all_tags"])]
+    dashboard: bool,
+
     /// Path to working copy directory
     #[arg(default_value = ".")]
     path: String,
@@ -55,6 +82,18 @@ pub struct Branch {
 
 }
 
+fn parse_duration_arg(arg: &str) -> Result<Duration> {
+    util::parse_duration(arg)
+}
+
+//  A branch or tag with the details of its most recent commit.
+struct BranchAge {
+    path: String,
+    revision: String,
+    author: String,
+    date: DateTime<Local>,
+}
+
 impl Branch {
     fn no_arguments(&self) -> bool {
         !self.all_branches
@@ -72,7 +111,13 @@ impl Branch {
     }
 
     pub fn run(&mut self) -> Result<()> {
-        if self.no_arguments() {
+        if self.dashboard {
+            let creds = crate::auth::get_credentials()?;
+            self.show_dashboard(&creds)
+        } else if self.age || self.older_than.is_some() {
+            let creds = crate::auth::get_credentials()?;
+            self.show_ages(&creds)
+        } else if self.no_arguments() {
             self.show_current_branch()
         } else {
             let creds = crate::auth::get_credentials()?;
@@ -133,6 +178,137 @@ impl Branch {
         Ok(())
     }
 
+    fn show_ages(&self, creds: &Option<Credentials>) -> Result<()> {
+        use rayon::prelude::*;
+
+        let base_url = svn::info(creds, &self.path, None)?.root_url;
+        let prefixes = svn::load_prefixes()?;
+        let mut all_prefixes = prefixes.branch_prefixes.clone();
+        all_prefixes.extend(prefixes.tag_prefixes.clone());
+
+        let mut search: Vec<(&String, &[Regex])> = Vec::new();
+        if self.list_branches() || !self.list_tags() {
+            search.extend(prefixes.branch_prefixes.iter().map(|p| (p, self.branch_regexes.as_slice())));
+        }
+        if self.list_tags() {
+            search.extend(prefixes.tag_prefixes.iter().map(|p| (p, self.tag_regexes.as_slice())));
+        }
+
+        //  The commit info of each directory listed under a prefix
+        //  is the most recent commit made anywhere on that branch.
+        let lists = search
+            .par_iter()
+            .map(|(prefix, _)| svn::path_list(creds, util::join_paths(&base_url, prefix).as_str()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let cutoff = self.older_than.map(|d| Local::now() - d);
+        let mut ages = Vec::new();
+        for ((prefix, regexes), list) in search.iter().zip(lists) {
+            for entry in list.entries {
+                let path = util::join_paths(format!("^/{prefix}"), &entry.name);
+                let acceptable = !all_prefixes.iter().any(|p| path[2..] == *p)
+                    && (regexes.is_empty() || regexes.iter().any(|r| r.is_match(&path)));
+                if acceptable && cutoff.map(|c| entry.commit_date < c).unwrap_or(true) {
+                    ages.push(BranchAge {
+                        path,
+                        revision: entry.commit_rev,
+                        author: entry.commit_author,
+                        date: entry.commit_date,
+                    });
+                }
+            }
+        }
+        ages.sort_by_key(|a| a.date);
+
+        let path_width = ages.iter().map(|a| a.path.len()).max().unwrap_or(0);
+        let rev_width = ages.iter().map(|a| util::display_revision(&a.revision).len()).max().unwrap_or(0);
+        let author_width = ages.iter().map(|a| a.author.len()).max().unwrap_or(0);
+        for BranchAge { path, revision, author, date } in &ages {
+            println!(
+                "{:path_width$} {:>rev_width$} {:author_width$} {} {}",
+                path.green(),
+                util::display_revision(revision).yellow(),
+                author.color(util::author_color(author)),
+                util::display_svn_date(date).magenta(),
+                util::display_svn_relative(date)
+            );
+        }
+        Ok(())
+    }
+
+    fn show_dashboard(&self, creds: &Option<Credentials>) -> Result<()> {
+        use rayon::prelude::*;
+
+        svn::require_capability(creds, &self.path, svn::Capability::Mergeinfo)?;
+        let base_url = svn::info(creds, &self.path, None)?.root_url;
+        let prefixes = svn::load_prefixes()?;
+        let trunk_url = util::join_paths(&base_url, &prefixes.trunk_prefix);
+        let trunk_rev = svn::info(creds, &trunk_url, None)?.commit_rev;
+
+        let lists = prefixes.branch_prefixes
+            .par_iter()
+            .map(|prefix| svn::path_list(creds, util::join_paths(&base_url, prefix).as_str()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut branches = Vec::new();
+        for (prefix, list) in prefixes.branch_prefixes.iter().zip(lists) {
+            for entry in list.entries.into_iter().filter(|e| e.kind == "dir") {
+                let path = util::join_paths(format!("^/{prefix}"), &entry.name);
+                let acceptable = !prefixes.branch_prefixes.iter().any(|p| path[2..] == *p)
+                    && (self.branch_regexes.is_empty() || self.branch_regexes.iter().any(|r| r.is_match(&path)));
+                if acceptable {
+                    branches.push((path, entry));
+                }
+            }
+        }
+
+        //  The counts only change when either the branch or the trunk has a new commit.
+        let mut cache = load_dashboard_cache();
+        let key = |path: &str, rev: &str| format!("{}@{}:{}", path, rev, trunk_rev);
+        let counts = branches
+            .par_iter()
+            .map(|(path, entry)| match cache.get(&key(path, &entry.commit_rev)) {
+                Some(counts) => Ok(*counts),
+                None => {
+                    let branch_url = util::join_paths(&base_url, &path[2..]);
+                    let ahead = svn::eligible_revisions(creds, &branch_url, &trunk_url)?.len();
+                    let behind = svn::eligible_revisions(creds, &trunk_url, &branch_url)?.len();
+                    Ok((ahead, behind))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        cache = branches
+            .iter()
+            .zip(&counts)
+            .map(|((path, entry), counts)| (key(path, &entry.commit_rev), *counts))
+            .collect();
+        save_dashboard_cache(&cache)?;
+
+        const BRANCH: &str = "Branch";
+        let path_width = branches.iter().map(|(p, _)| p.len()).max().unwrap_or(0).max(BRANCH.len());
+        println!("{:path_width$}  {:>6}  {:>6}  {:10}  Last commit", BRANCH, "Ahead", "Behind", "Status");
+        println!("{:->path_width$}  {:->6}  {:->6}  {:->10}  {:->20}", "-", "-", "-", "-", "-");
+        for ((path, entry), (ahead, behind)) in branches.iter().zip(&counts) {
+            let (status, color) = match (ahead, behind) {
+                (0, 0) => ("up-to-date", "green"),
+                (_, 0) => ("ahead", "yellow"),
+                (0, _) => ("behind", "red"),
+                _      => ("diverged", "red"),
+            };
+            println!(
+                "{:path_width$}  {:>6}  {:>6}  {:10}  {} {}",
+                path.color(color),
+                ahead,
+                behind,
+                status.color(color),
+                util::display_svn_date(&entry.commit_date).magenta(),
+                util::display_svn_relative(&entry.commit_date)
+            );
+        }
+        Ok(())
+    }
+
     fn list_entries<S, T>(
         &self,
         creds: &Option<Credentials>,
@@ -171,5 +347,28 @@ impl Branch {
     }
 }
 
+//  The ahead and behind counts of each branch keyed by
+//  branch@branch_rev:trunk_rev so that stale entries are never used.
+type DashboardCache = HashMap<String, (usize, usize)>;
+
+fn dashboard_cache_file() -> Result<PathBuf> {
+    util::data_directory().map(|dir| dir.join("dashboard_cache.json"))
+}
 
+//  The cache is only an optimization so a missing or unreadable file is ignored.
+fn load_dashboard_cache() -> DashboardCache {
+    let Ok(_lock) = util::lock_data() else {
+        return DashboardCache::default();
+    };
+    dashboard_cache_file()
+        .ok()
+        .and_then(|path| File::open(path).ok())
+        .and_then(|reader| serde_json::from_reader(reader).ok())
+        .unwrap_or_default()
+}
 
+fn save_dashboard_cache(cache: &DashboardCache) -> Result<()> {
+    let _lock = util::lock_data()?;
+    let writer = File::create(dashboard_cache_file()?)?;
+    Ok(serde_json::to_writer_pretty(writer, cache)?)
+}
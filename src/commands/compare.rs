@@ -0,0 +1,127 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use crate::auth::Credentials;
+use crate::diff::Algorithm;
+use crate::svn::{self, DiffSummary};
+use crate::util::{self, SvError::*};
+
+/// Compare two branches, tags or repository urls.
+///
+/// The names are resolved using the repository prefixes so you can simply
+/// give the name of a branch or tag.  The differences are those needed to
+/// transform URL_A into URL_B.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    Each name may be the trunk prefix, a branch or tag name (eg. 8.1), a prefixed\n\
+    path (eg. branches/8.1), a path relative to the repository root\n\
+    (eg. ^/tags/8.1.1) or a full URL.\n\
+    A working copy is only required when a name is not a full URL.  In that case\n\
+    credentials are taken from the SVU_USERNAME and SVU_PASSWORD environment\n\
+    variables or the svn credential cache."
+)]
+pub struct Compare {
+    /// Display only the paths that differ
+    #[arg(short, long, conflicts_with = "stat")]
+    summary: bool,
+
+    /// Display the number of lines changed in each file
+    #[arg(long)]
+    stat: bool,
+
+    /// Highlight the words that changed within each modified line of the diff
+    #[arg(long, conflicts_with_all = ["summary", "stat"])]
+    word_diff: bool,
+
+    /// Algorithm used to match the words of --word-diff
+    ///
+    /// Myers finds the fewest changed words.  Patience anchors on words that are
+    /// unique to both lines which gives fewer spurious matches on repetitive content.
+    #[arg(long, value_name = "ALGORITHM", value_enum, default_value_t = Algorithm::Myers, requires = "word_diff")]
+    diff_algorithm: Algorithm,
+
+    /// Number of lines of context to display around each change [default: 3]
+    ///
+    /// Requires svn's internal diff.  It has no effect if svn is configured
+    /// to use an external diff-cmd.
+    #[arg(short = 'U', long, value_name = "N", conflicts_with = "summary")]
+    context: Option<u32>,
+
+    /// The original branch, tag or url
+    #[arg(value_name = "URL_A")]
+    url_a: String,
+
+    /// The branch, tag or url compared with URL_A
+    #[arg(value_name = "URL_B")]
+    url_b: String,
+}
+
+impl Compare {
+    pub fn run(&mut self) -> Result<()> {
+        let (creds, old_url, new_url) = self.resolve_urls()?;
+
+        let old_kind = svn::info(&creds, &old_url, None)?.kind;
+        let new_kind = svn::info(&creds, &new_url, None)?.kind;
+        if old_kind != new_kind {
+            let msg = format!(
+                "Cannot compare a {} with a {}: {} is a {} and {} is a {}",
+                old_kind, new_kind, self.url_a, old_kind, self.url_b, new_kind
+            );
+            return Err(General(msg).into());
+        }
+
+        if self.summary {
+            let summary = svn::url_diff_summary(&creds, &old_url, &new_url)?;
+            show_summary(&summary, &old_url, &new_url);
+            return Ok(());
+        }
+
+        let lines = svn::url_diff(&creds, &old_url, &new_url, self.context)?;
+        if self.stat {
+            util::print_diff_stat(&util::diff_stat(&lines));
+        } else {
+            util::print_diff_lines(&lines, self.word_diff.then_some(self.diff_algorithm));
+        }
+        Ok(())
+    }
+
+    //  Full urls do not require a working copy.
+    fn resolve_urls(&self) -> Result<(Option<Credentials>, String, String)> {
+        if self.url_a.contains("://") && self.url_b.contains("://") {
+            Ok((crate::auth::env_credentials(), self.url_a.clone(), self.url_b.clone()))
+        } else {
+            let creds = crate::auth::get_credentials()?;
+            let root_url = svn::info(&creds, ".", None)?.root_url;
+            let old_url = svn::resolve_branch_url(&creds, &root_url, &self.url_a)?;
+            let new_url = svn::resolve_branch_url(&creds, &root_url, &self.url_b)?;
+            Ok((creds, old_url, new_url))
+        }
+    }
+}
+
+//  Display the status columns in the same form as svn followed by
+//  the path relative to the compared urls.
+fn show_summary(summary: &[DiffSummary], old_url: &str, new_url: &str) {
+    for entry in summary {
+        let url = entry.url.as_str();
+        let path = [old_url, new_url]
+            .iter()
+            .find_map(|base| url.strip_prefix(*base))
+            .map(|p| p.trim_start_matches('/'))
+            .filter(|p| !p.is_empty())
+            .unwrap_or(url);
+        let code = svn::item_status_code(&entry.item_status);
+        let color = match code {
+            "D" => "red",
+            "A" => "green",
+            "M" => "blue",
+            _   => "white",
+        };
+        let status = format!("{}{}      ", code, svn::props_status_code(&entry.props_status));
+        println!("{}{}", status.color(color), path.color(color));
+    }
+}
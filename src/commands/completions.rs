@@ -11,7 +11,6 @@ use clap_complete::{generate, shells::Shell};
     author,
     help_template = crate::app::HELP_TEMPLATE,
 )]
-
 pub struct Completions {
     /// Target shell for completions.  Omit to use current shell.
     #[arg()]
@@ -23,7 +22,7 @@ impl Completions {
         let shell = self.shell
             .or(Shell::from_env())
             .ok_or(General("Cannot determine shell".to_owned()))?;
-        let mut clap_cmd = crate::app::Commands::command();
+        let mut clap_cmd = crate::app::App::command();
         let name = clap_cmd.get_name().to_owned();
         generate(shell, &mut clap_cmd, name, &mut std::io::stdout());
         Ok(())
@@ -0,0 +1,112 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use crate::config;
+use crate::hooks::{self, HookContext};
+use crate::util::SvError::*;
+
+/// Display and set configuration values.
+///
+/// Configuration values are stored in the .svu directory at the top of the working copy.
+///
+/// Hooks are commands that are run using the shell from the working copy root
+/// directory before and after an operation that modifies the working copy or the svu
+/// configuration.  The hook is configured using the key `hooks.pre-<operation>` or
+/// `hooks.post-<operation>`.  If a `pre` hook exits with a nonzero status the operation
+/// is not performed.  Use the global --no-hooks option to bypass all hooks.
+///
+/// The operations are stash-push, stash-pop, stash-apply, stash-drop, stash-clear,
+/// update (bisect updating the working copy), prefix, config, ignore (--import and
+/// --output) and migrate.
+///
+/// The following environment variables are passed to hooks:
+/// SVU_HOOK, SVU_WC_ROOT, SVU_REVISION, SVU_BRANCH, and SVU_PATHS (one path per line).
+///
+/// The `display.timezone` key sets the time zone used to display dates.
+/// See the global --timezone option for the accepted values.
+///
+/// Set `svn.ignore-externals` to true to always behave as if the
+/// global --ignore-externals option had been given.
+///
+/// The `svn.jobs` key sets the default for the global --jobs option.
+///
+/// The `svn.timeout` key is the number of seconds that svn commands which
+/// contact the repository (such as info, log, list, cat, diff, blame, mergeinfo,
+/// update and export) may run before they are stopped.  Allow enough time for
+/// the largest export or update that you run.  The SVU_SVN_TIMEOUT environment
+/// variable takes precedence.  There is no limit by default or when it is 0.
+///
+/// Commands that only read from the repository are retried when they fail
+/// with a network error.  The `svn.retries` key sets the number of retries
+/// (default 2) and `svn.retry-delay` the milliseconds to wait before the
+/// first retry (default 500), which doubles for each retry.  The
+/// SVU_SVN_RETRIES and SVU_SVN_RETRY_DELAY environment variables take precedence.
+///
+/// The colors used to display diffs are read from the optional colors.toml
+/// file in the .svu directory.  Each line sets the color of one kind of diff
+/// line, where kind is one of header, index, property, add, context or del:
+///
+///     add = "bright green"
+///
+/// Colors are named as black, red, green, yellow, blue, magenta, cyan,
+/// white or "bright <color>" (eg. "bright blue").
+///
+/// The `capabilities.<uuid>.<name>` keys record whether the server hosting
+/// the repository supports `mergeinfo` and `log-revprops`.  They are set
+/// automatically the first time a command needs to know.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    With no arguments all configuration values are displayed.\n\
+    With only a KEY the value of that key is displayed.\n\
+    Hook operations: stash-push, stash-pop, stash-apply, stash-drop, stash-clear, update, prefix"
+)]
+pub struct Config {
+    /// Remove the configuration value for KEY.
+    #[arg(long, requires = "key", conflicts_with = "value")]
+    unset: bool,
+
+    /// Configuration key. (eg. hooks.pre-stash-push, display.timezone)
+    #[arg(value_name = "KEY")]
+    key: Option<String>,
+
+    /// New value for the configuration key.
+    #[arg(value_name = "VALUE")]
+    value: Option<String>,
+}
+
+impl Config {
+    pub fn run(&mut self) -> Result<()> {
+        let mut values = config::load_config()?;
+
+        match (&self.key, &self.value) {
+            (None, _) => {
+                for (key, value) in &values {
+                    println!("{} = {}", key.green(), value);
+                }
+            }
+            (Some(key), None) if self.unset => {
+                if values.remove(key).is_some() {
+                    hooks::with_hooks("config", &HookContext::default(), || config::save_config(&values))?;
+                }
+            }
+            (Some(key), None) => match values.get(key) {
+                Some(value) => println!("{}", value),
+                None => {
+                    let msg = format!("'{}' is not set", key);
+                    return Err(General(msg).into());
+                }
+            },
+            (Some(key), Some(value)) => {
+                config::validate_key(key)?;
+                config::validate_value(key, value)?;
+                values.insert(key.clone(), value.clone());
+                hooks::with_hooks("config", &HookContext::default(), || config::save_config(&values))?;
+            }
+        }
+        Ok(())
+    }
+}
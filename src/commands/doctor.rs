@@ -0,0 +1,260 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use std::fs::{read, rename};
+use std::path::{Path, PathBuf};
+use serde::{de::DeserializeOwned, Serialize};
+use crate::commands::bisect::{self, BisectData};
+use crate::commands::stash::{self, StashFileEntry};
+use crate::config::{self, ConfigMap};
+use crate::svn::{self, Prefixes};
+use crate::util::{self, SvError::*};
+
+/// Check the files in the .svu directory for corruption.
+///
+/// Each file that svu uses to save its state (configuration, prefixes, the
+/// bisect session and stash entries) is loaded to verify that it can be read
+/// and written back.  Problems are reported along with the offending file.
+///
+/// With --fix a corrupt file is renamed with a .bak extension so that svu
+/// falls back to its defaults, invalid configuration keys are removed and
+/// stash entries whose patch file is missing are dropped.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    Files that do not exist are not considered a problem.\n\
+    Exits with a nonzero status if any problems remain."
+)]
+pub struct Doctor {
+    /// Repair the problems that are found.
+    #[arg(long)]
+    fix: bool,
+}
+
+//  The outcome of checking one file.
+enum Status {
+    Missing,
+    Ok,
+    Problem(String),
+    Fixed(String),
+}
+
+impl Doctor {
+    pub fn run(&mut self) -> Result<()> {
+        let data_dir = util::data_directory()?;
+        let _lock = util::lock_data_directory(&data_dir)?;
+        let results = vec![
+            (config::config_file()?, self.check_config()?),
+            (svn::prefixes_file()?, self.check_json::<Prefixes>(&svn::prefixes_file()?)?),
+            (bisect::bisect_data_file()?, self.check_json::<BisectData>(&bisect::bisect_data_file()?)?),
+            (bisect::bisect_log_file()?, self.check_text(&bisect::bisect_log_file()?)?),
+            (stash::stash_entries_file()?, self.check_stash()?),
+        ];
+
+        let mut problems = 0;
+        for (path, status) in &results {
+            let name = path
+                .strip_prefix(&data_dir)
+                .unwrap_or(path)
+                .to_string_lossy();
+            match status {
+                Status::Missing => println!("{:<30} {}", name, "not present".dimmed()),
+                Status::Ok => println!("{:<30} {}", name, "ok".green()),
+                Status::Problem(msg) => {
+                    problems += 1;
+                    println!("{:<30} {}: {}", name, "problem".red(), msg);
+                }
+                Status::Fixed(msg) => println!("{:<30} {}: {}", name, "fixed".yellow(), msg),
+            }
+        }
+
+        if problems > 0 {
+            let msg = format!("{} problem(s) found, use --fix to repair them", problems);
+            Err(General(msg).into())
+        } else {
+            Ok(())
+        }
+    }
+
+    //  Move a corrupt file out of the way so that svu will use its defaults.
+    fn backup(&self, path: &Path, problem: String) -> Result<Status> {
+        if self.fix {
+            let mut backup_name = path.as_os_str().to_owned();
+            backup_name.push(".bak");
+            let backup_path = PathBuf::from(backup_name);
+            rename(path, &backup_path)?;
+            Ok(Status::Fixed(format!("{} (moved to {})", problem, backup_path.display())))
+        } else {
+            Ok(Status::Problem(problem))
+        }
+    }
+
+    fn check_json<T>(&self, path: &Path) -> Result<Status>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if !path.is_file() {
+            Ok(Status::Missing)
+        } else {
+            match util::check_json_file::<T>(path) {
+                Ok(_) => Ok(Status::Ok),
+                Err(e) => self.backup(path, e.to_string()),
+            }
+        }
+    }
+
+    fn check_text(&self, path: &Path) -> Result<Status> {
+        if !path.is_file() {
+            Ok(Status::Missing)
+        } else if String::from_utf8(read(path)?).is_err() {
+            self.backup(path, "file is not valid UTF-8 text".to_string())
+        } else {
+            Ok(Status::Ok)
+        }
+    }
+
+    fn check_config(&self) -> Result<Status> {
+        let path = config::config_file()?;
+        let status = self.check_json::<ConfigMap>(&path)?;
+        if !matches!(status, Status::Ok) {
+            return Ok(status);
+        }
+
+        let mut values = config::load_config()?;
+        let invalid: Vec<String> = values
+            .keys()
+            .filter(|key| config::validate_key(key).is_err())
+            .cloned()
+            .collect();
+
+        if invalid.is_empty() {
+            Ok(Status::Ok)
+        } else {
+            let msg = format!("invalid keys: {}", invalid.join(", "));
+            if self.fix {
+                values.retain(|key, _| !invalid.contains(key));
+                config::save_config(&values)?;
+                Ok(Status::Fixed(msg))
+            } else {
+                Ok(Status::Problem(msg))
+            }
+        }
+    }
+
+    fn check_stash(&self) -> Result<Status> {
+        let path = stash::stash_entries_file()?;
+        let status = self.check_json::<Vec<StashFileEntry>>(&path)?;
+        if !matches!(status, Status::Ok) {
+            return Ok(status);
+        }
+
+        let entries = util::check_json_file::<Vec<StashFileEntry>>(&path)?;
+        let missing = stash::missing_patch_entries(&entries, self.fix)?;
+        if missing.is_empty() {
+            Ok(Status::Ok)
+        } else {
+            let ids: Vec<String> = missing.iter().map(|id| format!("stash-{}", id)).collect();
+            let msg = format!("patch file missing for {}", ids.join(", "));
+            if self.fix {
+                Ok(Status::Fixed(msg))
+            } else {
+                Ok(Status::Problem(msg))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFIXES: &str = r#"{ "trunkPrefix": "trunk", "branchPrefixes": ["branches"], "tagPrefixes": ["tags"] }"#;
+    const CONFIG: &str = r#"{ "svn.timeout": "30" }"#;
+    const BISECT_DATA: &str = r#"{
+        "originalRev": "12", "headRev": "20", "firstRev": "1", "maxRev": "20", "minRev": "5",
+        "skipped": ["7"], "termGood": null, "termBad": null
+    }"#;
+    const STASH_ENTRIES: &str = r#"[{
+        "branch": "trunk", "revision": "12", "description": "WIP",
+        "date": "2024-03-01T12:00:00.000000Z", "patchName": "stash.patch",
+        "items": [{ "path": "a.c", "revision": "12", "status": "modified", "isDir": false }]
+    }]"#;
+
+    //  A scratch directory that is removed when the test finishes.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("svu-doctor-{}-{}", name, std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name: &str, contents: &str) -> PathBuf {
+            let path = self.0.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn state_files_round_trip() {
+        let dir = TempDir::new("round-trip");
+        let doctor = Doctor { fix: false };
+        let ok = |status: Status| matches!(status, Status::Ok);
+        assert!(ok(doctor.check_json::<Prefixes>(&dir.file("prefixes.json", PREFIXES)).unwrap()));
+        assert!(ok(doctor.check_json::<ConfigMap>(&dir.file("config.json", CONFIG)).unwrap()));
+        assert!(ok(doctor.check_json::<BisectData>(&dir.file("bisect_data.json", BISECT_DATA)).unwrap()));
+        assert!(ok(doctor.check_json::<Vec<StashFileEntry>>(&dir.file("stash_entries.json", STASH_ENTRIES)).unwrap()));
+    }
+
+    #[test]
+    fn missing_file_is_not_a_problem() {
+        let dir = TempDir::new("missing");
+        let status = Doctor { fix: false }.check_json::<Prefixes>(&dir.0.join("prefixes.json")).unwrap();
+        assert!(matches!(status, Status::Missing));
+    }
+
+    #[test]
+    fn corrupt_file_is_reported() {
+        let dir = TempDir::new("corrupt");
+        let path = dir.file("prefixes.json", &PREFIXES[..PREFIXES.len() / 2]);
+        let status = Doctor { fix: false }.check_json::<Prefixes>(&path).unwrap();
+        assert!(matches!(status, Status::Problem(_)));
+        assert!(path.is_file());
+
+        //  A file of the wrong shape is also a problem.
+        let path = dir.file("bisect_data.json", PREFIXES);
+        let status = Doctor { fix: false }.check_json::<BisectData>(&path).unwrap();
+        assert!(matches!(status, Status::Problem(_)));
+    }
+
+    #[test]
+    fn fix_moves_corrupt_file_to_backup() {
+        let dir = TempDir::new("fix");
+        let path = dir.file("stash_entries.json", "[{");
+        let status = Doctor { fix: true }.check_json::<Vec<StashFileEntry>>(&path).unwrap();
+        assert!(matches!(status, Status::Fixed(_)));
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_to_string(dir.0.join("stash_entries.json.bak")).unwrap(), "[{");
+    }
+
+    #[test]
+    fn text_file_must_be_utf8() {
+        let dir = TempDir::new("text");
+        let doctor = Doctor { fix: false };
+        assert!(matches!(doctor.check_text(&dir.file("bisect_log", "# log\n")).unwrap(), Status::Ok));
+        let path = dir.0.join("bisect_log");
+        std::fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+        assert!(matches!(doctor.check_text(&path).unwrap(), Status::Problem(_)));
+    }
+}
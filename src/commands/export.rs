@@ -0,0 +1,75 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use std::path::PathBuf;
+use crate::svn;
+use crate::util::SvError::*;
+
+/// Export a clean copy of a branch or tag.
+///
+/// The NAME is resolved using the repository prefixes so you can simply give
+/// the name of a branch or tag.  See the `prefix` command if you are using
+/// non-standard prefixes for branches and tags.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    NAME may be the trunk prefix, a branch or tag name (eg. 8.1), a prefixed path\n\
+    (eg. branches/8.1), a path relative to the repository root (eg. ^/tags/8.1.1)\n\
+    or a full URL.\n\
+    DEST defaults to the last component of the resolved path."
+)]
+pub struct Export {
+    /// The revision to export.
+    ///
+    /// Revisions can be a numeric value or one of: HEAD, BASE, PREV, COMMITTED.
+    #[arg(short, long, value_name = "REV")]
+    revision: Option<String>,
+
+    /// Export into DEST even if it already exists.
+    #[arg(long)]
+    force: bool,
+
+    /// Name of the branch or tag to export.
+    #[arg(value_name = "NAME")]
+    name: String,
+
+    /// Destination directory.
+    #[arg(value_name = "DEST")]
+    dest: Option<PathBuf>,
+}
+
+impl Export {
+    pub fn run(&mut self) -> Result<()> {
+        let creds = crate::auth::get_credentials()?;
+        let root_url = svn::info(&creds, ".", None)?.root_url;
+        let url = svn::resolve_branch_url(&creds, &root_url, &self.name)?;
+
+        let dest = match &self.dest {
+            Some(dest) => dest.clone(),
+            None => PathBuf::from(url.trim_end_matches('/').rsplit('/').next().unwrap_or(&self.name)),
+        };
+
+        if dest.exists() && !self.force {
+            let msg = format!("{} already exists, use --force to export into it", dest.display());
+            return Err(General(msg).into());
+        }
+
+        let revision = match &self.revision {
+            Some(rev) => Some(svn::resolve_revision(&creds, rev, &url)?),
+            None => None,
+        };
+
+        let exported = svn::export(&creds, &url, &dest, revision.as_deref(), self.force)?;
+        let rel_url = url.strip_prefix(&root_url).map(|p| format!("^{}", p)).unwrap_or(url.clone());
+        println!(
+            "Exported {} at revision {} to {}",
+            rel_url.green(),
+            exported.yellow(),
+            dest.display().to_string().blue()
+        );
+        Ok(())
+    }
+}
@@ -5,9 +5,11 @@ use anyhow::Result;
 use clap::Parser;
 use colored::*;
 use crate::auth::Credentials;
-use crate::util::{SvError::*, join_paths, display_svn_datetime};
+use crate::util::{self, SvError::*, join_paths, display_svn_datetime};
 use crate::svn::{self, Prefixes, SvnInfo};
+use crate::diff::{self, Algorithm};
 use chrono::Local;
+use std::collections::HashMap;
 use std::fmt::Display;
 
 /// Display commit revisions of files across tags and branches.
@@ -48,6 +50,30 @@ pub struct Filerevs {
     #[arg(short = 'T', long, conflicts_with = "tag_regexes")]
     all_tags: bool,
 
+    /// Group the results into a section for each branch and tag
+    ///
+    /// By default the results are grouped by path.
+    #[arg(short, long)]
+    group_by_branch: bool,
+
+    /// Group the branches and tags by the content of each file
+    ///
+    /// The content of each file is fetched and compared so that branches
+    /// holding identical copies of a file are reported together even when
+    /// those copies were committed in different revisions.  Each group is
+    /// identified by the SHA-1 checksum of its content, which is the checksum
+    /// that `svn info` reports for a file in a working copy.
+    #[arg(short = 'c', long, conflicts_with = "group_by_branch")]
+    by_content: bool,
+
+    /// Algorithm used to count the changed lines with --by-content
+    ///
+    /// Myers finds the fewest changed lines.  Patience anchors on lines that are
+    /// unique to both files which gives more meaningful counts when content has
+    /// been moved or the files contain many repeated lines.
+    #[arg(long, value_name = "ALGORITHM", value_enum, default_value_t = Algorithm::Myers, requires = "by_content")]
+    diff_algorithm: Algorithm,
+
     /// PATH or URL to target file
     #[arg(num_args = 1..)]
     paths: Vec<String>,
@@ -96,8 +122,23 @@ impl Filerevs {
         let mut sorted_prefixes = prefixes.clone();
         sorted_prefixes.sort_by(|a, b| a.len().cmp(&b.len()).reverse()); // Sorteed by length longest first.
 
+        let mut results = Vec::new();
         for path_entry in &path_list {
-            show_path_result(&creds, root_url, path_entry, &prefixes, &sorted_prefixes)?;
+            results.push(get_path_result(&creds, root_url, path_entry, &prefixes, &sorted_prefixes)?);
+        }
+
+        if self.by_content {
+            let mut cache = ContentCache::new();
+            for result in &results {
+                let groups = get_content_groups(&creds, result, &mut cache, self.diff_algorithm)?;
+                show_content_groups(result, &groups);
+            }
+        } else if self.group_by_branch {
+            show_branch_results(&results, &prefixes);
+        } else {
+            for result in &results {
+                show_path_result(result);
+            }
         }
         Ok(())
     }
@@ -188,25 +229,47 @@ fn max_width(label: &str, value_widths: impl Iterator<Item = usize>) -> usize {
     value_widths.fold(label.len(), |m, v| m.max(v))
 }
 
-// /this/is/the/users/path
-// Location        Revision  Author  Date         Size
-// --------------  --------  ------  -----------  ----------
-// trunk               7601
-// branches/8.1        7645
-// tags/8.1.1-GA       7625
-fn show_path_result(
+//  The info for a path on a given branch/tag prefix.
+//  The info is None if the path does not exist on that prefix.
+struct Entry(String, Option<Box<SvnInfo>>);
+
+//  The results for a single target path across all prefixes.
+struct PathResult {
+    rel_path: String,
+    is_dir: bool,
+    entries: Vec<Entry>,
+}
+
+impl PathResult {
+    fn display_path(&self) -> String {
+        if self.is_dir {
+            self.rel_path.to_owned() + "/"
+        } else {
+            self.rel_path.clone()
+        }
+    }
+}
+
+const LOCATION: &str = "Location";
+const PATH: &str     = "Path";
+const REVISION: &str = "Revision";
+const AUTHOR: &str   = "Author";
+const DATE: &str     = "Date";
+const SIZE: &str     = "Size";
+const SIZE_WIDTH: usize = 8;
+const COL_SEP: &str  = " ";
+
+fn get_path_result(
     creds: &Option<Credentials>,
     root_url: &str,
     path_entry: &SvnInfo,
     prefixes: &[String],
     sorted_prefixes: &[String]
-) -> Result<()> {
+) -> Result<PathResult> {
     use rayon::prelude::*;
 
-    struct Entry(String, Option<Box<SvnInfo>>);
-
-    let rel_path = &get_svn_rel_path(&path_entry.rel_url, sorted_prefixes)?;
-    let results: Vec<_> = prefixes
+    let rel_path = get_svn_rel_path(&path_entry.rel_url, sorted_prefixes)?;
+    let entries: Vec<_> = prefixes
         .par_iter()
         .map(|prefix| {
             let path = join_paths(join_paths(root_url, prefix.as_str()), rel_path.as_str());
@@ -217,66 +280,218 @@ fn show_path_result(
         })
         .collect();
 
-    const LOCATION: &str = "Location";
-    const REVISION: &str = "Revision";
-    const AUTHOR: &str   = "Author";
-    const DATE: &str     = "Date";
-    const SIZE: &str     = "Size";
+    Ok(PathResult { rel_path, is_dir: path_entry.kind == "dir", entries })
+}
+
+//  Print the header lines for the columns of a table.
+//  The first column is either the Location or the Path.
+fn show_headers(first_label: &str, widths: &[usize]) {
+    let labels = [first_label, REVISION, AUTHOR, DATE];
+    for (label, width) in labels.iter().zip(widths) {
+        print!("{:width$}{}", label, COL_SEP);
+    }
+    println!("{:SIZE_WIDTH$}", SIZE);
 
-    let location_width = max_width(LOCATION, results.iter().map(|r| r.0.len() + 2));
+    for width in widths {
+        print!("{:->width$}{}", "-", COL_SEP);
+    }
+    println!("{:->SIZE_WIDTH$}{}", "-", COL_SEP);
+}
+
+//  Print one row of a table. The first column is either the Location or the Path.
+fn show_row(first_col: &str, opt_info: &Option<Box<SvnInfo>>, widths: &[usize]) {
+    let [first_width, revision_width, author_width, date_width] = widths else {
+        unreachable!("show_row() requires four column widths");
+    };
+    if let Some(info) = opt_info {
+        let size = info
+            .size
+            .map(|s| s.to_string())
+            .unwrap_or("n/a".to_string());
+        print!("{:first_width$}{}", first_col.green(), COL_SEP);
+        print!("{:>revision_width$}{}", info.commit_rev.yellow(), COL_SEP);
+        print!("{:author_width$}{}", info.commit_author.color(util::author_color(&info.commit_author)), COL_SEP);
+        print!("{:date_width$}{}", display_svn_datetime(&info.commit_date).magenta(), COL_SEP);
+        println!("{:>SIZE_WIDTH$}", size);
+    }
+    else {
+        println!("{:first_width$}{}{}", first_col.green(), COL_SEP, "<does not exist>".red());
+    }
+}
+
+//  Returns the widths of the Revision, Author and Date columns.
+fn info_widths<'a>(infos: impl Iterator<Item = &'a Option<Box<SvnInfo>>> + Clone) -> [usize; 3] {
     let revision_width = max_width(
         REVISION,
-        results.iter().map(|r| match &r.1 {
-            Some(info) => info.commit_rev.len(),
-            None => 0,
-        })
+        infos.clone().map(|i| i.as_ref().map(|info| info.commit_rev.len()).unwrap_or(0))
     );
     let author_width = max_width(
-        REVISION,
-        results.iter().map(|r| match &r.1 {
-            Some(info) => info.commit_author.len(),
-            None => 0,
-        })
+        AUTHOR,
+        infos.map(|i| i.as_ref().map(|info| info.commit_author.len()).unwrap_or(0))
     );
     let date_width = display_svn_datetime(&Local::now()).len();
-    let size_width = 8;
-    let col_sep    = " ";
+    [revision_width, author_width, date_width]
+}
+
+// /this/is/the/users/path
+// Location        Revision  Author  Date         Size
+// --------------  --------  ------  -----------  ----------
+// trunk               7601
+// branches/8.1        7645
+// tags/8.1.1-GA       7625
+fn show_path_result(result: &PathResult) {
+    let location_width = max_width(LOCATION, result.entries.iter().map(|r| r.0.len() + 2));
+    let [revision_width, author_width, date_width] = info_widths(result.entries.iter().map(|r| &r.1));
+    let widths = [location_width, revision_width, author_width, date_width];
 
     println!();
-    if path_entry.kind == "dir" {
-        println!("{}", (rel_path.to_owned() + "/").blue());
-    } else {
-        println!("{}", rel_path.blue());
-    }
-    // Headers
-    print!("{:location_width$}{}", LOCATION, col_sep);
-    print!("{:revision_width$}{}", REVISION, col_sep);
-    print!("{:author_width$}{}", AUTHOR, col_sep);
-    print!("{:date_width$}{}", DATE, col_sep);
-    println!("{:size_width$}", SIZE);
-
-    print!("{:->location_width$}{}", "-", col_sep);
-    print!("{:->revision_width$}{}", "-", col_sep);
-    print!("{:->author_width$}{}", "-", col_sep);
-    print!("{:->date_width$}{}", "-", col_sep);
-    println!("{:->size_width$}{}", "-", col_sep);
-
-    for Entry(prefix, opt_info) in &results {
+    println!("{}", result.display_path().blue());
+    show_headers(LOCATION, &widths);
+
+    for Entry(prefix, opt_info) in &result.entries {
         let loc = "^/".to_string() + prefix;
-        if let Some(info) = opt_info {
-            let size = info
-                .size
-                .map(|s| s.to_string())
-                .unwrap_or("n/a".to_string());
-            print!("{:location_width$}{}", (loc.as_str()).green(), col_sep);
-            print!("{:>revision_width$}{}", info.commit_rev.yellow(), col_sep);
-            print!("{:author_width$}{}", info.commit_author.cyan(), col_sep);
-            print!("{:date_width$}{}", display_svn_datetime(&info.commit_date).magenta(), col_sep);
-            println!("{:>size_width$}", size);
-                }
-        else {
-            println!("{:location_width$}{}{}", loc.green(), col_sep, "<does not exist>".red());
+        show_row(&loc, opt_info, &widths);
+    }
+}
+
+// ^/branches/8.1
+// ----------------------------------------------------------------------
+// Path             Revision  Author  Date         Size
+// ---------------  --------  ------  -----------  ----------
+// src/main.rs          7645
+// src/util.rs          7601
+fn show_branch_results(results: &[PathResult], prefixes: &[String]) {
+    let path_width = max_width(PATH, results.iter().map(|r| r.display_path().len()));
+    let [revision_width, author_width, date_width] = info_widths(
+        results.iter().flat_map(|r| r.entries.iter().map(|e| &e.1))
+    );
+    let widths = [path_width, revision_width, author_width, date_width];
+
+    for (index, prefix) in prefixes.iter().enumerate() {
+        println!();
+        println!("{}", ("^/".to_string() + prefix).blue());
+        println!("{}", util::divider(70));
+        show_headers(PATH, &widths);
+        for result in results {
+            show_row(&result.display_path(), &result.entries[index].1, &widths);
+        }
+    }
+}
+
+//  File contents keyed by url@revision so that each
+//  version of a file is only fetched once during a run.
+type ContentCache = HashMap<String, Vec<u8>>;
+
+//  A set of locations that have identical file content.
+//  The delta is the number of lines (deleted, inserted) relative
+//  to the first group and is None for the first group and for binary files.
+struct ContentGroup {
+    checksum: String,
+    size: usize,
+    key: String,
+    binary: bool,
+    delta: Option<(usize, usize)>,
+    locations: Vec<String>,
+}
+
+//  Returns the groups in the order that their first location appears.
+//  Locations where the file does not exist are not included in any group.
+fn get_content_groups(
+    creds: &Option<Credentials>,
+    result: &PathResult,
+    cache: &mut ContentCache,
+    algorithm: Algorithm
+) -> Result<Vec<ContentGroup>> {
+    use rayon::prelude::*;
+
+    if result.is_dir {
+        let msg = format!("{} is a directory, --by-content only compares files", result.rel_path);
+        return Err(General(msg).into());
+    }
+
+    let keys: Vec<_> = result.entries
+        .iter()
+        .filter_map(|Entry(_, info)| info.as_ref())
+        .map(|info| format!("{}@{}", info.url, info.commit_rev))
+        .filter(|key| !cache.contains_key(key))
+        .collect();
+    let fetched = keys
+        .into_par_iter()
+        .map(|key| svn::cat(creds, &key, None).map(|content| (key, content)))
+        .collect::<Result<Vec<_>>>()?;
+    cache.extend(fetched);
+
+    let mut groups: Vec<ContentGroup> = Vec::new();
+    for Entry(prefix, info) in &result.entries {
+        if let Some(info) = info {
+            let key = format!("{}@{}", info.url, info.commit_rev);
+            let content = &cache[&key];
+            let location = "^/".to_string() + prefix;
+            match groups.iter_mut().find(|g| cache[&g.key] == *content) {
+                Some(group) => group.locations.push(location),
+                None => groups.push(ContentGroup {
+                    checksum: util::sha1_hex(content),
+                    size: content.len(),
+                    binary: svn::is_binary(creds, &key, None)?,
+                    key,
+                    delta: None,
+                    locations: vec![location]
+                }),
+            }
         }
     }
-    Ok(())
-}
\ No newline at end of file
+
+    if let Some((first, rest)) = groups.split_first_mut() {
+        let first_content = String::from_utf8_lossy(&cache[&first.key]);
+        let first_lines: Vec<&str> = first_content.lines().collect();
+        for group in rest.iter_mut().filter(|g| !first.binary && !g.binary) {
+            let content = String::from_utf8_lossy(&cache[&group.key]);
+            let lines: Vec<&str> = content.lines().collect();
+            group.delta = Some(diff::change_counts(&diff::diff(&first_lines, &lines, algorithm)));
+        }
+    }
+    Ok(groups)
+}
+
+// /this/is/the/users/path
+// Checksum                                  Size  Lines  Locations
+// ----------------------------------------  ----  -----  ------------------------------
+// 2fd4e1c67a2d28fced849ee1bb76e7391b93eb12   120         ^/trunk, ^/branches/8.1
+// de9f2c7fd25e1b3afad3e85a0bd17d9b100db4b3   118  +2 -3  ^/tags/8.1.1-GA
+fn show_content_groups(result: &PathResult, groups: &[ContentGroup]) {
+    const CHECKSUM: &str = "Checksum";
+    const LINES: &str = "Lines";
+    const LOCATIONS: &str = "Locations";
+    let delta_str = |group: &ContentGroup| -> String {
+        if group.binary {
+            "binary".to_string()
+        } else {
+            group.delta
+                .map(|(del, ins)| format!("+{} -{}", ins, del))
+                .unwrap_or_default()
+        }
+    };
+    let checksum_width = max_width(CHECKSUM, groups.iter().map(|g| g.checksum.len()));
+    let size_width = max_width(SIZE, groups.iter().map(|g| g.size.to_string().len()));
+    let lines_width = max_width(LINES, groups.iter().map(|g| delta_str(g).len()));
+
+    println!();
+    println!("{}", result.display_path().blue());
+    println!("{:checksum_width$}{}{:>size_width$}{}{:lines_width$}{}{}", CHECKSUM, COL_SEP, SIZE, COL_SEP, LINES, COL_SEP, LOCATIONS);
+    println!("{:->checksum_width$}{}{:->size_width$}{}{:->lines_width$}{}{:->30}", "-", COL_SEP, "-", COL_SEP, "-", COL_SEP, "-");
+    for group in groups {
+        print!("{:checksum_width$}{}", group.checksum.yellow(), COL_SEP);
+        print!("{:>size_width$}{}", group.size, COL_SEP);
+        print!("{:lines_width$}{}", delta_str(group).cyan(), COL_SEP);
+        println!("{}", group.locations.join(", ").green());
+    }
+
+    let missing: Vec<_> = result.entries
+        .iter()
+        .filter(|Entry(_, info)| info.is_none())
+        .map(|Entry(prefix, _)| "^/".to_string() + prefix)
+        .collect();
+    if !missing.is_empty() {
+        println!("{} {}", "<does not exist>".red(), missing.join(", "));
+    }
+}
@@ -0,0 +1,120 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use crate::auth::Credentials;
+use crate::svn;
+use crate::util::{self, SvError::*};
+
+/// Search the contents of the files in a repository path.
+///
+/// The files are read directly from the repository so nothing needs
+/// to be checked out.  Binary files are skipped.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    PATTERN is a regular expression.\n\
+    Each match is displayed as path:line:text.\n\
+    Files whose svn:mime-type property marks them as binary are skipped.\n\
+    Use the global --jobs option to limit the number of files fetched at once."
+)]
+pub struct Grep {
+    /// The revision to search.
+    ///
+    /// Revisions can be a numeric value or one of: HEAD, BASE, PREV, COMMITTED.
+    #[arg(short, long, value_name = "REV")]
+    revision: Option<String>,
+
+    /// Ignore case when matching.
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Display only the names of the files that contain a match.
+    #[arg(short = 'l', long)]
+    files_with_matches: bool,
+
+    /// The regular expression to search for.
+    #[arg(value_name = "PATTERN")]
+    pattern: String,
+
+    /// Path to working copy file, directory or URL.
+    #[arg(value_name = "PATH", default_value = ".")]
+    path: String,
+}
+
+//  The lines of a file that matched the pattern.
+struct FileMatches {
+    path: String,
+    lines: Vec<(usize, String)>,
+}
+
+impl Grep {
+    pub fn run(&mut self) -> Result<()> {
+        let regex = RegexBuilder::new(&self.pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|e| General(format!("Invalid pattern: {}", e)))?;
+
+        let creds = crate::auth::get_credentials()?;
+        let path = match self.path.strip_prefix("^/") {
+            Some(rel) => util::join_paths(svn::info(&creds, ".", None)?.root_url, rel),
+            None => self.path.clone(),
+        };
+        let revision = match &self.revision {
+            Some(rev) => Some(svn::resolve_revision(&creds, rev, &path)?),
+            None => None,
+        };
+
+        let files: Vec<String> = if svn::info(&creds, &path, revision.as_deref())?.kind == "file" {
+            vec![path.clone()]
+        } else {
+            svn::path_list_recursive(&creds, &path, revision.as_deref())?
+                .entries
+                .into_iter()
+                .filter(|e| e.kind == "file")
+                .map(|e| util::join_paths(&path, &e.name))
+                .collect()
+        };
+
+        let results = files
+            .par_iter()
+            .map(|file| search_file(&creds, file, revision.as_deref(), &regex))
+            .collect::<Result<Vec<_>>>()?;
+
+        for FileMatches { path, lines } in results.into_iter().flatten() {
+            if self.files_with_matches {
+                println!("{}", path.magenta());
+            } else {
+                for (number, text) in &lines {
+                    println!("{}:{}:{}", path.magenta(), number.to_string().green(), util::highlight_matches(text, &regex));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//  Returns None for binary files and files without a match.
+fn search_file(creds: &Option<Credentials>, path: &str, revision: Option<&str>, regex: &Regex) -> Result<Option<FileMatches>> {
+    if svn::is_binary(creds, path, revision)? {
+        return Ok(None);
+    }
+    let content = svn::cat(creds, path, revision)?;
+    let content = String::from_utf8_lossy(&content);
+    let lines: Vec<(usize, String)> = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(index, line)| (index + 1, line.to_string()))
+        .collect();
+
+    if lines.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(FileMatches { path: path.to_string(), lines }))
+    }
+}
@@ -1,11 +1,16 @@
 
 use anyhow::Result;
 use clap::Parser;
+use colored::*;
+use rayon::prelude::*;
 use crate::auth::Credentials;
+use crate::hooks::{self, HookContext};
 use crate::svn;
 use crate::util;
 use crate::util::SvError::*;
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
 use std::fmt::Display;
 
 /// Print svn:ignore and svn:global-ignores entries in .gitignore format.
@@ -14,75 +19,446 @@ use std::fmt::Display;
     author,
     help_template = crate::app::HELP_TEMPLATE,
     after_help = "\
-    Recursively finds all svn:ignore and svn:global-ignores properties in the repository and \
-    writes them to stdout in .gitignore format."
+    Recursively finds all svn:ignore and svn:global-ignores properties below PATH (or only\n\
+    those of PATH itself with --non-recursive) and \
+    writes them to stdout in .gitignore format.\n\
+    Entries from svn:ignore are anchored to their directory.\n\
+    Entries from svn:global-ignores match at any depth below their directory.\n\
+    With --import the properties are instead set from a .gitignore file using the\n\
+    reverse of these rules.  Negated patterns (!) are not supported.\n\
+    With --porcelain each pattern is written as DIRECTORY<TAB>SCOPE<TAB>PATTERN where\n\
+    DIRECTORY is relative to PATH (. for PATH itself) and SCOPE is `local` for\n\
+    svn:ignore or `global` for svn:global-ignores.\n\
+    The properties are fetched in parallel; use the global --jobs option to limit\n\
+    the number of svn commands run at once."
 )]
-
 pub struct Ignore {
+    /// Set the svn:ignore and svn:global-ignores properties from a .gitignore file
+    ///
+    /// Properties of directories that are not mentioned in the file are removed.
+    #[arg(long, value_name = "FILE")]
+    import: Option<PathBuf>,
+
+    /// Report how the properties differ from the --import file without changing them
+    ///
+    /// Exits with a nonzero status if there are differences.
+    #[arg(long, requires = "import")]
+    check_only: bool,
+
+    /// Display the svn commands that --import would run without running them
+    #[arg(short = 'n', long, requires = "import", conflicts_with = "check_only")]
+    dry_run: bool,
+
+    /// Write one tab separated record per pattern for use by other tools
+    #[arg(long, conflicts_with = "import")]
+    porcelain: bool,
+
+    /// Terminate each --porcelain record with NUL rather than a newline
+    #[arg(short = 'z', requires = "porcelain")]
+    null_terminated: bool,
+
+    /// Write the .gitignore entries to FILE instead of stdout
+    ///
+    /// The file is replaced only once all of the entries have been collected.
+    #[arg(short, long, value_name = "FILE", conflicts_with_all = ["import", "porcelain"])]
+    output: Option<PathBuf>,
+
+    /// Keep the existing content of the --output file and add the entries in a delimited section
+    ///
+    /// A section written by a previous --append is replaced.
+    #[arg(long, requires = "output")]
+    append: bool,
+
+    /// Only display the ignores of PATH itself, not of its subdirectories
+    #[arg(short = 'N', long, conflicts_with = "import")]
+    non_recursive: bool,
+
     /// Path to working Working copy directory.
     #[arg(default_value = ".")]
     path: String,
 }
 
+//  The ignore patterns of each directory relative to the top level PATH.
+//  The key is the directory and true for svn:global-ignores.
+type IgnoreMap = BTreeMap<(String, bool), BTreeSet<String>>;
+
 impl Ignore {
     pub fn run(&mut self) -> Result<()> {
         let creds = crate::auth::get_credentials()?;
-        let prefix_len = self.path.trim_end_matches('/').len() + 1; // Add one for trailing slash
+        self.path = normalize_path(&self.path);
+
+        if let Some(import_file) = self.import.clone() {
+            return self.import_ignores(&creds, &import_file);
+        }
+        if self.porcelain {
+            return self.porcelain_ignores(&creds);
+        }
+        if !is_working_directory(&creds, &self.path)? {
+            let msg = format!("{} is not a subversion working copy directory", self.path);
+            return Err(General(msg).into());
+        }
+
+        //  The properties of each directory are fetched in parallel but the
+        //  results are kept in directory order so the output is stable.
+        let dirs = ignore_dirs(&creds, &self.path, !self.non_recursive)?;
+        let results = dirs
+            .par_iter()
+            .map(|dir_path| gitignore_lines(&creds, &self.path, dir_path))
+            .collect::<Result<Vec<_>>>()?;
+        let lines = dedupe_lines(results.into_iter().flatten());
+
+        if let Some(output) = &self.output {
+            let context = HookContext { paths: vec![output.display().to_string()], ..Default::default() };
+            hooks::with_hooks("ignore", &context, || write_output(output, &lines, self.append))
+        } else {
+            for line in lines {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+    }
+}
+
+//  Returns PATH followed by all of the directories below it (unless not `recursive`)
+//  in depth first order.
+fn ignore_dirs(creds: &Option<Credentials>, path: &str, recursive: bool) -> Result<Vec<String>> {
+    let mut dirs = vec![path.to_string()];
+    if recursive {
+        let list = svn::path_list_recursive(creds, path, None)?;
+        dirs.extend(
+            list.entries
+                .into_iter()
+                .filter(|e| e.kind == "dir")
+                .map(|e| util::join_paths(path, e.name.trim_end_matches('/')))
+        );
+    }
+    Ok(dirs)
+}
+
+//  Normalize the path so that trailing slashes (eg. `./` or `wc//`)
+//  do not throw off the relative paths.  The root directory `/`
+//  must keep its slash.
+fn normalize_path(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() { "/".to_string() } else { trimmed.to_string() }
+}
 
-        fn ignore_lines(lines: &str) -> impl Iterator<Item = &str> {
-            lines
+//  Returns the directory relative to the (normalized) top level PATH.
+//  PATH itself is the empty string.
+fn relative_dir<'a>(path: &str, dir_path: &'a str) -> &'a str {
+    //  Add one for the slash that separates PATH from the entries below it.
+    let prefix_len = if path == "/" { 1 } else { path.len() + 1 };
+    dir_path.get(prefix_len..).unwrap_or("")
+}
+
+//  The .gitignore lines for the svn:ignore and svn:global-ignores of one directory.
+fn gitignore_lines(creds: &Option<Credentials>, path: &str, dir_path: &str) -> Result<Vec<GitignoreLine>> {
+    let rel_dir = relative_dir(path, dir_path);
+    let mut lines = Vec::new();
+
+    for global in [false, true] {
+        if let Some(ignore_output) = get_ignores(creds, dir_path, global)? {
+            let ignores = ignore_output
                 .split('\n')
                 .map(|l| l.trim())  // Clean up and skip blank lines
-                .filter(|l| !l.is_empty())
+                .filter(|l| !l.is_empty());
+            for ignore in ignores {
+                let ignore = ignore.trim_end_matches('/');
+                let is_dir = !global && is_directory(util::join_paths(dir_path, ignore));
+                lines.push(gitignore_line(rel_dir, ignore, global, is_dir));
+            }
         }
+    }
+    Ok(lines)
+}
 
-        fn svn_ignore(
-            creds: &Option<Credentials>,
-            dir_path: &str,
-            prefix_len: usize,
-        ) -> Result<()> {
-            let print_ignores = |global: bool| -> Result<()> {
-                if let Some(ignore_output) = get_ignores(creds, dir_path, global)? {
-                    for ignore in ignore_lines(&ignore_output) {
-                        let ignore_path = if global {
-                            // Start path with ** for global ignores
-                            let base_path = util::join_paths(dir_path, "**");
-                            util::join_paths(base_path, ignore.to_owned().trim_end_matches('/'))
-                        } else {
-                            util::join_paths(dir_path, ignore.to_owned().trim_end_matches('/'))
-                        };
-                        //  Directory entries end with a slash
-                        let suffix = if is_directory(&ignore_path) { "/" } else { "" };
-                        // We prefix each path with a slash so that it refers to the
-                        // specific entry as per .gitignore rules.
-                        // See: https://git-scm.com/docs/gitignore
-                        println!("/{}{}", &ignore_path[prefix_len..], suffix);
-                    }
-                }
-                Ok(())
-            };
+//  A .gitignore line along with the directory (relative to PATH) and
+//  the svn pattern that produced it.
+struct GitignoreLine {
+    dir: String,
+    pattern: String,
+    line: String,
+}
 
-            print_ignores(false)?;
-            print_ignores(true)?;
+fn gitignore_line(rel_dir: &str, ignore: &str, global: bool, is_dir: bool) -> GitignoreLine {
+    let line = if global {
+        // Global ignores apply at any depth below the directory so they
+        // are not anchored.  A pattern with no slash matches at any depth
+        // and a leading `dir/**/` matches at any depth below `dir`.
+        // See: https://git-scm.com/docs/gitignore
+        if rel_dir.is_empty() {
+            escape_gitignore(ignore)
+        } else {
+            util::join_paths(util::join_paths(rel_dir, "**"), ignore)
+        }
+    } else {
+        //  Directory entries end with a slash
+        let suffix = if is_dir { "/" } else { "" };
+        // We prefix each path with a slash so that it refers to the
+        // specific entry as per .gitignore rules.
+        if rel_dir.is_empty() {
+            format!("/{}{}", ignore, suffix)
+        } else {
+            format!("/{}/{}{}", rel_dir, ignore, suffix)
+        }
+    };
+    GitignoreLine { dir: rel_dir.to_string(), pattern: ignore.to_string(), line }
+}
 
-            //  Recursively process all subdirectories
-            let path_list = svn::path_list(creds, dir_path)?;
-            for sub_dir in &path_list.entries {
-                if sub_dir.kind == "dir" {
-                    let subdir_path = util::join_paths(dir_path, sub_dir.name.trim_end_matches('/'));
-                    svn_ignore(creds, &subdir_path, prefix_len)?;
-                }
+//  Keep one line for each directory and pattern.  A pattern can be listed
+//  more than once (eg. `name` and `name/`) and the same pattern can appear
+//  in both the svn:ignore and svn:global-ignores of a directory, producing
+//  `/d/name` and `d/**/name`.  Local ignores are produced before the global
+//  ignores of their directory so the more specific local ignore is kept.
+fn dedupe_lines<I>(lines: I) -> Vec<String>
+where
+    I: IntoIterator<Item = GitignoreLine>,
+{
+    let mut seen = HashSet::new();
+    lines
+        .into_iter()
+        .filter(|l| seen.insert((l.dir.clone(), l.pattern.clone())))
+        .map(|l| l.line)
+        .collect()
+}
+
+const SECTION_BEGIN: &str = "# BEGIN svu ignore";
+const SECTION_END: &str = "# END svu ignore";
+
+//  Write the lines to the output file.  When appending, the lines are
+//  placed in a delimited section after the existing content, replacing
+//  the section from an earlier append if there is one.
+fn write_output(path: &Path, lines: &[String], append: bool) -> Result<()> {
+    let mut content = String::new();
+    if append && path.exists() {
+        let existing = read_to_string(path)?;
+        let mut in_section = false;
+        for line in existing.lines() {
+            if line == SECTION_BEGIN {
+                in_section = true;
+            } else if line == SECTION_END && in_section {
+                in_section = false;
+            } else if !in_section {
+                content.push_str(line);
+                content.push('\n');
             }
-            Ok(())
+        }
+        if !content.is_empty() && !content.ends_with("\n\n") {
+            content.push('\n');
+        }
+    }
+    if append {
+        content.push_str(SECTION_BEGIN);
+        content.push('\n');
+    }
+    for line in lines {
+        content.push_str(line);
+        content.push('\n');
+    }
+    if append {
+        content.push_str(SECTION_END);
+        content.push('\n');
+    }
+    util::write_file_atomic(path, content.as_bytes())
+}
+
+impl Ignore {
+    //  The records are ordered by directory and then scope.
+    fn porcelain_ignores(&self, creds: &Option<Credentials>) -> Result<()> {
+        if !is_working_directory(creds, &self.path)? {
+            let msg = format!("{} is not a subversion working copy directory", self.path);
+            return Err(General(msg).into());
         }
 
-        if !is_working_directory(&creds, &self.path)? {
+        let mut current = IgnoreMap::new();
+        current_ignores(creds, &self.path, !self.non_recursive, &mut current)?;
+
+        let terminator = if self.null_terminated { '\0' } else { '\n' };
+        for ((rel_dir, global), patterns) in &current {
+            let dir = if rel_dir.is_empty() { "." } else { rel_dir.as_str() };
+            let scope = if *global { "global" } else { "local" };
+            for pattern in patterns {
+                print!("{}\t{}\t{}{}", dir, scope, pattern, terminator);
+            }
+        }
+        Ok(())
+    }
+
+    fn import_ignores(&self, creds: &Option<Credentials>, import_file: &Path) -> Result<()> {
+        if !is_working_directory(creds, &self.path)? {
             let msg = format!("{} is not a subversion working copy directory", self.path);
+            return Err(General(msg).into());
+        }
+
+        let desired = parse_gitignore(&read_to_string(import_file)?);
+        let mut current = IgnoreMap::new();
+        current_ignores(creds, &self.path, true, &mut current)?;
+
+        let keys: BTreeSet<&(String, bool)> = desired.keys().chain(current.keys()).collect();
+        let empty = BTreeSet::new();
+        let mut differences = 0;
+        let mut changes = Vec::new();
+        for key @ (rel_dir, global) in keys {
+            let want = desired.get(key).unwrap_or(&empty);
+            let have = current.get(key).unwrap_or(&empty);
+            if want == have {
+                continue;
+            }
+            differences += 1;
+            let dir = if rel_dir.is_empty() { "." } else { rel_dir.as_str() };
+            println!("{} ({})", dir.blue(), prop_name(*global));
+            for pattern in want.difference(have) {
+                println!("{}", format!("+ {}", pattern).green());
+            }
+            for pattern in have.difference(want) {
+                println!("{}", format!("- {}", pattern).red());
+            }
+            let dir_path = if rel_dir.is_empty() { self.path.clone() } else { util::join_paths(&self.path, rel_dir) };
+            if self.dry_run {
+                println!("{}", set_ignores_command(&dir_path, *global, want));
+            } else if !self.check_only {
+                changes.push((dir_path, *global, want));
+            }
+        }
+
+        if !changes.is_empty() {
+            let paths = changes.iter().map(|(dir_path, _, _)| dir_path.clone()).collect();
+            hooks::with_hooks("ignore", &HookContext { paths, ..Default::default() }, || {
+                for (dir_path, global, want) in &changes {
+                    set_ignores(creds, dir_path, *global, want)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        if differences > 0 && self.check_only {
+            let msg = format!(
+                "{} ignore properties differ from {}",
+                differences,
+                import_file.display()
+            );
             Err(General(msg).into())
         } else {
-            svn_ignore(&creds, &self.path, prefix_len)
+            Ok(())
+        }
+    }
+}
+
+fn prop_name(global: bool) -> &'static str {
+    if global { "svn:global-ignores" } else { "svn:ignore" }
+}
+
+//  A line that begins with `#` or `!` has a special meaning in .gitignore
+//  so a pattern that begins with either is escaped with a backslash.
+//  Wildcards (`*`, `?` and `[...]`) are left alone because svn:ignore
+//  patterns are globs with the same meaning, and svn strips the trailing
+//  whitespace from each pattern so that never needs to be escaped.
+fn escape_gitignore(line: &str) -> String {
+    if line.starts_with('#') || line.starts_with('!') {
+        format!("\\{}", line)
+    } else {
+        line.to_string()
+    }
+}
+
+//  Reverse the transformation done when printing the properties.
+//  Anchored patterns (/dir/name) become svn:ignore entries of their directory,
+//  patterns below `dir/**/` and patterns without a slash become
+//  svn:global-ignores entries.
+fn parse_gitignore(text: &str) -> IgnoreMap {
+    let mut ignores = IgnoreMap::new();
+    for line in text.lines().map(|l| l.trim()) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let warn = |reason: &str| eprintln!("{} {}, skipping: {}", "warning:".yellow(), reason, line);
+        if line.starts_with('!') {
+            warn("negated patterns are not supported");
+            continue;
+        }
+
+        //  Remove the escape from a literal leading `#` or `!`.
+        let unescaped = line.strip_prefix('\\').filter(|l| l.starts_with('#') || l.starts_with('!')).unwrap_or(line);
+        let pattern = unescaped.trim_end_matches('/');
+        let (dir, name, global) = if let Some(anchored) = pattern.strip_prefix('/') {
+            let (dir, name) = anchored.rsplit_once('/').unwrap_or(("", anchored));
+            (dir, name, false)
+        } else if let Some(name) = pattern.strip_prefix("**/") {
+            ("", name, true)
+        } else if let Some((dir, name)) = pattern.split_once("/**/") {
+            (dir, name, true)
+        } else if let Some((dir, name)) = pattern.rsplit_once('/') {
+            //  A slash within the pattern anchors it to the top level.
+            (dir, name, false)
+        } else {
+            ("", pattern, true)
+        };
+
+        if name.is_empty() || name.contains('/') || dir.contains("**") || name.contains("**") {
+            warn("pattern cannot be represented by an svn property");
+            continue;
+        }
+        ignores.entry((dir.to_string(), global)).or_default().insert(name.to_string());
+    }
+    ignores
+}
+
+//  Collect the current ignore properties of PATH and (if `recursive`) the directories below it.
+fn current_ignores(creds: &Option<Credentials>, path: &str, recursive: bool, ignores: &mut IgnoreMap) -> Result<()> {
+    let dirs = ignore_dirs(creds, path, recursive)?;
+    let results = dirs
+        .par_iter()
+        .map(|dir_path| -> Result<Vec<(bool, Option<String>)>> {
+            [false, true].into_iter().map(|global| Ok((global, get_ignores(creds, dir_path, global)?))).collect()
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for (dir_path, props) in dirs.iter().zip(results) {
+        let rel_dir = relative_dir(path, dir_path);
+        for (global, output) in props {
+            let patterns: BTreeSet<String> = output
+                .unwrap_or_default()
+                .lines()
+                .map(|l| l.trim().trim_end_matches('/'))
+                .filter(|l| !l.is_empty())
+                .map(|l| l.to_string())
+                .collect();
+            if !patterns.is_empty() {
+                ignores.insert((rel_dir.to_string(), global), patterns);
+            }
         }
     }
+    Ok(())
+}
+
+//  Set the property to the given patterns, removing it if there are none.
+fn set_ignores(creds: &Option<Credentials>, path: &str, global: bool, patterns: &BTreeSet<String>) -> Result<()> {
+    let mut cmd = if patterns.is_empty() {
+        svn::SvnCmd::new("pdel")
+    } else {
+        svn::SvnCmd::new("pset")
+    };
+    cmd.with_creds(creds).arg(prop_name(global));
+    if !patterns.is_empty() {
+        cmd.arg(patterns.iter().cloned().collect::<Vec<String>>().join("\n"));
+    }
+    let output = cmd.arg(path).run()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  The command line that set_ignores would run, for display by --dry-run.
+fn set_ignores_command(path: &str, global: bool, patterns: &BTreeSet<String>) -> String {
+    if patterns.is_empty() {
+        format!("svn propdel {} {}", prop_name(global), util::shell_quote(path))
+    } else {
+        let value = patterns.iter().cloned().collect::<Vec<String>>().join("\n");
+        format!("svn propset {} {} {}", prop_name(global), util::shell_quote(&value), util::shell_quote(path))
+    }
 }
 
 fn is_directory<S>(path: S) -> bool
@@ -100,6 +476,7 @@ fn is_working_directory(creds: &Option<Credentials>, path: &str) -> Result<bool>
 fn get_ignores(creds: &Option<Credentials>, path: &str, global: bool) -> Result<Option<String>> {
     let output = svn::SvnCmd::new("pget")
         .with_creds(creds)
+        .with_timeout()
         .arg(if global { "svn:global-ignores" } else { "svn:ignore" })
         .arg(path)
         .run()?;
@@ -110,3 +487,143 @@ fn get_ignores(creds: &Option<Credentials>, path: &str, global: bool) -> Result<
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_and_global_ignore_of_same_name_produce_one_line() {
+        let lines = dedupe_lines([
+            gitignore_line("", "build", false, true),
+            gitignore_line("", "build", true, false),
+            gitignore_line("d", "name", false, false),
+            gitignore_line("d", "name", true, false),
+        ]);
+        assert_eq!(lines, vec!["/build/", "/d/name"]);
+    }
+
+    #[test]
+    fn pattern_listed_twice_produces_one_line() {
+        let lines = dedupe_lines([
+            gitignore_line("d", "name", true, false),
+            gitignore_line("d", "name", true, false),
+        ]);
+        assert_eq!(lines, vec!["d/**/name"]);
+    }
+
+    //  Match a path against a .gitignore pattern following the rules in
+    //  https://git-scm.com/docs/gitignore for patterns without wildcards:
+    //  a pattern with no slash (other than a trailing one) matches a name at
+    //  any depth, otherwise it is relative to the .gitignore directory and
+    //  `**` matches zero or more directories.
+    fn git_matches(pattern: &str, path: &str) -> bool {
+        fn match_parts(pattern: &[&str], path: &[&str]) -> bool {
+            match pattern.split_first() {
+                None => path.is_empty(),
+                Some((&"**", rest)) => (0..=path.len()).any(|skip| match_parts(rest, &path[skip..])),
+                Some((first, rest)) => path.first() == Some(first) && match_parts(rest, &path[1..]),
+            }
+        }
+        let pattern = pattern.trim_end_matches('/');
+        let path: Vec<&str> = path.split('/').collect();
+        if pattern.contains('/') {
+            let parts: Vec<&str> = pattern.trim_start_matches('/').split('/').collect();
+            match_parts(&parts, &path)
+        } else {
+            path.last() == Some(&pattern)
+        }
+    }
+
+    fn matching_paths(line: &str) -> Vec<&'static str> {
+        const PATHS: [&str; 7] = ["name", "d/name", "d/x/name", "e/name", "e/d/name", "names", "d/names"];
+        PATHS.into_iter().filter(|path| git_matches(line, path)).collect()
+    }
+
+    #[test]
+    fn global_ignores_are_unanchored() {
+        let line = gitignore_line("", "name", true, false).line;
+        assert_eq!(line, "name");
+        assert_eq!(matching_paths(&line), vec!["name", "d/name", "d/x/name", "e/name", "e/d/name"]);
+
+        let line = gitignore_line("d", "name", true, false).line;
+        assert_eq!(line, "d/**/name");
+        assert_eq!(matching_paths(&line), vec!["d/name", "d/x/name"]);
+    }
+
+    #[test]
+    fn local_ignores_are_anchored() {
+        let line = gitignore_line("", "name", false, false).line;
+        assert_eq!(line, "/name");
+        assert_eq!(matching_paths(&line), vec!["name"]);
+
+        let line = gitignore_line("d", "name", false, true).line;
+        assert_eq!(line, "/d/name/");
+        assert_eq!(matching_paths(&line), vec!["d/name"]);
+    }
+
+    //  The local ignore line for `name` in `dir_path` below the top level PATH.
+    fn line_below(path: &str, dir_path: &str) -> String {
+        let path = normalize_path(path);
+        gitignore_line(relative_dir(&path, dir_path), "name", false, false).line
+    }
+
+    #[test]
+    fn top_level_path_is_the_root_of_the_lines() {
+        //  The directories are joined to PATH as ignore_dirs() does.
+        for path in [".", "./", "/home/user/wc", "/home/user/wc/", "wc/sub", "wc/sub//", "/"] {
+            let top = normalize_path(path);
+            assert_eq!(line_below(path, &top), "/name", "PATH {:?}", path);
+            assert_eq!(line_below(path, &util::join_paths(&top, "src")), "/src/name", "PATH {:?}", path);
+            assert_eq!(
+                line_below(path, &util::join_paths(util::join_paths(&top, "src"), "lib")),
+                "/src/lib/name",
+                "PATH {:?}",
+                path
+            );
+        }
+    }
+
+    //  Print a pattern as a .gitignore line and parse it back again.
+    fn round_trip(rel_dir: &str, pattern: &str, global: bool) -> IgnoreMap {
+        let line = gitignore_line(rel_dir, pattern, global, false).line;
+        parse_gitignore(&line)
+    }
+
+    fn ignore_map(rel_dir: &str, pattern: &str, global: bool) -> IgnoreMap {
+        IgnoreMap::from([((rel_dir.to_string(), global), BTreeSet::from([pattern.to_string()]))])
+    }
+
+    #[test]
+    fn leading_hash_and_bang_are_escaped() {
+        assert_eq!(escape_gitignore("#notes.txt"), "\\#notes.txt");
+        assert_eq!(escape_gitignore("!important"), "\\!important");
+        assert_eq!(escape_gitignore("a#b!c"), "a#b!c");
+    }
+
+    #[test]
+    fn metacharacters_round_trip() {
+        let patterns = ["#notes.txt", "!important", "file[12].c", "[ab]", "]", "*.o", "a?b", "name with space", "a\\b"];
+        for pattern in patterns {
+            for (rel_dir, global) in [("", true), ("", false), ("src", true), ("src", false)] {
+                assert_eq!(
+                    round_trip(rel_dir, pattern, global),
+                    ignore_map(rel_dir, pattern, global),
+                    "pattern {:?} in {:?} (global: {})",
+                    pattern,
+                    rel_dir,
+                    global
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn same_name_in_different_directories_is_kept() {
+        let lines = dedupe_lines([
+            gitignore_line("a", "name", false, false),
+            gitignore_line("b", "name", true, false),
+        ]);
+        assert_eq!(lines, vec!["/a/name", "b/**/name"]);
+    }
+}
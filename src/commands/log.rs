@@ -2,12 +2,38 @@
 
 use regex::Regex;
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use crate::auth::Credentials;
-use crate::svn::{self, LogEntry};
+use crate::svn::{self, LogEntry, LogPath, MergeSource};
 use crate::util;
 use colored::*;
 use chrono::{DateTime, Local};
+use rayon::prelude::*;
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use crate::util::SvError::*;
+use std::fs::File;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+
+//  Limits on how much merge history is fetched.
+const MAX_MERGE_DEPTH: u32 = 5;
+const MAX_MERGED_ENTRIES: usize = 50;
+
+//  The commits that a merge brought in from one source.
+//  Entries are only fetched when --merged-commits is used.
+struct MergedCommits {
+    source: MergeSource,
+    entries: Vec<MergedEntry>,
+    omitted: usize,
+}
+
+struct MergedEntry {
+    entry: LogEntry,
+    merged: Vec<MergedCommits>,
+}
+
+type PrefixFn<'a> = dyn Fn(&str, &str, &DateTime<Local>) -> String + Sync + 'a;
 
 /// Display formatted log entries.
 #[derive(Debug, Parser)]
@@ -17,13 +43,28 @@ use chrono::{DateTime, Local};
     after_help = "\
     By default shows only the first line of each commit message (see --full)\n\
     If only 1 revision is given and it is not a range then :0 is appended to make it a range.\n\
-    If no revision is given and the first path looks like a revision it will be treated as one."
+    If no revision is given and the first path looks like a revision it will be treated as one.\n\
+    A DATE is given as YYYY-MM-DD, YYYY-MM-DD HH:MM, as an RFC3339 timestamp\n\
+    or as a duration before now such as 12h, 30d, 6w, 3m or 1y."
 )]
 pub struct Log {
     /// Limit the number of commits displayed
-    #[arg(short, long, value_name = "NUM")]
+    ///
+    /// The limit is applied after --match, --only-mine, --since, --until, --dedup and --prune-empty
+    /// have removed commits.  Without it all of the commits in the range are displayed.
+    #[arg(short, long, short_alias = 'n', value_name = "NUM")]
     limit: Option<u32>,
 
+    /// Skip the first NUM matching commits
+    ///
+    /// The commits are skipped after all other filtering and before --limit
+    /// is applied so that `--skip 20 --limit 20` displays the second page of 20.
+    /// The skipped commits must still be fetched from the repository and when
+    /// --match, --only-mine or --prune-empty are used the entire range of
+    /// revisions is fetched.
+    #[arg(long, value_name = "NUM")]
+    skip: Option<u32>,
+
     /// Display the author of each commit
     #[arg(short, long)]
     author: bool,
@@ -38,6 +79,32 @@ pub struct Log {
     #[arg(short, long)]
     time: bool,
 
+    /// Display the date of each commit relative to now (eg. 3 days ago)
+    ///
+    /// Overrides the --date and --time flags
+    #[arg(long)]
+    relative: bool,
+
+    /// Do not display the revision of each commit
+    ///
+    /// Together with --no-author and --no-date this produces output that does
+    /// not change when the log is regenerated, which is useful for changelogs.
+    /// The message is always displayed.
+    #[arg(long)]
+    no_rev: bool,
+
+    /// Do not display the author of each commit
+    ///
+    /// Overrides the --author and --verbose flags
+    #[arg(long)]
+    no_author: bool,
+
+    /// Do not display the date of each commit
+    ///
+    /// Overrides the --date, --time and --verbose flags
+    #[arg(long)]
+    no_date: bool,
+
     /// Display the full commit message
     ///
     /// Without this only the first line of the commit message is displayed.
@@ -58,10 +125,160 @@ pub struct Log {
     #[arg(short = 'p', long)]
     show_paths: bool,
 
+    /// Display only the action and path of each path affected by a commit
+    ///
+    /// Each path is written as ACTION<TAB>PATH without color for use by scripts.
+    #[arg(long, conflicts_with_all = ["show_paths", "affected_by_merge", "json", "csv", "format"])]
+    name_status: bool,
+
+    /// Terminate each --name-status record with NUL rather than a newline
+    #[arg(short = 'z', requires = "name_status")]
+    null_terminated: bool,
+
+    /// Mark the paths that were changed by a merge
+    ///
+    /// Paths in a commit that recorded merge info are marked as (merged), or as
+    /// (mergeinfo) if the only change was to record the merge.  Implies --show-paths.
+    #[arg(long)]
+    affected_by_merge: bool,
+
+    /// Display paths relative to the current working copy directory
+    ///
+    /// By default paths are displayed relative to the repository root.
+    #[arg(long)]
+    relative_paths: bool,
+
+    /// Display only the changed paths whose action is one of ACTIONS
+    ///
+    /// ACTIONS is any combination of the letters A (added), D (deleted),
+    /// M (modified) and R (replaced).  eg: --diff-filter AD
+    #[arg(long, value_name = "ACTIONS", value_parser = parse_actions)]
+    diff_filter: Option<String>,
+
+    /// Display only the changed paths that match REGEX
+    ///
+    /// If more than one --path-match argument is given then paths that
+    /// match any one of the regular expressions are displayed.
+    #[arg(long = "path-match", value_name = "REGEX")]
+    path_regexes: Vec<Regex>,
+
+    /// Omit commits that have no paths left to display
+    ///
+    /// A commit is omitted when --diff-filter and --path-match have removed
+    /// all of its paths.  This is applied after all other filtering and
+    /// before --skip and --limit so that omitted commits are not counted.
+    #[arg(long)]
+    prune_empty: bool,
+
+    /// Format used to display each commit
+    ///
+    /// The subject formats display only the first line of each commit message
+    /// without any color and are suitable for generating release notes.
+    /// They ignore the options that add details to each commit.
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = Format::Default)]
+    format: Format,
+
+    /// Truncate each commit message to N columns in the one line formats
+    ///
+    /// By default the message is truncated to fit the terminal and is not
+    /// truncated when the output is not a terminal.  The subject formats are
+    /// only truncated when this option is given.  Use 0 to never truncate.
+    #[arg(long, value_name = "N")]
+    msg_width: Option<usize>,
+
+    /// Output the commits as JSON
+    ///
+    /// Each commit includes its `date` and `date_utc` as UTC timestamps and
+    /// `date_local` in the display time zone (see the global --timezone option).
+    /// Subversion does not record the time zone of the committer.
+    /// The paths are included when --show-paths is given, each with a nested
+    /// `from` object holding the path and revision it was copied from.
+    #[arg(long, conflicts_with_all = ["format", "reverse_apply"])]
+    json: bool,
+
+    /// Output the commits as CSV
+    ///
+    /// The first line contains the field names.  Multi-line messages
+    /// and the paths are quoted with one line per message line or path.
+    #[arg(long, conflicts_with_all = ["format", "reverse_apply", "json"])]
+    csv: bool,
+
+    /// Comma separated list of fields included in the --json or --csv output
+    ///
+    /// Fields are output in the order given.  The paths are only included when
+    /// they are requested.
+    /// [default: revision,author,date,date_utc,date_local,message]
+    #[arg(long, value_name = "FIELDS", value_enum, value_delimiter = ',', num_args = 1..)]
+    fields: Vec<LogField>,
+
+    /// Display the revisions merged by each commit
+    #[arg(long)]
+    merge_info: bool,
+
+    /// Display the commits brought in by each merge
+    ///
+    /// The commits are fetched from the merge source.  When DEPTH is greater than 1 the
+    /// merges found among those commits are followed as well, up to DEPTH levels.
+    /// Implies --merge-info.
+    #[arg(
+        long,
+        value_name = "DEPTH",
+        num_args = 0..=1,
+        default_missing_value = "1",
+        value_parser = clap::value_parser!(u32).range(1..=MAX_MERGE_DEPTH as i64),
+    )]
+    merged_commits: Option<u32>,
+
+    /// Display each commit only once
+    ///
+    /// A commit that was brought in by more than one merge, or that falls within
+    /// more than one revision range, is only displayed where it first appears.
+    /// Duplicates are removed before --skip and --limit are applied.
+    #[arg(long)]
+    dedup: bool,
+
+    /// Output a patch that reverts the commits in the revision range
+    ///
+    /// The patch can be applied with `svn patch` to undo all of the commits in the range.
+    /// A warning is given for each file in the patch that has been changed by
+    /// later commits since those changes may conflict with the patch.
+    #[arg(long, requires = "revisions")]
+    reverse_apply: bool,
+
+    /// Write the --reverse-apply patch to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE", requires = "reverse_apply")]
+    output: Option<PathBuf>,
+
+    /// Display the copy and rename history of each path before the log
+    ///
+    /// Lists the path that each path was copied from and the revision of the
+    /// copy, back to where it was originally added.
+    #[arg(long, conflicts_with_all = ["format", "json", "csv", "reverse_apply"])]
+    path_history: bool,
+
+    /// Display the commit that originally added each path
+    ///
+    /// Copies and renames are followed back to where the path was first added.
+    /// The original path is displayed when it differs from the current one.
+    #[arg(long, conflicts_with_all = [
+        "format", "json", "csv", "reverse_apply", "path_history", "stop_on_copy", "revisions", "incoming"
+    ])]
+    first_change: bool,
+
     /// Do not cross copies while traversing history
     #[arg(long)]
     stop_on_copy: bool,
 
+    /// Continue the log across copies and renames of a single PATH
+    ///
+    /// When the history reaches the commit that copied or renamed the path
+    /// the log continues with the path and revision that it was copied from,
+    /// so the entire life of the path is displayed as one log.
+    #[arg(long, conflicts_with_all = [
+        "stop_on_copy", "first_change", "revisions", "incoming", "reverse_apply"
+    ])]
+    follow: bool,
+
     /// Output the commits in the reverse order
     #[arg(long)]
     reverse: bool,
@@ -75,6 +292,24 @@ pub struct Log {
     #[arg(short, long = "revision", value_name = "REV", num_args = 0.., conflicts_with = "incoming")]
     revisions: Vec<String>,
 
+    /// Display only commits made by the current svn user
+    ///
+    /// The username is taken from the credentials used to access the repository
+    /// or from the svn credential cache.
+    #[arg(long, visible_alias = "me")]
+    only_mine: bool,
+
+    /// Display only commits made on or after DATE
+    ///
+    /// When no revisions are given only the revisions around the dates
+    /// are fetched from the repository.
+    #[arg(long, value_name = "DATE", value_parser = util::parse_date_arg)]
+    since: Option<DateTime<Local>>,
+
+    /// Display only commits made before DATE
+    #[arg(long, value_name = "DATE", value_parser = util::parse_date_arg)]
+    until: Option<DateTime<Local>>,
+
     /// Disply only commits with a matching message
     ///
     /// If more than one --match argument is given then commits with messages that
@@ -86,6 +321,240 @@ pub struct Log {
     #[arg(value_name = "PATH", num_args = 0..)]
     paths: Vec<String>,
 }
+#[derive(Serialize)]
+struct JsonFromPath<'a> {
+    path: &'a str,
+    revision: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonLogPath<'a> {
+    action: &'a str,
+    kind: &'a str,
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from: Option<JsonFromPath<'a>>,
+}
+
+//  The JSON representation of a log entry.
+#[derive(Serialize)]
+struct JsonLogEntry<'a> {
+    revision: &'a str,
+    author: &'a str,
+    date: String,
+    date_utc: String,
+    date_local: String,
+    message: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    paths: Option<Vec<JsonLogPath<'a>>>,
+}
+
+//  The fields that can be selected for the --json and --csv output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogField {
+    #[value(name = "revision", alias = "rev")]
+    Revision,
+    #[value(name = "author")]
+    Author,
+    #[value(name = "date")]
+    Date,
+    #[value(name = "date_utc")]
+    DateUtc,
+    #[value(name = "date_local")]
+    DateLocal,
+    #[value(name = "message", alias = "msg")]
+    Message,
+    #[value(name = "paths")]
+    Paths,
+}
+
+const DEFAULT_FIELDS: [LogField; 6] = [
+    LogField::Revision,
+    LogField::Author,
+    LogField::Date,
+    LogField::DateUtc,
+    LogField::DateLocal,
+    LogField::Message,
+];
+
+impl LogField {
+    fn name(&self) -> &'static str {
+        match self {
+            LogField::Revision  => "revision",
+            LogField::Author    => "author",
+            LogField::Date      => "date",
+            LogField::DateUtc   => "date_utc",
+            LogField::DateLocal => "date_local",
+            LogField::Message   => "message",
+            LogField::Paths     => "paths",
+        }
+    }
+}
+
+//  A log entry limited to the selected fields.
+//  Serialized as a map so that the fields appear in the requested order.
+struct SelectedEntry<'a> {
+    entry: JsonLogEntry<'a>,
+    fields: &'a [LogField],
+}
+
+impl Serialize for SelectedEntry<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let e = &self.entry;
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for field in self.fields {
+            match field {
+                LogField::Revision  => map.serialize_entry(field.name(), e.revision)?,
+                LogField::Author    => map.serialize_entry(field.name(), e.author)?,
+                LogField::Date      => map.serialize_entry(field.name(), &e.date)?,
+                LogField::DateUtc   => map.serialize_entry(field.name(), &e.date_utc)?,
+                LogField::DateLocal => map.serialize_entry(field.name(), &e.date_local)?,
+                LogField::Message   => map.serialize_entry(field.name(), e.message)?,
+                LogField::Paths     => map.serialize_entry(field.name(), &e.paths)?,
+            }
+        }
+        map.end()
+    }
+}
+
+impl SelectedEntry<'_> {
+    fn csv_row(&self) -> String {
+        let e = &self.entry;
+        self.fields
+            .iter()
+            .map(|field| {
+                let value = match field {
+                    LogField::Revision  => e.revision.to_string(),
+                    LogField::Author    => e.author.to_string(),
+                    LogField::Date      => e.date.clone(),
+                    LogField::DateUtc   => e.date_utc.clone(),
+                    LogField::DateLocal => e.date_local.clone(),
+                    LogField::Message   => e.message.join("\n"),
+                    LogField::Paths     => e.paths
+                        .iter()
+                        .flatten()
+                        .map(|p| format!("{} {}", p.action, p.path))
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                };
+                util::csv_field(&value)
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+impl<'a> JsonLogEntry<'a> {
+    fn new(entry: &'a LogEntry, include_paths: bool) -> Self {
+        let date_utc = util::svn_date_to_rfc3339_string(&entry.date);
+        let paths = include_paths.then(|| {
+            entry.paths
+                .iter()
+                .map(|p| JsonLogPath {
+                    action: &p.action,
+                    kind: &p.kind,
+                    path: &p.path,
+                    from: p.from_path.as_ref().map(|f| JsonFromPath { path: &f.path, revision: &f.revision }),
+                })
+                .collect()
+        });
+        JsonLogEntry {
+            revision: &entry.revision,
+            author: &entry.author,
+            date: date_utc.clone(),
+            date_utc,
+            date_local: util::display_svn_rfc3339(&entry.date),
+            message: &entry.msg,
+            paths,
+        }
+    }
+}
+
+//  The actions accepted by --diff-filter.
+fn parse_actions(arg: &str) -> std::result::Result<String, String> {
+    let actions = arg.to_ascii_uppercase();
+    if !actions.is_empty() && actions.chars().all(|c| "ADMR".contains(c)) {
+        Ok(actions)
+    } else {
+        Err("must be one or more of the letters A, D, M and R".to_string())
+    }
+}
+
+//  Display the lineage of a path as a compact list.
+fn show_path_history(creds: &Option<Credentials>, path: &str) -> Result<()> {
+    let info = svn::info(creds, path, None)?;
+    let repo_path = info.rel_url.trim_start_matches('^');
+    let steps = svn::path_lineage(creds, &info.root_url, repo_path, &info.commit_rev)?;
+    let display = |p: &str| format!("^{}", p);
+
+    match steps.as_slice() {
+        [] => println!("{}: no prior lineage", info.rel_url.green()),
+        [step] if step.from.is_none() => {
+            println!("{}: no prior lineage (added in {})", info.rel_url.green(), step.revision.yellow());
+        }
+        _ => {
+            let width = steps.iter().map(|s| display(&s.path).len()).max().unwrap_or(0);
+            println!("Path history of {}", info.rel_url.green());
+            for step in &steps {
+                match &step.from {
+                    Some(from) => println!(
+                        "  {:width$}  copied in {} from {}@{}",
+                        display(&step.path),
+                        step.revision.yellow(),
+                        display(&from.path),
+                        from.revision.yellow(),
+                        width = width
+                    ),
+                    None => println!(
+                        "  {:width$}  added in {}",
+                        display(&step.path),
+                        step.revision.yellow(),
+                        width = width
+                    ),
+                }
+            }
+        }
+    }
+    println!();
+    Ok(())
+}
+
+//  Display the commit in which a path was originally added,
+//  following copies back to the path's true origin.
+fn show_first_change(creds: &Option<Credentials>, path: &str, fields: util::HeaderFields, path_style: util::PathStyle) -> Result<()> {
+    let info = svn::info(creds, path, None)?;
+    let repo_path = info.rel_url.trim_start_matches('^');
+    let steps = svn::path_lineage(creds, &info.root_url, repo_path, &info.commit_rev)?;
+    let Some(origin) = steps.last() else {
+        return Err(General(format!("No history found for {}", info.rel_url)).into());
+    };
+
+    let url = format!("{}{}@{}", info.root_url, origin.path, origin.revision);
+    let entries = svn::log(creds, &[url.as_str()], &[origin.revision.as_str()], true, Some(1), false, true)?;
+    let Some(entry) = entries.first() else {
+        return Err(General(format!("No log entry found for {}@{}", origin.path, origin.revision)).into());
+    };
+
+    if origin.path != repo_path {
+        println!("{} was originally added as {}", info.rel_url.green(), format!("^{}", origin.path).green());
+    } else {
+        println!("{} was added in {}", info.rel_url.green(), origin.revision.yellow());
+    }
+    util::show_commit(entry, &fields, util::MessageStyle::Full, path_style, None, false);
+    println!();
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// The standard colored output
+    Default,
+    /// Only the first line of the message
+    Subject,
+    /// The revision followed by the first line of the message
+    RevSubject,
+}
+
 impl Log {
 
     pub fn run(&mut self) -> Result<()> {
@@ -95,16 +564,29 @@ impl Log {
             self.revisions = vec!["HEAD:BASE".to_owned()];
         }
 
-        self.author = self.author || self.verbose;
-        self.time = self.time || self.verbose;
+        self.author = (self.author || self.verbose) && !self.no_author;
+        self.time = (self.time || self.verbose) && !self.no_date;
         self.full = self.full || self.verbose;
-        self.date = self.date && !self.time;
+        self.date = (self.date || self.relative) && !self.time && !self.no_date;
+        if self.no_rev && self.format == Format::RevSubject {
+            self.format = Format::Subject;
+        }
+        self.merge_info = self.merge_info || self.merged_commits.is_some();
+        self.show_paths = self.show_paths || self.affected_by_merge || self.fields.contains(&LogField::Paths);
 
         if self.paths.is_empty() {
             self.paths.push(".".to_string());
         }
 
-        self.show_results()
+        if !self.fields.is_empty() && !self.json && !self.csv {
+            return Err(General("--fields can only be used with --json or --csv".to_string()).into());
+        }
+
+        if self.reverse_apply {
+            self.show_reverse_patch()
+        } else {
+            self.show_results()
+        }
     }
 
 
@@ -125,7 +607,33 @@ impl Log {
 
         let creds = crate::auth::get_credentials()?;
 
+        if self.path_history {
+            for path in &self.paths {
+                show_path_history(&creds, path)?;
+            }
+        }
+
+        if self.first_change {
+            let path_style = if self.name_status {
+                util::PathStyle::NameStatus { null_terminated: self.null_terminated }
+            } else if self.show_paths {
+                util::PathStyle::Full
+            } else {
+                util::PathStyle::Hidden
+            };
+            for path in &self.paths {
+                let fields = util::HeaderFields { relative: self.relative, ..Default::default() };
+                show_first_change(&creds, path, fields, path_style)?;
+            }
+            return Ok(());
+        }
+
         let mut entries = self.get_log_entries(&creds)?;
+        let rel_base = if self.relative_paths {
+            Some(util::working_copy_repo_path(&creds)?)
+        } else {
+            None
+        };
 
         //  In the case where we are showing `incoming` commits
         //  we will have a single revision of "HEAD:BASE".
@@ -148,57 +656,339 @@ impl Log {
 
         //  Get the length of the longest revision string and author name
         let (max_rev_len, max_author_len) = entries.iter().fold((0, 0), |(max_r, max_a), e| {
-            (max_r.max(e.revision.len()), max_a.max(e.author.len()))
+            (max_r.max(util::display_revision(&e.revision).len()), max_a.max(e.author.len()))
         });
+        let max_relative_len = if self.relative {
+            entries.iter().map(|e| util::display_svn_relative(&e.date).len()).max().unwrap_or(0)
+        } else {
+            0
+        };
 
+        let fields = util::HeaderFields {
+            rev: !self.no_rev,
+            author: self.author,
+            date: self.date || self.time,
+            relative: self.relative,
+        };
         let build_prefix = |revision: &str, author: &str, date: &DateTime<Local>| -> String {
+            let mut parts = Vec::new();
+            if fields.rev {
+                parts.push(format!("{:width$}", util::display_revision(revision).yellow(), width=max_rev_len));
+            }
+            if fields.author {
+                parts.push(format!("{:width$}", author.color(util::author_color(author)), width=max_author_len));
+            }
+            if fields.date {
+                let date_str = if self.relative {
+                    format!("{:width$}", util::display_svn_relative(date), width=max_relative_len).magenta()
+                } else if self.time {
+                    util::display_svn_datetime(date).magenta()
+                } else {
+                    util::display_svn_date(date).magenta()
+                };
+                parts.push(date_str.to_string());
+            }
+            //  Keep the separating space so the message follows the prefix.
+            parts.iter().map(|p| format!("{} ", p)).collect()
+        };
+
+        if self.reverse {
+            entries.reverse();
+        }
 
-            let rev_str = format!("{:width$}", revision.yellow(), width=max_rev_len);
-            let author_str = format!("{:width$}", author.cyan(), width=max_author_len);
-            let date_str = if self.time {
-                util::display_svn_datetime(date).magenta()
+        if self.json || self.csv {
+            let fields = if !self.fields.is_empty() {
+                self.fields.clone()
+            } else if self.show_paths {
+                [&DEFAULT_FIELDS[..], &[LogField::Paths]].concat()
             } else {
-                util::display_svn_date(date).magenta()
+                DEFAULT_FIELDS.to_vec()
             };
+            let selected: Vec<SelectedEntry> = entries
+                .iter()
+                .filter(|e| Some(&e.revision) != omit_rev.as_ref())
+                .map(|e| SelectedEntry {
+                    entry: JsonLogEntry::new(e, fields.contains(&LogField::Paths)),
+                    fields: &fields,
+                })
+                .collect();
+            if self.json {
+                println!("{}", serde_json::to_string_pretty(&selected)?);
+            } else {
+                let header: Vec<&str> = fields.iter().map(|f| f.name()).collect();
+                println!("{}", header.join(","));
+                for entry in &selected {
+                    println!("{}", entry.csv_row());
+                }
+            }
+            return Ok(());
+        }
 
-
-            match (self.author, self.date || self.time) {
-                (true, true) => format!("{} {} {}", rev_str, author_str, date_str),
-                (true, false) => format!("{} {}", rev_str, author_str),
-                (false, true) => format!("{} {}", rev_str, date_str),
-                (false, false) => rev_str
+        if self.format != Format::Default {
+            for entry in entries.iter().filter(|e| Some(&e.revision) != omit_rev.as_ref()) {
+                let subject = util::subject_line(entry, self.format == Format::RevSubject);
+                match self.msg_width {
+                    Some(width) if width > 0 => println!("{}", util::truncate_to_width(&subject, width)),
+                    _ => println!("{}", subject),
+                }
             }
-        };
+            return Ok(());
+        }
 
-        if self.reverse {
-            entries.reverse();
+        if self.merge_info || self.affected_by_merge {
+            svn::require_capability(&creds, &self.paths[0], svn::Capability::Mergeinfo)?;
         }
 
-        for LogEntry { revision, author, date, msg, paths } in &entries {
+        let merges = if self.merge_info {
+            let depth = self.merged_commits.unwrap_or(0);
+            let wc_path = self.paths.first().map(|p| p.as_str()).unwrap_or(".");
+            let root_url = svn::info(&creds, wc_path, None)?.root_url;
+            entries
+                .par_iter()
+                .map(|entry| self.get_merged_commits(&creds, &root_url, &entry.revision, depth))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        //  Determine which commits are merges.  If we have already
+        //  fetched the merge info then there is no need to do it again.
+        let is_merge: Vec<bool> = if !self.affected_by_merge {
+            Vec::new()
+        } else if self.merge_info {
+            merges.iter().map(|m| !m.is_empty()).collect()
+        } else {
+            let wc_path = self.paths.first().map(|p| p.as_str()).unwrap_or(".");
+            let root_url = svn::info(&creds, wc_path, None)?.root_url;
+            entries
+                .par_iter()
+                .map(|entry| Ok(!svn::merged_revisions(&creds, &root_url, &entry.revision)?.is_empty()))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let mut seen: HashSet<String> = entries.iter().map(|e| e.revision.clone()).collect();
+        for (index, LogEntry { revision, author, date, msg, paths }) in entries.iter().enumerate() {
             if Some(revision) != omit_rev.as_ref() {
                 let msg_1st = msg.first().map(|s| s.as_str()).unwrap_or("");
                 let prefix = build_prefix(revision, author, date);
 
                 if self.full {
-                    println!("\n{}", prefix);
+                    println!("\n{}", prefix.trim_end());
                     for line in msg {
                         println!("{}", line);
                     }
                 } else {
-                    println!("{} {}", prefix, msg_1st);
+                    println!("{}{}", prefix, self.fit_message(&prefix, msg_1st));
+                }
+
+                if self.name_status {
+                    util::print_name_status(paths, rel_base.as_deref(), self.null_terminated);
                 }
 
                 if self.show_paths {
                     for path in paths {
-                        println!("{}", util::formatted_log_path(path))
+                        let merged = is_merge.get(index).copied().unwrap_or(false);
+                        println!("{}", util::formatted_log_path(path, rel_base.as_deref(), merged))
+                    }
+                }
+
+                if let Some(merged) = merges.get(index) {
+                    self.show_merged_commits(merged, 1, &build_prefix, rel_base.as_deref(), &mut seen);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    //  Truncate a one line message that follows the prefix.
+    //  Without --msg-width the whole line is fitted to the terminal.
+    fn fit_message(&self, prefix: &str, msg: &str) -> String {
+        match self.msg_width {
+            Some(0) => msg.to_string(),
+            Some(width) => util::truncate_to_width(msg, width),
+            None => match util::terminal_width() {
+                Some(columns) => {
+                    let available = columns.saturating_sub(util::display_width(prefix));
+                    util::truncate_to_width(msg, available)
+                }
+                None => msg.to_string(),
+            },
+        }
+    }
+
+    //  Fetch the sources merged by the commit and, if depth > 0,
+    //  the commits that were merged from each source.
+    fn get_merged_commits(
+        &self,
+        creds: &Option<Credentials>,
+        root_url: &str,
+        revision: &str,
+        depth: u32,
+    ) -> Result<Vec<MergedCommits>> {
+        let sources = svn::merged_revisions(creds, root_url, revision)?;
+
+        sources
+            .into_par_iter()
+            .map(|source| {
+                if depth == 0 {
+                    return Ok(MergedCommits { source, entries: Vec::new(), omitted: 0 });
+                }
+
+                let mut entries = Vec::new();
+                for (start, end) in &source.ranges {
+                    //  Use a peg revision in case the source has since been deleted.
+                    let url = format!("{}@{}", util::join_paths(root_url, &source.path), end);
+                    let range = format!("{}:{}", end, start);
+                    entries.extend(svn::log(creds, &[url], &[range], true, None, false, self.show_paths)?);
+                }
+                entries.sort_by_key(|e| std::cmp::Reverse(e.revision.parse::<u64>().unwrap_or(0)));
+                entries.dedup_by(|a, b| a.revision == b.revision);
+
+                let omitted = entries.len().saturating_sub(MAX_MERGED_ENTRIES);
+                entries.truncate(MAX_MERGED_ENTRIES);
+
+                let entries = entries
+                    .into_par_iter()
+                    .map(|entry| {
+                        let merged = self.get_merged_commits(creds, root_url, &entry.revision, depth - 1)?;
+                        Ok(MergedEntry { entry, merged })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(MergedCommits { source, entries, omitted })
+            })
+            .collect()
+    }
+
+    fn show_merged_commits(
+        &self,
+        merged: &[MergedCommits],
+        level: usize,
+        build_prefix: &PrefixFn,
+        rel_base: Option<&str>,
+        seen: &mut HashSet<String>,
+    ) {
+        let indent = "  ".repeat(level);
+        for MergedCommits { source, entries, omitted } in merged {
+            let ranges: Vec<String> = source.ranges
+                .iter()
+                .map(|(start, end)| if start == end { format!("r{}", start) } else { format!("r{}-{}", start, end) })
+                .collect();
+            println!("{}{} {}: {}", indent, "Merged".green(), source.path.blue(), ranges.join(","));
+
+            for MergedEntry { entry, merged } in entries {
+                if self.dedup && !seen.insert(entry.revision.clone()) {
+                    continue;
+                }
+                let prefix = build_prefix(&entry.revision, &entry.author, &entry.date);
+                if self.full {
+                    println!("{}  {}", indent, prefix.trim_end());
+                    for line in &entry.msg {
+                        println!("{}  {}", indent, line);
+                    }
+                } else {
+                    let lead = format!("{}  {}", indent, prefix);
+                    println!("{}{}", lead, self.fit_message(&lead, &entry.msg_1st()));
+                }
+
+                if self.show_paths {
+                    for path in &entry.paths {
+                        println!("{}  {}", indent, util::formatted_log_path(path, rel_base, self.affected_by_merge && !merged.is_empty()))
                     }
                 }
+                self.show_merged_commits(merged, level + 2, build_prefix, rel_base, seen);
+            }
+
+            if *omitted > 0 {
+                println!("{}  ... {} more", indent, omitted);
+            }
+        }
+    }
+
+    //  The reverse patch for revisions LOW:HIGH is the diff from HIGH back to LOW-1.
+    fn show_reverse_patch(&self) -> Result<()> {
+        let creds = crate::auth::get_credentials()?;
+        let path = self.paths[0].as_str();
+
+        if self.revisions.len() != 1 {
+            return Err(General("--reverse-apply requires a single revision range".to_string()).into());
+        }
+        let resolved = svn::resolve_revision_range(&creds, &self.revisions[0], path)?;
+        let (low, high) = match resolved.split_once(':') {
+            Some((a, b)) => {
+                let (a, b): (u64, u64) = (a.parse()?, b.parse()?);
+                (a.min(b), a.max(b))
+            }
+            None => {
+                let rev: u64 = resolved.parse()?;
+                (rev, rev)
+            }
+        };
+        if low == 0 {
+            return Err(General("Revision 0 cannot be reverted".to_string()).into());
+        }
+
+        let lines = svn::range_diff(&creds, path, &high.to_string(), &(low - 1).to_string())?;
+        let patch_files: Vec<&str> = lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("Index: "))
+            .collect();
+
+        if patch_files.is_empty() {
+            eprintln!("The revisions {}:{} made no changes to {}", low, high, path);
+            return Ok(());
+        }
+
+        //  Warn about files that were changed after the range.
+        let path_info = svn::info(&creds, path, None)?;
+        let base = util::decode_url_path(path_info.rel_url.trim_start_matches('^'));
+        let later_range = format!("HEAD:{}", high + 1);
+        let later = svn::log(&creds, &[path], &[later_range.as_str()], false, None, false, true)
+            .unwrap_or_default();
+        for file in &patch_files {
+            let repo_path = if path_info.kind == "dir" { util::join_paths(&base, file) } else { base.clone() };
+            let changes: Vec<&str> = later
+                .iter()
+                .filter(|e| e.paths.iter().any(|p| p.path == repo_path))
+                .map(|e| e.revision.as_str())
+                .collect();
+            if !changes.is_empty() {
+                eprintln!(
+                    "{} {} was changed after r{} (in r{}) and may conflict",
+                    "warning:".yellow(),
+                    file,
+                    high,
+                    changes.join(", r")
+                );
             }
         }
 
+        match &self.output {
+            Some(file_path) => {
+                let mut writer = File::create(file_path)?;
+                for line in &lines {
+                    writeln!(writer, "{}", line)?;
+                }
+                println!("Reverse patch for r{}:{} written to {}", low, high, file_path.display());
+            }
+            None => {
+                util::print_diff_lines(&lines, None);
+            }
+        }
         Ok(())
     }
 
+    fn filtering_paths(&self) -> bool {
+        self.diff_filter.is_some() || !self.path_regexes.is_empty()
+    }
+
+    //  Return true if the path passes the --diff-filter and --path-match filters.
+    fn path_is_selected(&self, path: &LogPath) -> bool {
+        self.diff_filter.as_ref().is_none_or(|actions| actions.contains(path.action.as_str()))
+            && (self.path_regexes.is_empty() || self.path_regexes.iter().any(|r| r.is_match(&path.path)))
+    }
+
     fn get_log_entries(&self, creds: &Option<Credentials>) -> Result<Vec<LogEntry>> {
         let mut revisions = self.revisions.clone();
         let mut paths = self.paths.clone();
@@ -223,30 +1013,208 @@ impl Log {
             resolved_revs[0] = format!("{}:0", resolved_revs[0]);
         }
 
-        let entries = svn::log(
-            creds,
-            &paths,
-            &resolved_revs,
-            true, // include_msg
-            self.limit,
-            self.stop_on_copy,
-            self.show_paths,
-        )?;
-
-        //  Check any regular expressions entered by the user.
-        //  Include the entry if it matches at least one of them.
-        if self.regexes.is_empty() {
-            Ok(entries)
+        //  Let svn select the revisions between the dates.  The dates are
+        //  also checked below because svn selects the youngest revision
+        //  at or before each date, which may fall outside of the range.
+        if resolved_revs.is_empty() && (self.since.is_some() || self.until.is_some()) {
+            let newest = self.until.map(|d| util::svn_revision_date(&d)).unwrap_or_else(|| "HEAD".to_string());
+            let oldest = self.since.map(|d| util::svn_revision_date(&d)).unwrap_or_else(|| "0".to_string());
+            resolved_revs.push(format!("{}:{}", newest, oldest));
+        }
+
+        //  The limit is applied to the filtered entries so when filtering
+        //  svn cannot be told how many entries to return.  The entries are
+        //  read as svn produces them and svn is stopped once enough of them
+        //  have passed the filters.
+        let filtering = !self.regexes.is_empty()
+            || self.only_mine
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.prune_empty
+            || self.dedup;
+        let server_limit = match (self.skip, self.limit) {
+            _ if filtering => None,
+            (Some(skip), Some(limit)) => Some(skip + limit),
+            _ => self.limit,
+        };
+
+        let include_paths = self.show_paths || self.prune_empty || self.name_status || self.filtering_paths();
+        let stream: Box<dyn Iterator<Item = Result<LogEntry>>> = if self.follow {
+            if paths.len() > 1 {
+                return Err(General("--follow accepts only a single PATH".to_string()).into());
+            }
+            let info = svn::info(creds, paths.first().map(|p| p.as_str()).unwrap_or("."), None)?;
+            let repo_path = info.rel_url.trim_start_matches('^');
+            let entries = svn::follow_log(creds, &info.root_url, repo_path, &info.commit_rev, server_limit, include_paths)?;
+            Box::new(entries.into_iter().map(Ok))
         } else {
-            let matching = |entry: &LogEntry| -> bool {
+            Box::new(svn::log_stream(
+                creds,
+                &paths,
+                &resolved_revs,
+                true, // include_msg
+                server_limit,
+                self.stop_on_copy,
+                include_paths,
+            )?)
+        };
+
+        let username = if self.only_mine { Some(crate::auth::current_username(creds)?) } else { None };
+        let mut seen = HashSet::new();
+        let mut skip = self.skip.unwrap_or(0);
+        let mut entries = Vec::new();
+        for entry in stream {
+            //  Also covers a limit of zero.
+            if self.limit.is_some_and(|limit| entries.len() >= limit as usize) {
+                break;
+            }
+            let mut entry = entry?;
+
+            //  Check any regular expressions entered by the user.
+            //  Include the entry if it matches at least one of them.
+            if !self.regexes.is_empty() {
                 let msg = entry.msg.join("\n");
-                self.regexes.iter().any(|r| r.is_match(msg.as_str()))
-            };
-            let new_entries = entries.into_iter().filter(matching).collect();
-            Ok(new_entries)
+                if !self.regexes.iter().any(|r| r.is_match(msg.as_str())) {
+                    continue;
+                }
+            }
+
+            if username.as_ref().is_some_and(|username| entry.author != *username) {
+                continue;
+            }
+
+            if self.since.is_some_and(|since| entry.date < since) || self.until.is_some_and(|until| entry.date >= until) {
+                continue;
+            }
+
+            //  Overlapping revision ranges can produce the same commit more than once.
+            if self.dedup && !seen.insert(entry.revision.clone()) {
+                continue;
+            }
+
+            if self.filtering_paths() {
+                entry.paths.retain(|path| self.path_is_selected(path));
+            }
+
+            //  This must remain the final check so that it
+            //  sees the paths left by all of the other filters.
+            if self.prune_empty && entry.paths.is_empty() {
+                continue;
+            }
+
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            entries.push(entry);
+            if self.limit.is_some_and(|limit| entries.len() >= limit as usize) {
+                break;
+            }
         }
+        Ok(entries)
     }
 }
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_path(action: &str, path: &str) -> LogPath {
+        LogPath {
+            path: path.to_string(),
+            kind: "file".to_string(),
+            action: action.to_string(),
+            text_mods: true,
+            prop_mods: false,
+            from_path: None,
+        }
+    }
+
+    fn selected(args: &[&str]) -> Vec<String> {
+        let log = Log::try_parse_from([&["log"], args].concat()).unwrap();
+        [
+            log_path("A", "/trunk/src/new.rs"),
+            log_path("M", "/trunk/src/main.rs"),
+            log_path("D", "/trunk/docs/old.md"),
+            log_path("R", "/trunk/README.md"),
+        ]
+        .iter()
+        .filter(|p| log.path_is_selected(p))
+        .map(|p| format!("{} {}", p.action, p.path))
+        .collect()
+    }
+
+    #[test]
+    fn all_paths_are_selected_without_filters() {
+        assert_eq!(selected(&[]).len(), 4);
+    }
+
+    #[test]
+    fn diff_filter_selects_actions() {
+        assert_eq!(selected(&["--diff-filter", "ad"]), vec!["A /trunk/src/new.rs", "D /trunk/docs/old.md"]);
+        assert_eq!(selected(&["--diff-filter", "R"]), vec!["R /trunk/README.md"]);
+        assert!(Log::try_parse_from(["log", "--diff-filter", "AX"]).is_err());
+        assert!(Log::try_parse_from(["log", "--diff-filter", ""]).is_err());
+    }
+
+    #[test]
+    fn path_match_selects_any_matching_path() {
+        assert_eq!(
+            selected(&["--path-match", r"\.md$", "--path-match", "main"]),
+            vec!["M /trunk/src/main.rs", "D /trunk/docs/old.md", "R /trunk/README.md"]
+        );
+    }
+
+    #[test]
+    fn filters_are_combined() {
+        assert_eq!(selected(&["--diff-filter", "AM", "--path-match", "^/trunk/src/"]).len(), 2);
+        assert!(selected(&["--diff-filter", "D", "--path-match", "src"]).is_empty());
+    }
+
+    const COPY_LOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<log>
+<logentry revision="7">
+<author>joe</author>
+<date>2024-03-01T12:30:45.123456Z</date>
+<paths>
+<path action="A" kind="file" copyfrom-path="/trunk/a.c" copyfrom-rev="6" text-mods="false" prop-mods="false">/trunk/b.c</path>
+</paths>
+<msg>Copy a.c</msg>
+</logentry>
+</log>
+"#;
+
+    fn json_entry(fields: &[LogField]) -> serde_json::Value {
+        let entries = svn::parse_svn_log(COPY_LOG).unwrap();
+        let selected = SelectedEntry {
+            entry: JsonLogEntry::new(&entries[0], fields.contains(&LogField::Paths)),
+            fields,
+        };
+        serde_json::to_value(&selected).unwrap()
+    }
+
+    #[test]
+    fn default_fields_keep_the_date() {
+        let names: Vec<&str> = DEFAULT_FIELDS.iter().map(|f| f.name()).collect();
+        assert_eq!(names, vec!["revision", "author", "date", "date_utc", "date_local", "message"]);
+
+        let json = json_entry(&DEFAULT_FIELDS);
+        assert_eq!(json["date"], "2024-03-01T12:30:45.123456Z");
+        assert_eq!(json["date_utc"], json["date"]);
+        assert!(json["date_local"].is_string());
+    }
+
+    #[test]
+    fn json_entry_includes_copy_sources() {
+        let entries = svn::parse_svn_log(COPY_LOG).unwrap();
+        let json = json_entry(&[LogField::Revision, LogField::Date, LogField::Paths]);
+        assert_eq!(json["revision"], "7");
+        assert_eq!(json["date"], util::svn_date_to_rfc3339_string(&entries[0].date));
+        assert_eq!(json["paths"][0]["path"], "/trunk/b.c");
+        assert_eq!(json["paths"][0]["from"]["path"], "/trunk/a.c");
+        assert_eq!(json["paths"][0]["from"]["revision"], "6");
+    }
+}
@@ -0,0 +1,106 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use crate::hooks::{self, HookContext};
+use crate::util;
+
+/// Rename the legacy .sv directory to .svu in many working copies.
+///
+/// Earlier versions of this tool stored their data in a .sv directory at
+/// the root of each working copy.  It is renamed automatically the first
+/// time svu is used in a working copy, but this command migrates all of
+/// the working copies found under a directory at once.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    The directories beneath ROOT are searched for working copies.  The search\n\
+    does not descend into a working copy once one has been found.\n\
+    Working copies that have already been migrated are left untouched."
+)]
+pub struct Migrate {
+    /// Display the working copies that would be migrated without renaming anything.
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+
+    /// The directory to search [default: .]
+    #[arg(value_name = "ROOT")]
+    root: Option<PathBuf>,
+}
+
+impl Migrate {
+    pub fn run(&mut self) -> Result<()> {
+        let root = self.root.clone().unwrap_or_else(|| PathBuf::from("."));
+        let mut working_copies = Vec::new();
+        find_working_copies(&root, &mut working_copies)?;
+
+        let migrate = || -> Result<(usize, usize)> {
+            let mut migrated = 0;
+            let mut skipped = 0;
+            for wc_root in &working_copies {
+                if !wc_root.join(".sv").is_dir() {
+                    continue;
+                }
+                if wc_root.join(".svu").is_dir() {
+                    println!("{} {} (both .sv and .svu exist)", "Skipped".yellow(), wc_root.display());
+                    skipped += 1;
+                } else if self.dry_run {
+                    println!("{} {}", "Would migrate".green(), wc_root.display());
+                    migrated += 1;
+                } else if util::migrate_data_directory(wc_root)? {
+                    println!("{} {}", "Migrated".green(), wc_root.display());
+                    migrated += 1;
+                }
+            }
+            Ok((migrated, skipped))
+        };
+
+        let (migrated, skipped) = if self.dry_run {
+            migrate()?
+        } else {
+            let paths = working_copies
+                .iter()
+                .filter(|wc_root| wc_root.join(".sv").is_dir() && !wc_root.join(".svu").is_dir())
+                .map(|wc_root| wc_root.display().to_string())
+                .collect();
+            hooks::with_hooks("migrate", &HookContext { paths, ..Default::default() }, migrate)?
+        };
+
+        let verb = if self.dry_run { "would be migrated" } else { "migrated" };
+        println!(
+            "{} working copies found, {} {}, {} skipped",
+            working_copies.len(),
+            migrated,
+            verb,
+            skipped
+        );
+        Ok(())
+    }
+}
+
+//  A working copy root contains the .svn administrative directory.
+//  Symbolic links are not followed.
+fn find_working_copies(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    if dir.join(".svn").is_dir() {
+        found.push(dir.to_path_buf());
+        return Ok(());
+    }
+
+    let mut subdirs: Vec<PathBuf> = match read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|e| e.path())
+            .collect(),
+        Err(_) => return Ok(()),  // Skip directories that we cannot read.
+    };
+    subdirs.sort();
+    for subdir in &subdirs {
+        find_working_copies(subdir, found)?;
+    }
+    Ok(())
+}
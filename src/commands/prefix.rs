@@ -3,6 +3,9 @@ use anyhow::Result;
 use clap::Parser;
 use crate::{svn, util};
 use crate::util::SvError::*;
+use crate::hooks::{self, HookContext};
+use colored::*;
+use serde::Serialize;
 
 
 /// Display and configure repository prefixes.
@@ -16,6 +19,9 @@ use crate::util::SvError::*;
 /// For example you may keep older branches in ^/obsolete-branches in order to keep your
 /// ^/branches location less cluttered.
 /// 
+/// Branch and tag prefixes are searched in the order that they are listed.  New prefixes
+/// are added at the end; use --move-up and --move-down to change the order.
+///
 /// All prefixes must start with '^/'
 #[derive(Debug, Parser)]
 #[command(
@@ -46,83 +52,383 @@ pub struct Prefix {
     /// Set the trunk prefix.
     #[arg(long, value_name = "PREFIX", value_parser = parse_prefix)]
     set_trunk: Option<String>,
+
+    /// Move a branch or tag prefix one place earlier in the search order.
+    #[arg(long, value_name = "PREFIX", value_parser = parse_prefix)]
+    move_up: Vec<String>,
+
+    /// Move a branch or tag prefix one place later in the search order.
+    #[arg(long, value_name = "PREFIX", value_parser = parse_prefix)]
+    move_down: Vec<String>,
+
+    /// Display the branch and tag prefixes sorted by name rather than in search order.
+    #[arg(long)]
+    sorted: bool,
+
+    /// Save added prefixes even if they do not exist in the repository.
+    ///
+    /// Use this when the directories have not been committed yet.
+    #[arg(short, long)]
+    force: bool,
+
+    /// List the branches or tags found under a prefix without saving it.
+    ///
+    /// Use this to check a prefix before adding it.
+    #[arg(
+        long,
+        visible_alias = "list-branches-under",
+        value_name = "PREFIX",
+        value_parser = parse_prefix,
+        conflicts_with_all = ["add_branch", "rem_branch", "add_tag", "rem_tag", "set_trunk", "move_up", "move_down"]
+    )]
+    test: Option<String>,
+
+    /// Propose prefixes by scanning the top two levels of the repository.
+    ///
+    /// Directories named trunk, branches or tags (and common variants) are
+    /// used to build the proposal.  The proposal is displayed but not saved
+    /// unless --apply is also given.
+    #[arg(
+        long,
+        conflicts_with_all = ["add_branch", "rem_branch", "add_tag", "rem_tag", "set_trunk", "move_up", "move_down", "test"]
+    )]
+    detect: bool,
+
+    /// Save the prefixes proposed by --detect.
+    #[arg(long, requires = "detect")]
+    apply: bool,
+
+    /// Output the prefixes as JSON.
+    ///
+    /// The messages describing any changes are not displayed.
+    #[arg(long, conflicts_with = "test")]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct JsonPrefixes {
+    trunk: String,
+    branches: Vec<String>,
+    tags: Vec<String>,
 }
 
 impl Prefix {
     pub fn run(&mut self) -> Result<()> {
+        if let Some(prefix) = &self.test {
+            return test_prefix(prefix);
+        }
+
+        if self.detect {
+            let prefixes = detect_prefixes()?;
+            if self.apply {
+                hooks::with_hooks("prefix", &HookContext::default(), || svn::save_prefixes(&prefixes))?;
+                if !self.json {
+                    println!("Detected prefixes {}\n", "saved".green());
+                }
+            } else if !self.json {
+                println!("Proposed prefixes (use --apply to save them)\n");
+            }
+            return self.output_prefixes(prefixes);
+        }
+
         let mut prefixes = svn::load_prefixes()?;
         let mut modified = false;
 
+        //  Verify all of the new prefixes before changing anything.
+        if !self.force {
+            let new_prefixes: Vec<&String> = self.set_trunk.iter()
+                .filter(|p| **p != prefixes.trunk_prefix)
+                .chain(self.add_branch.iter().filter(|p| !prefixes.branch_prefixes.contains(p)))
+                .chain(self.add_tag.iter().filter(|p| !prefixes.tag_prefixes.contains(p)))
+                .collect();
+            if !new_prefixes.is_empty() {
+                let creds = crate::auth::get_credentials()?;
+                let root_url = svn::info(&creds, ".", None)?.root_url;
+                for prefix in new_prefixes {
+                    verify_prefix(&creds, &root_url, prefix)
+                        .map_err(|e| General(format!("{} (use --force to add it anyway)", e)))?;
+                }
+            }
+        }
+
         if let Some(trunk_prefix) = &self.set_trunk {
-            prefixes.trunk_prefix = trunk_prefix.clone();
-            modified = true;
+            if prefixes.trunk_prefix == *trunk_prefix {
+                if !self.json {
+                    println!("Trunk prefix ^/{} {}", trunk_prefix, "already set, no change".yellow());
+                }
+            } else {
+                prefixes.trunk_prefix = trunk_prefix.clone();
+                if !self.json {
+                    println!("Trunk prefix ^/{} {}", trunk_prefix, "set".green());
+                }
+                modified = true;
+            }
         }
 
-        if !self.add_branch.is_empty() || !self.rem_branch.is_empty() {
-            let to_add: Vec<String> = self
-                .add_branch
-                .iter()
-                .filter(|a| !prefixes.branch_prefixes.contains(a))
-                .cloned()
-                .collect();
-            prefixes.branch_prefixes.extend(to_add);
+        modified |= update_prefixes(&mut prefixes.branch_prefixes, &self.add_branch, &self.rem_branch, "Branch", "branches", self.json);
+        modified |= update_prefixes(&mut prefixes.tag_prefixes, &self.add_tag, &self.rem_tag, "Tag", "tags", self.json);
 
-            prefixes
-                .branch_prefixes
-                .retain(|e| !self.rem_branch.contains(e));
+        for (moves, up) in [(&self.move_up, true), (&self.move_down, false)] {
+            for prefix in moves {
+                modified |= move_prefix(&mut prefixes, prefix, up, self.json);
+            }
+        }
 
-            if prefixes.branch_prefixes.is_empty() {
-                prefixes.branch_prefixes.push("branches".to_string());
+        //  Only touch the prefixes file when something actually changed.
+        if modified {
+            hooks::with_hooks("prefix", &HookContext::default(), || svn::save_prefixes(&prefixes))?;
+        }
+
+        let requested = self.set_trunk.is_some()
+            || !self.add_branch.is_empty()
+            || !self.rem_branch.is_empty()
+            || !self.add_tag.is_empty()
+            || !self.rem_tag.is_empty()
+            || !self.move_up.is_empty()
+            || !self.move_down.is_empty();
+        if requested && !self.json {
+            println!();
+        }
+
+        self.output_prefixes(prefixes)
+    }
+
+    fn output_prefixes(&self, prefixes: svn::Prefixes) -> Result<()> {
+        if self.json {
+            let mut prefixes = JsonPrefixes {
+                trunk: prefixes.trunk_prefix,
+                branches: prefixes.branch_prefixes,
+                tags: prefixes.tag_prefixes,
+            };
+            if self.sorted {
+                prefixes.branches.sort();
+                prefixes.tags.sort();
             }
-            modified = true;
+            println!("{}", serde_json::to_string_pretty(&prefixes)?);
+        } else {
+            show_prefixes(prefixes, self.sorted);
         }
+        Ok(())
+    }
+}
 
-        if !self.add_tag.is_empty() || !self.rem_tag.is_empty() {
-            let to_add: Vec<String> = self
-                .add_tag
-                .iter()
-                .filter(|a| !prefixes.tag_prefixes.contains(a))
-                .cloned()
-                .collect();
-            prefixes.tag_prefixes.extend(to_add);
+//  Display all of the configured prefixes to stdout.
+//  The branch and tag prefixes are displayed in the order that
+//  they are searched unless `sorted` is true.
+fn show_prefixes(prefixes: svn::Prefixes, sorted: bool) {
+    let divider = util::divider(40);
+    println!("Trunk prefix");
+    println!("{}", divider);
+    println!("^/{}", prefixes.trunk_prefix);
+
+    println!("\nBranch prefixes");
+    println!("{}", divider);
+    let mut branch_prefixes = prefixes.branch_prefixes;
+    if sorted {
+        branch_prefixes.sort();
+    }
+    for prefix in &branch_prefixes {
+        println!("^/{}", prefix);
+    }
+
+    println!("\nTag prefixes");
+    println!("{}", divider);
+    let mut tag_prefixes = prefixes.tag_prefixes;
+    if sorted {
+        tag_prefixes.sort();
+    }
+    for prefix in &tag_prefixes {
+        println!("^/{}", prefix);
+    }
+}
 
-            prefixes.tag_prefixes.retain(|e| !self.rem_tag.contains(e));
+//  Directory names (compared without regard to case) that identify
+//  the trunk, branches and tags of a repository.
+const TRUNK_NAMES: [&str; 2] = ["trunk", "mainline"];
+const BRANCH_NAMES: [&str; 2] = ["branches", "branch"];
+const TAG_NAMES: [&str; 3] = ["tags", "tag", "releases"];
 
-            if prefixes.tag_prefixes.is_empty() {
-                prefixes.tag_prefixes.push("tags".to_string());
+//  Scan the directories at the top two levels of the repository
+//  for the trunk, branches and tags.  When nothing is found for one
+//  of them the default prefix is proposed.
+fn detect_prefixes() -> Result<svn::Prefixes> {
+    let creds = crate::auth::get_credentials()?;
+    let root_url = svn::info(&creds, ".", None)?.root_url;
+
+    let top_dirs: Vec<String> = svn::path_list(&creds, &root_url)?
+        .entries
+        .into_iter()
+        .filter(|e| e.kind == "dir")
+        .map(|e| e.name)
+        .collect();
+
+    //  Only look below the top level directories that are not
+    //  themselves a trunk, branches or tags directory.
+    let is_layout_dir = |name: &str| {
+        let name = name.to_lowercase();
+        TRUNK_NAMES.contains(&name.as_str())
+            || BRANCH_NAMES.contains(&name.as_str())
+            || TAG_NAMES.contains(&name.as_str())
+    };
+    let urls: Vec<String> = top_dirs
+        .iter()
+        .filter(|name| !is_layout_dir(name))
+        .map(|name| util::join_paths(&root_url, name))
+        .collect();
+    let mut candidates = top_dirs.clone();
+    if !urls.is_empty() {
+        for (parent, list) in top_dirs.iter().filter(|name| !is_layout_dir(name)).zip(svn::path_lists(&creds, &urls)?) {
+            for entry in list.entries.into_iter().filter(|e| e.kind == "dir") {
+                candidates.push(format!("{}/{}", parent, entry.name));
             }
-            modified = true;
         }
+    }
 
-        if modified {
-            svn::save_prefixes(&prefixes)?;
+    let matching = |names: &[&str]| -> Vec<String> {
+        let mut found: Vec<String> = candidates
+            .iter()
+            .filter(|path| {
+                let leaf = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+                names.contains(&leaf.as_str())
+            })
+            .cloned()
+            .collect();
+        found.sort();
+        found
+    };
+
+    let trunks = matching(&TRUNK_NAMES);
+    //  Prefer a trunk at the top level of the repository.
+    let trunk_prefix = trunks
+        .iter()
+        .find(|p| !p.contains('/'))
+        .or(trunks.first())
+        .cloned()
+        .unwrap_or_else(|| "trunk".to_string());
+    for other in trunks.iter().filter(|p| **p != trunk_prefix) {
+        eprintln!("{} ^/{} also looks like a trunk, using ^/{}", "Warning:".yellow(), other, trunk_prefix);
+    }
+
+    let branch_prefixes = matching(&BRANCH_NAMES);
+    let tag_prefixes = matching(&TAG_NAMES);
+    Ok(svn::Prefixes {
+        trunk_prefix,
+        branch_prefixes: if branch_prefixes.is_empty() { vec!["branches".to_string()] } else { branch_prefixes },
+        tag_prefixes: if tag_prefixes.is_empty() { vec!["tags".to_string()] } else { tag_prefixes },
+    })
+}
+
+//  Apply the additions and removals to a list of prefixes reporting
+//  the outcome of each one.  If the last prefix is removed then the
+//  default prefix is restored.
+//  The outcomes are not displayed when `quiet` is true.
+//  Returns true only if the list was actually changed.
+fn update_prefixes(prefixes: &mut Vec<String>, to_add: &[String], to_remove: &[String], label: &str, default: &str, quiet: bool) -> bool {
+    let original = prefixes.clone();
+
+    for prefix in to_add {
+        if prefixes.contains(prefix) {
+            if !quiet {
+                println!("{} prefix ^/{} {}", label, prefix, "already present, no change".yellow());
+            }
+        } else {
+            prefixes.push(prefix.clone());
+            if !quiet {
+                println!("{} prefix ^/{} {}", label, prefix, "added".green());
+            }
         }
+    }
 
-        let divider = util::divider(40);
-        //  Finally display all of the configured prefixes to stdout.
-        println!("Trunk prefix");
-        println!("{}", divider);
-        println!("^/{}", prefixes.trunk_prefix);
-
-        println!("\nBranch prefixes");
-        println!("{}", divider);
-        let mut sorted = prefixes.branch_prefixes;
-        sorted.sort();
-        for prefix in &sorted {
-            println!("^/{}", prefix);
+    for prefix in to_remove {
+        if prefixes.contains(prefix) {
+            prefixes.retain(|p| p != prefix);
+            if !quiet {
+                println!("{} prefix ^/{} {}", label, prefix, "removed".green());
+            }
+        } else if !quiet {
+            println!("{} prefix ^/{} {}", label, prefix, "not present, no change".yellow());
         }
+    }
 
-        println!("\nTag prefixes");
-        println!("{}", divider);
-        let mut sorted = prefixes.tag_prefixes;
-        sorted.sort();
-        for prefix in &sorted {
-            println!("^/{}", prefix);
+    if prefixes.is_empty() {
+        prefixes.push(default.to_string());
+        if !quiet {
+            println!("{} prefix ^/{} {}", label, default, "restored as the default".green());
         }
-        Ok(())
     }
+
+    *prefixes != original
 }
 
+//  Move a prefix one place within the branch or tag prefixes that contain it.
+//  The outcome is not displayed when `quiet` is true.
+//  Returns true only if the order was actually changed.
+fn move_prefix(prefixes: &mut svn::Prefixes, prefix: &str, up: bool, quiet: bool) -> bool {
+    let direction = if up { "up" } else { "down" };
+    for (list, label) in [(&mut prefixes.branch_prefixes, "Branch"), (&mut prefixes.tag_prefixes, "Tag")] {
+        if let Some(index) = list.iter().position(|p| p == prefix) {
+            let target = if up { index.checked_sub(1) } else { Some(index + 1).filter(|i| *i < list.len()) };
+            return match target {
+                Some(target) => {
+                    list.swap(index, target);
+                    if !quiet {
+                        println!("{} prefix ^/{} {}", label, prefix, format!("moved {}", direction).green());
+                    }
+                    true
+                }
+                None => {
+                    let end = if up { "first" } else { "last" };
+                    if !quiet {
+                        println!("{} prefix ^/{} {}", label, prefix, format!("already {}, no change", end).yellow());
+                    }
+                    false
+                }
+            };
+        }
+    }
+    if !quiet {
+        println!("Prefix ^/{} {}", prefix, "not present, no change".yellow());
+    }
+    false
+}
+
+//  Display the entries that the prefix would expose as branches or tags.
+fn test_prefix(prefix: &str) -> Result<()> {
+    let creds = crate::auth::get_credentials()?;
+    let root_url = svn::info(&creds, ".", None)?.root_url;
+    let url = verify_prefix(&creds, &root_url, prefix)?;
+
+    let mut entries: Vec<String> = svn::path_list(&creds, &url)?
+        .entries
+        .into_iter()
+        .filter(|e| e.kind == "dir")
+        .map(|e| e.name)
+        .collect();
+    entries.sort();
+
+    println!("Entries under ^/{}", prefix);
+    println!("{}", util::divider(40));
+    for name in &entries {
+        println!("{}", util::join_paths(format!("^/{}", prefix), name).green());
+    }
+    if entries.is_empty() {
+        println!("{}", "<no entries>".red());
+    }
+    Ok(())
+}
+
+//  Make sure that the prefix refers to a directory in the repository.
+//  Returns the url of the prefix.
+fn verify_prefix(creds: &Option<crate::auth::Credentials>, root_url: &str, prefix: &str) -> Result<String> {
+    let url = util::join_paths(root_url, prefix);
+    match svn::info(creds, &url, None) {
+        Ok(info) if info.kind == "dir" => Ok(url),
+        _ => {
+            let msg = format!("The prefix ^/{} is not a directory in the repository", prefix);
+            Err(General(msg).into())
+        }
+    }
+}
 
 fn parse_prefix(arg: &str) -> Result<String> {
     if !arg.starts_with("^/") {
@@ -133,3 +439,88 @@ fn parse_prefix(arg: &str) -> Result<String> {
         Ok(arg[2..].trim_end_matches('/').to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn prefixes(branches: &[&str], tags: &[&str]) -> svn::Prefixes {
+        svn::Prefixes {
+            trunk_prefix: "trunk".to_string(),
+            branch_prefixes: strings(branches),
+            tag_prefixes: strings(tags),
+        }
+    }
+
+    fn update(list: &[&str], to_add: &[&str], to_remove: &[&str]) -> (Vec<String>, bool) {
+        let mut list = strings(list);
+        let changed = update_prefixes(&mut list, &strings(to_add), &strings(to_remove), "Branch", "branches", true);
+        (list, changed)
+    }
+
+    #[test]
+    fn prefixes_are_added_and_removed() {
+        assert_eq!(update(&["branches"], &["releases"], &[]), (strings(&["branches", "releases"]), true));
+        assert_eq!(update(&["branches", "releases"], &[], &["branches"]), (strings(&["releases"]), true));
+        assert_eq!(update(&["branches"], &["releases"], &["branches"]), (strings(&["releases"]), true));
+    }
+
+    #[test]
+    fn adding_a_present_or_removing_a_missing_prefix_is_no_change() {
+        assert_eq!(update(&["branches"], &["branches"], &[]), (strings(&["branches"]), false));
+        assert_eq!(update(&["branches"], &[], &["releases"]), (strings(&["branches"]), false));
+    }
+
+    #[test]
+    fn removing_the_last_prefix_restores_the_default() {
+        assert_eq!(update(&["releases"], &[], &["releases"]), (strings(&["branches"]), true));
+        assert_eq!(update(&["releases", "features"], &[], &["features", "releases"]), (strings(&["branches"]), true));
+        //  Removing the default itself leaves the list as it was.
+        assert_eq!(update(&["branches"], &[], &["branches"]), (strings(&["branches"]), false));
+    }
+
+    #[test]
+    fn prefixes_are_moved_one_place() {
+        let mut p = prefixes(&["a", "b", "c"], &["tags"]);
+        assert!(move_prefix(&mut p, "b", true, true));
+        assert_eq!(p.branch_prefixes, strings(&["b", "a", "c"]));
+        assert!(move_prefix(&mut p, "b", false, true));
+        assert_eq!(p.branch_prefixes, strings(&["a", "b", "c"]));
+        assert!(move_prefix(&mut p, "c", true, true));
+        assert_eq!(p.branch_prefixes, strings(&["a", "c", "b"]));
+    }
+
+    #[test]
+    fn moving_a_prefix_past_either_end_is_no_change() {
+        let mut p = prefixes(&["a", "b", "c"], &["tags"]);
+        assert!(!move_prefix(&mut p, "a", true, true));
+        assert!(!move_prefix(&mut p, "c", false, true));
+        assert_eq!(p.branch_prefixes, strings(&["a", "b", "c"]));
+
+        let mut p = prefixes(&["only"], &["tags"]);
+        assert!(!move_prefix(&mut p, "only", true, true));
+        assert!(!move_prefix(&mut p, "only", false, true));
+        assert_eq!(p.branch_prefixes, strings(&["only"]));
+    }
+
+    #[test]
+    fn tag_prefixes_are_moved() {
+        let mut p = prefixes(&["branches"], &["tags", "releases"]);
+        assert!(move_prefix(&mut p, "releases", true, true));
+        assert_eq!(p.tag_prefixes, strings(&["releases", "tags"]));
+        assert_eq!(p.branch_prefixes, strings(&["branches"]));
+    }
+
+    #[test]
+    fn moving_a_missing_prefix_is_no_change() {
+        let mut p = prefixes(&["a", "b"], &["tags"]);
+        assert!(!move_prefix(&mut p, "missing", true, true));
+        assert!(!move_prefix(&mut p, "trunk", false, true));
+        assert_eq!(p.branch_prefixes, strings(&["a", "b"]));
+        assert_eq!(p.tag_prefixes, strings(&["tags"]));
+    }
+}
@@ -0,0 +1,88 @@
+
+use anyhow::Result;
+use clap::Parser;
+use std::io::Write;
+use crate::svn;
+
+/// Display the revision numbers of commits, one per line.
+///
+/// This is intended for use in scripts.  Only the revision numbers
+/// of the commits are written with no other formatting.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    The revisions are written most recent first unless --reverse is given.\n\
+    If only 1 revision is given and it is not a range then :0 is appended to make it a range.\n\
+    If no revision is given and the first path looks like a revision it will be treated as one."
+)]
+pub struct RevList {
+    /// Specify a revision or a range of revisions
+    ///
+    /// Revisions can be a numeric value or one of: HEAD, BASE, PREV, COMMITTED.
+    /// A range is specified by separating two revisions with a colon eg: HEAD:1000
+    #[arg(short, long = "revision", value_name = "REV", num_args = 0..)]
+    revisions: Vec<String>,
+
+    /// Limit the number of revisions listed
+    #[arg(short, long, value_name = "NUM")]
+    limit: Option<u32>,
+
+    /// Output the revisions in the reverse order
+    #[arg(long)]
+    reverse: bool,
+
+    /// Display only the number of revisions
+    #[arg(short, long)]
+    count: bool,
+
+    /// Separate the revisions with NUL characters rather than newlines
+    #[arg(short = 'z', long)]
+    null: bool,
+
+    /// Limit commits to specific paths [default: .]
+    #[arg(value_name = "PATH", num_args = 0..)]
+    paths: Vec<String>,
+}
+
+impl RevList {
+    pub fn run(&mut self) -> Result<()> {
+        let creds = crate::auth::get_credentials()?;
+
+        //  If no revisions are specified and the first 'path' looks like a revision
+        //  then treat it as one.
+        if self.revisions.is_empty()
+            && !self.paths.is_empty()
+            && svn::looks_like_revision_range(self.paths[0].as_str()) {
+            self.revisions = vec![self.paths.remove(0)];
+        }
+        if self.paths.is_empty() {
+            self.paths.push(".".to_string());
+        }
+
+        let mut resolved_revs = Vec::new();
+        for rev in &self.revisions {
+            resolved_revs.push(svn::resolve_revision_range(&creds, rev, &self.paths[0])?);
+        }
+        if resolved_revs.len() == 1 && !resolved_revs[0].contains(':') {
+            resolved_revs[0] = format!("{}:0", resolved_revs[0]);
+        }
+
+        let mut entries = svn::log(&creds, &self.paths, &resolved_revs, false, self.limit, false, false)?;
+        if self.reverse {
+            entries.reverse();
+        }
+
+        let terminator = if self.null { "\0" } else { "\n" };
+        let mut stdout = std::io::stdout().lock();
+        if self.count {
+            write!(stdout, "{}{}", entries.len(), terminator)?;
+        } else {
+            for entry in &entries {
+                write!(stdout, "{}{}", entry.revision, terminator)?;
+            }
+        }
+        Ok(())
+    }
+}
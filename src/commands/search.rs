@@ -0,0 +1,101 @@
+
+use anyhow::Result;
+use clap::Parser;
+use regex::RegexBuilder;
+use crate::svn;
+use crate::util::{self, SvError::*};
+
+/// Display the commits whose message matches a regular expression.
+///
+/// The matching text is highlighted in each commit that is displayed.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    PATTERN is a regular expression that is matched against the entire message\n\
+    so a pattern can span lines.  The commits are displayed newest first as they\n\
+    are found.\n\
+    eg. svu search -i 'PROJ-12[0-9]+' --path ^/trunk"
+)]
+pub struct Search {
+    /// Ignore case when matching
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Also display commits whose author matches PATTERN
+    #[arg(short, long)]
+    author: bool,
+
+    /// Specify a revision or a range of revisions to search [default: HEAD:0]
+    ///
+    /// Revisions can be a numeric value or one of: HEAD, BASE, PREV, COMMITTED.
+    #[arg(short, long = "revision", value_name = "REV")]
+    revision: Option<String>,
+
+    /// Limit the number of matching commits displayed
+    #[arg(short, long, value_name = "NUM")]
+    limit: Option<usize>,
+
+    /// Display the paths affected by each matching commit
+    #[arg(short = 'p', long)]
+    show_paths: bool,
+
+    /// Only search the history of PATH [default: .]
+    ///
+    /// May be given more than once.
+    #[arg(long = "path", value_name = "PATH")]
+    paths: Vec<String>,
+
+    /// The regular expression to search for
+    #[arg(value_name = "PATTERN")]
+    pattern: String,
+}
+
+impl Search {
+    pub fn run(&mut self) -> Result<()> {
+        let regex = RegexBuilder::new(&self.pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|e| General(format!("Invalid pattern: {}", e)))?;
+
+        if self.paths.is_empty() {
+            self.paths.push(".".to_string());
+        }
+        let creds = crate::auth::get_credentials()?;
+        let range = match &self.revision {
+            Some(rev) => svn::resolve_revision_range(&creds, rev, &self.paths[0])?,
+            None => "HEAD:0".to_string(),
+        };
+
+        let path_style = if self.show_paths { util::PathStyle::Full } else { util::PathStyle::Hidden };
+        let mut found = 0;
+        for entry in svn::log_stream(&creds, &self.paths, &[range], true, None, false, self.show_paths)? {
+            if self.limit.is_some_and(|limit| found >= limit) {
+                break;
+            }
+            let mut entry = entry?;
+            let msg_match = regex.is_match(&entry.msg.join("\n"));
+            let author_match = self.author && regex.is_match(&entry.author);
+            if !msg_match && !author_match {
+                continue;
+            }
+
+            found += 1;
+            entry.msg = entry.msg.iter().map(|line| util::highlight_matches(line, &regex)).collect();
+            if author_match {
+                entry.author = util::highlight_matches(&entry.author, &regex);
+            }
+            util::show_commit(&entry, &util::HeaderFields::default(), util::MessageStyle::Full, path_style, None, false);
+            //  Stop svn as soon as enough commits have been found.
+            if self.limit.is_some_and(|limit| found >= limit) {
+                break;
+            }
+        }
+
+        if found == 0 {
+            println!("No matching commits found");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::svn;
+use crate::util;
+
+/// Summarize the number of commits made by each author.
+///
+/// The authors are listed with the most prolific first.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    visible_alias = "contributors",
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    A DATE is given as YYYY-MM-DD, YYYY-MM-DD HH:MM, as an RFC3339 timestamp\n\
+    or as a duration before now such as 12h, 30d, 6w, 3m or 1y.\n\
+    eg. svu shortlog --since 2024-01-01 --until 3m"
+)]
+pub struct Shortlog {
+    /// Specify a revision or a range of revisions [default: HEAD:0]
+    ///
+    /// Revisions can be a numeric value or one of: HEAD, BASE, PREV, COMMITTED.
+    #[arg(short, long = "revision", value_name = "REV")]
+    revision: Option<String>,
+
+    /// Only count commits made on or after DATE
+    #[arg(long, value_name = "DATE", value_parser = util::parse_date_arg)]
+    since: Option<DateTime<Local>>,
+
+    /// Only count commits made before DATE
+    #[arg(long, value_name = "DATE", value_parser = util::parse_date_arg)]
+    until: Option<DateTime<Local>>,
+
+    /// Treat email style author names as the user name before the @
+    ///
+    /// Authors are also compared without regard to case so that
+    /// jdoe, JDoe and jdoe@example.com are counted together.
+    #[arg(short, long)]
+    email: bool,
+
+    /// Output the counts as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Limit commits to specific paths [default: .]
+    #[arg(value_name = "PATH", num_args = 0..)]
+    paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AuthorCount {
+    author: String,
+    commits: usize,
+}
+
+impl Shortlog {
+    pub fn run(&mut self) -> Result<()> {
+        if self.paths.is_empty() {
+            self.paths.push(".".to_string());
+        }
+        let creds = crate::auth::get_credentials()?;
+        let range = match &self.revision {
+            Some(rev) => svn::resolve_revision_range(&creds, rev, &self.paths[0])?,
+            None => "HEAD:0".to_string(),
+        };
+
+        //  The log is processed as it arrives so that the
+        //  entire history is never held in memory.
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for entry in svn::log_stream(&creds, &self.paths, &[range], false, None, false, false)? {
+            let entry = entry?;
+            if self.since.is_some_and(|since| entry.date < since) || self.until.is_some_and(|until| entry.date >= until) {
+                continue;
+            }
+            *counts.entry(self.author_key(&entry.author)).or_default() += 1;
+        }
+
+        let mut counts: Vec<AuthorCount> = counts
+            .into_iter()
+            .map(|(author, commits)| AuthorCount { author, commits })
+            .collect();
+        counts.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.author.cmp(&b.author)));
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&counts)?);
+            return Ok(());
+        }
+        if counts.is_empty() {
+            println!("No commits found");
+            return Ok(());
+        }
+
+        let width = counts[0].commits.to_string().len();
+        for AuthorCount { author, commits } in &counts {
+            println!("{:>width$}  {}", commits, author.color(util::author_color(author)), width = width);
+        }
+        Ok(())
+    }
+
+    fn author_key(&self, author: &str) -> String {
+        if self.email {
+            author.split('@').next().unwrap_or(author).to_lowercase()
+        } else {
+            author.to_string()
+        }
+    }
+}
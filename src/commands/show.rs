@@ -1,8 +1,15 @@
 
 use anyhow::Result;
-use clap::Parser;
-use crate::svn;
-use crate::util;
+use clap::{Parser, ValueEnum};
+use crate::auth::Credentials;
+use crate::diff::Algorithm;
+use crate::svn::{self, LogEntry};
+use crate::util::{self, SvError::*};
+use regex::Regex;
+use colored::*;
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 /// Show the details of a commit
 #[derive(Debug, Parser)]
@@ -12,7 +19,8 @@ use crate::util;
     after_help = "\
     The revision defaults to the current working copy revision.\n\
     If no revision is given and the first path looks like a revision it will be treated as one.\n\
-    If no path is given it defaults to the current working copy directory."
+    If no path is given it defaults to the current working copy directory.\n\
+    If the revision is a range (eg. 100:110) then each commit in the range is shown."
 )]
 pub struct Show {
     /// The commit revision.
@@ -27,19 +35,110 @@ pub struct Show {
     #[arg(short = 'd', long)]
     show_diff: bool,
 
+    /// Show a summary of the lines changed in each file.
+    ///
+    /// Binary files are counted separately.
+    #[arg(long)]
+    stat: bool,
+
+    /// Highlight the words that changed within each modified line of the diff.
+    #[arg(long, requires = "show_diff")]
+    word_diff: bool,
+
+    /// Algorithm used to match the words of --word-diff
+    ///
+    /// Myers finds the fewest changed words.  Patience anchors on words that are
+    /// unique to both lines which gives fewer spurious matches on repetitive content.
+    #[arg(long, value_name = "ALGORITHM", value_enum, default_value_t = Algorithm::Myers, requires = "word_diff")]
+    diff_algorithm: Algorithm,
+
+    /// Number of lines of context to display around each change [default: 3]
+    ///
+    /// Requires svn's internal diff.  It has no effect if svn is configured
+    /// to use an external diff-cmd.
+    #[arg(short = 'U', long, value_name = "N")]
+    context: Option<u32>,
+
     /// Display the paths affected by the commit.
     #[arg(short = 'p', long)]
     show_paths: bool,
 
+    /// Display only the action and path of each path affected by the commit.
+    ///
+    /// Each path is written as ACTION<TAB>PATH without color for use by scripts.
+    #[arg(long, conflicts_with_all = ["show_paths", "affected_by_merge"])]
+    name_status: bool,
+
+    /// Terminate each --name-status record with NUL rather than a newline.
+    #[arg(short = 'z', requires = "name_status")]
+    null_terminated: bool,
+
+    /// Mark the paths that were changed by a merge.
+    ///
+    /// Paths in a commit that recorded merge info are marked as (merged), or as
+    /// (mergeinfo) if the only change was to record the merge.  Implies --show-paths.
+    #[arg(long)]
+    affected_by_merge: bool,
+
+    /// Display paths relative to the current working copy directory.
+    #[arg(long)]
+    relative_paths: bool,
+
     /// Do not display the commit message.
     #[arg(short, long)]
     no_message: bool,
 
+    /// Reflow the lines of the commit message to fit the terminal.
+    ///
+    /// When the output is not a terminal the lines are reflowed to 80 columns.
+    #[arg(long, conflicts_with = "no_message")]
+    wrap: bool,
+
+    /// Display the revision, author, date and first line of the message on one line.
+    #[arg(long, conflicts_with_all = ["no_message", "wrap", "no_rev", "no_author", "no_date"])]
+    oneline: bool,
+
+    /// Do not display the revision in the commit header.
+    #[arg(long)]
+    no_rev: bool,
+
+    /// Do not display the author in the commit header.
+    #[arg(long)]
+    no_author: bool,
+
+    /// Do not display the date in the commit header.
+    #[arg(long)]
+    no_date: bool,
+
+    /// The format used to display each commit.
+    ///
+    /// The email format displays a patch with mail style headers that
+    /// can be applied elsewhere using `svn patch`.
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = Pretty::Default)]
+    pretty: Pretty,
+
+    /// Write the diff of each commit to a separate file in DIR.
+    ///
+    /// The files are named rNNNN.diff, or numbered patch files with
+    /// --pretty=email.  DIR is created if necessary.
+    #[arg(short, long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Overwrite existing files in the --output-dir.
+    #[arg(long, requires = "output_dir")]
+    force: bool,
+
     /// Limit commits to specific paths [default: .]
     #[arg(value_name = "PATH", num_args = 0..)]
     paths: Vec<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Pretty {
+    Default,
+    Email,
+}
+
 impl Show {
     pub fn run(&mut self) -> Result<()> {
         let mut paths = self.paths.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
@@ -63,23 +162,159 @@ impl Show {
         //  In some cases when the revision is PREV, it may not produce a log entry
         //  even though 'svn info' would succeed.  To work around this oddity
         //  we append :0 to the revision and limit the log to 0 entry.
-        let mut rev_vector = Vec::<&str>::new();
-        let mut resolved_rev: String;
+        //  A range of revisions shows every commit in the range.
+        let mut rev_vector = Vec::<String>::new();
+        let mut limit = Some(1);
         if let Some(rev) = &self.revision {
-            resolved_rev = svn::resolve_revision_range(&creds, rev.as_str(), paths[0])?;
-            resolved_rev += ":0";
-            rev_vector.push(resolved_rev.as_str());
+            let resolved_rev = svn::resolve_revision_range(&creds, rev.as_str(), paths[0])?;
+            if resolved_rev.contains(':') {
+                limit = None;
+                rev_vector.push(resolved_rev);
+            } else {
+                rev_vector.push(resolved_rev + ":0");
+            }
         }
 
-        let log_entry = &svn::log(&creds, &paths, &rev_vector, true, Some(1), false, true)?[0];
-        util::show_commit(log_entry, !self.no_message, self.show_paths);
-        if self.show_diff {
-            println!();
-            let lines = svn::change_diff(&creds, paths[0], &log_entry.revision)?;
-            for line in &lines {
-                util::print_diff_line(line);
+        let rel_base = if self.relative_paths {
+            Some(util::working_copy_repo_path(&creds)?)
+        } else {
+            None
+        };
+
+        let rev_vector: Vec<&str> = rev_vector.iter().map(|r| r.as_str()).collect();
+        //  The commits are displayed as they arrive so that a large
+        //  range of revisions does not have to be fetched first.
+        let mut log_entries = svn::log_stream(&creds, &paths, &rev_vector, true, limit, false, true)?.peekable();
+        if log_entries.peek().is_none() {
+            return Err(General("No commits found".to_string()).into());
+        }
+
+        if let Some(dir) = &self.output_dir {
+            let log_entries = log_entries.collect::<Result<Vec<_>>>()?;
+            return self.write_files(&creds, paths[0], dir, &log_entries);
+        }
+
+        for log_entry in log_entries {
+            let log_entry = log_entry?;
+            match self.pretty {
+                Pretty::Default => self.show_default(&creds, paths[0], &log_entry, rel_base.as_deref())?,
+                Pretty::Email   => self.show_email(&creds, paths[0], &log_entry)?,
+            }
+        }
+        Ok(())
+    }
+
+    //  Write one file per commit.  All of the file names are checked
+    //  before anything is written so that we do not stop part way through.
+    fn write_files(&self, creds: &Option<Credentials>, path: &str, dir: &Path, log_entries: &[LogEntry]) -> Result<()> {
+        let file_paths: Vec<PathBuf> = log_entries
+            .iter()
+            .enumerate()
+            .map(|(index, log_entry)| match self.pretty {
+                Pretty::Default => dir.join(format!("r{}.diff", log_entry.revision)),
+                Pretty::Email   => dir.join(patch_file_name(log_entry, index + 1)),
+            })
+            .collect();
+
+        if !self.force {
+            if let Some(existing) = file_paths.iter().find(|p| p.exists()) {
+                let msg = format!("{} already exists, use --force to overwrite it", existing.display());
+                return Err(General(msg).into());
+            }
+        }
+
+        create_dir_all(dir)?;
+        for (log_entry, file_path) in log_entries.iter().zip(&file_paths) {
+            let diff = svn::change_diff(creds, path, &log_entry.revision, self.context)?;
+            let content = match self.pretty {
+                Pretty::Default => diff.iter().map(|l| format!("{}\n", l)).collect(),
+                Pretty::Email   => email_patch(log_entry, &diff),
+            };
+            File::create(file_path)?.write_all(content.as_bytes())?;
+            println!("{}", file_path.display());
+        }
+        println!("Wrote {} file(s) to {}", file_paths.len(), dir.display().to_string().blue());
+        Ok(())
+    }
+
+    fn show_default(&self, creds: &Option<Credentials>, path: &str, log_entry: &LogEntry, rel_base: Option<&str>) -> Result<()> {
+        let merged = if self.affected_by_merge {
+            svn::require_capability(creds, path, svn::Capability::Mergeinfo)?;
+            let root_url = svn::info(creds, path, None)?.root_url;
+            !svn::merged_revisions(creds, &root_url, &log_entry.revision)?.is_empty()
+        } else {
+            false
+        };
+        let path_style = if self.name_status {
+            util::PathStyle::NameStatus { null_terminated: self.null_terminated }
+        } else if self.show_paths || self.affected_by_merge {
+            util::PathStyle::Full
+        } else {
+            util::PathStyle::Hidden
+        };
+        let fields = util::HeaderFields { rev: !self.no_rev, author: !self.no_author, date: !self.no_date, relative: false };
+        let msg_style = if self.oneline {
+            util::MessageStyle::OneLine
+        } else if self.no_message {
+            util::MessageStyle::Hidden
+        } else if self.wrap {
+            util::MessageStyle::Wrap(util::terminal_width().unwrap_or(80))
+        } else {
+            util::MessageStyle::Full
+        };
+        util::show_commit(log_entry, &fields, msg_style, path_style, rel_base, merged);
+        if self.show_diff || self.stat {
+            let lines = svn::change_diff(creds, path, &log_entry.revision, self.context)?;
+            if self.stat {
+                println!();
+                util::print_diff_stat(&util::diff_stat(&lines));
+            }
+            if self.show_diff {
+                println!();
+                util::print_diff_lines(&lines, self.word_diff.then_some(self.diff_algorithm));
             }
         }
         Ok(())
     }
+
+    //  Write the commit as a patch with email headers to stdout.
+    fn show_email(&self, creds: &Option<Credentials>, path: &str, log_entry: &LogEntry) -> Result<()> {
+        let diff = svn::change_diff(creds, path, &log_entry.revision, self.context)?;
+        print!("{}", email_patch(log_entry, &diff));
+        Ok(())
+    }
+}
+
+fn email_patch(log_entry: &LogEntry, diff: &[String]) -> String {
+    let mut patch = String::new();
+    patch += &format!("From r{} Mon Sep 17 00:00:00 2001\n", log_entry.revision);
+    patch += &format!("From: {}\n", log_entry.author);
+    patch += &format!("Date: {}\n", util::display_svn_rfc2822(&log_entry.date));
+    patch += &format!("Subject: [PATCH r{}] {}\n", log_entry.revision, log_entry.msg_1st());
+    patch += "\n";
+    for line in log_entry.msg.iter().skip(1).skip_while(|l| l.trim().is_empty()) {
+        patch += &format!("{}\n", line);
+    }
+    patch += "---\n";
+    for path in &log_entry.paths {
+        patch += &format!(" {} {}\n", path.action, path.path);
+    }
+    patch += "\n";
+    for line in diff.iter() {
+        patch += &format!("{}\n", line);
+    }
+    patch
+}
+
+//  eg. 0001-r1234-fix-the-widget.patch
+fn patch_file_name(log_entry: &LogEntry, number: usize) -> String {
+    let re = Regex::new(r"[^A-Za-z0-9]+").expect("Error parsing patch name regex");
+    let subject = log_entry.msg_1st();
+    let slug = re.replace_all(&subject, "-");
+    let slug: String = slug.trim_matches('-').chars().take(52).collect();
+    if slug.is_empty() {
+        format!("{:04}-r{}.patch", number, log_entry.revision)
+    } else {
+        format!("{:04}-r{}-{}.patch", number, log_entry.revision, slug.to_lowercase())
+    }
 }
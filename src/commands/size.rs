@@ -0,0 +1,81 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use std::collections::BTreeMap;
+use crate::svn;
+use crate::util;
+
+/// Display the total size of the files in a repository path.
+///
+/// The sizes are taken from the repository so nothing is checked out.
+/// A subtotal is displayed for each subdirectory of the path followed
+/// by the grand total.
+#[derive(Debug, Parser)]
+#[command(
+    visible_aliases = ["du"],
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    The sizes are those of the files in the repository and do not include\n\
+    the administrative overhead of a working copy."
+)]
+pub struct Size {
+    /// The revision to examine.
+    ///
+    /// Revisions can be a numeric value or one of: HEAD, BASE, PREV, COMMITTED.
+    #[arg(short, long, value_name = "REV")]
+    revision: Option<String>,
+
+    /// Display sizes in bytes rather than human readable units.
+    #[arg(short, long)]
+    bytes: bool,
+
+    /// Path to working copy directory or URL.
+    #[arg(default_value = ".")]
+    path: String,
+}
+
+impl Size {
+    pub fn run(&mut self) -> Result<()> {
+        let creds = crate::auth::get_credentials()?;
+        let path = match self.path.strip_prefix("^/") {
+            Some(rel) => util::join_paths(svn::info(&creds, ".", None)?.root_url, rel),
+            None => self.path.clone(),
+        };
+        let revision = match &self.revision {
+            Some(rev) => Some(svn::resolve_revision(&creds, rev, &path)?),
+            None => None,
+        };
+
+        let list = svn::path_list_recursive(&creds, &path, revision.as_deref())?;
+
+        //  Files directly within the path are totaled under "."
+        let mut subtotals = BTreeMap::<String, (u64, usize)>::new();
+        for entry in list.entries.iter().filter(|e| e.kind == "file") {
+            let dir = match entry.name.split_once('/') {
+                Some((dir, _)) => format!("{}/", dir),
+                None => ".".to_string(),
+            };
+            let total = subtotals.entry(dir).or_insert((0, 0));
+            total.0 += entry.size.unwrap_or(0);
+            total.1 += 1;
+        }
+
+        let display = |bytes: u64| -> String {
+            if self.bytes { bytes.to_string() } else { util::human_size(bytes) }
+        };
+        let (total_bytes, total_files) = subtotals
+            .values()
+            .fold((0, 0), |(b, f), (bytes, files)| (b + bytes, f + files));
+        let name_width = subtotals.keys().map(|k| k.len()).max().unwrap_or(0).max(5);
+        let size_width = display(total_bytes).len();
+
+        for (dir, (bytes, files)) in &subtotals {
+            println!("{:>size_width$}  {:name_width$}  {} files", display(*bytes).yellow(), dir.blue(), files);
+        }
+        println!("{}", util::divider(size_width + name_width + 12));
+        println!("{:>size_width$}  {:name_width$}  {} files", display(total_bytes).green(), "total", total_files);
+        Ok(())
+    }
+}
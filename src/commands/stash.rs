@@ -14,6 +14,7 @@ use serde::{Deserialize, Serialize};
 use regex::Regex;
 use pathdiff::diff_paths;
 use std::env::current_dir;
+use crate::hooks::HookContext;
 
 mod push;
 mod pop;
@@ -113,7 +114,7 @@ fn stash_path() -> Result<PathBuf> {
 }
 
 
-fn stash_entries_file() -> Result<PathBuf> {
+pub(crate) fn stash_entries_file() -> Result<PathBuf> {
     Ok(stash_path()?.join("stash_entries.json"))
 }
 
@@ -129,7 +130,7 @@ const MODIFIED:    &str = "modified";
 // revision of the file when it was modified (for display only)
 // status will be one of: deleted, modified, added, unversioned
 #[derive(Clone, Debug, Serialize, Deserialize)]
-  struct StashItem {
+pub(crate) struct StashItem {
     path: String,
     revision: String,
     status: String,
@@ -162,7 +163,7 @@ impl StashItem {
 use crate::util::datetime_serializer;
 //  Stash entries saved to .svu/stash/stash_entries.json
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct StashFileEntry {
+pub(crate) struct StashFileEntry {
     branch: String,
     revision: String,
     description: String,
@@ -183,9 +184,18 @@ impl StashFileEntry {
         )
     }
 
+    fn hook_context(&self) -> HookContext {
+        HookContext {
+            revision: Some(self.revision.clone()),
+            branch: Some(self.branch.clone()),
+            paths: self.items.iter().map(|i| i.path.clone()).collect(),
+        }
+    }
+
 }
 
 fn load_stash_entries() -> Result<Vec<StashFileEntry>> {
+    let _lock = util::lock_data()?;
     let path = stash_entries_file()?;
     if path.is_file() {
         let reader = File::open(path)?;
@@ -197,6 +207,7 @@ fn load_stash_entries() -> Result<Vec<StashFileEntry>> {
 }
 
 fn add_stash_entry(stash: &StashFileEntry) -> Result<()> {
+    let _lock = util::lock_data()?;
     let mut entries = load_stash_entries()?;
 
     entries.insert(0, stash.clone());
@@ -205,12 +216,57 @@ fn add_stash_entry(stash: &StashFileEntry) -> Result<()> {
 }
 
 fn save_stash_entries(entries: &[StashFileEntry]) -> Result<()> {
+    let _lock = util::lock_data()?;
     let writer = File::create(stash_entries_file()?)?;
     Ok(serde_json::to_writer_pretty(writer, entries)?)
 }
 
+//  Used by the doctor command to find stash entries whose patch file is missing.
+//  Returns the index of each such entry.  If `remove` is true then those
+//  entries are removed from the stash entries file.
+pub(crate) fn missing_patch_entries(entries: &[StashFileEntry], remove: bool) -> Result<Vec<usize>> {
+    let dir = stash_path()?;
+    let missing: Vec<usize> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| !dir.join(&entry.patch_name).is_file())
+        .map(|(index, _)| index)
+        .collect();
+
+    if remove && !missing.is_empty() {
+        let kept: Vec<StashFileEntry> = entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !missing.contains(index))
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        save_stash_entries(&kept)?;
+    }
+    Ok(missing)
+}
+
 //  Runs `svn status` on the working copy root directory
 //  If we are not including unversioned items then we filter them out and build the list
+//  This does not alter the working copy.
+fn get_wc_items(wc_root: &Path, unversioned: bool) -> Result<Vec<StashItem>> {
+    let status = svn::status(".", Some(wc_root), false)?;
+    let mut items = Vec::<StashItem>::new();
+
+    for entry in status.entries {
+        if entry.item_status != NORMAL && (unversioned || entry.item_status != UNVERSIONED) {
+            let is_dir = wc_root.join(&entry.path).is_dir();
+            items.push(StashItem {
+                path: entry.path,
+                revision: entry.revision,
+                status: entry.item_status,
+                is_dir,
+            });
+        }
+    }
+    Ok(items)
+}
+
+//  Takes the items returned by get_wc_items() and returns the items to be stashed.
 //
 //  If we are including unversioned items then it is a bit more complicated:
 //  `svn status` will include unversioned directories but will not include their contents
@@ -220,29 +276,11 @@ fn save_stash_entries(entries: &[StashFileEntry]) -> Result<()> {
 //  At this point `svn status` will return all of the previously unversioned items as
 //  "added" so we must mark them as unversioned in our own item list.
 //  So this function will alter the working copy when unversioned items are being stashed.
-fn get_stash_items(wc_root: &Path, unversioned: bool) -> Result<Vec<StashItem>> {
-    fn get_wc_items(wc_root: &Path, unversioned: bool) -> Result<Vec<StashItem>> {
-        let status = svn::status(".", Some(wc_root))?;
-        let mut items = Vec::<StashItem>::new();
-
-        for entry in status.entries {
-            if entry.item_status != NORMAL && (unversioned || entry.item_status != UNVERSIONED) {
-                let is_dir = wc_root.join(&entry.path).is_dir();
-                items.push(StashItem {
-                    path: entry.path,
-                    revision: entry.revision,
-                    status: entry.item_status,
-                    is_dir,
-                });
-            }
-        }
-        Ok(items)
-    }
-
+fn get_stash_items(wc_root: &Path, wc_items: Vec<StashItem>, unversioned: bool) -> Result<Vec<StashItem>> {
     fn fixup_unversioned_items<'a>(
-        initial_items: &'a Vec<StashItem>,
+        initial_items: &'a [StashItem],
         wc_root: &Path
-    ) -> Result<Cow<'a, Vec<StashItem>>> {
+    ) -> Result<Cow<'a, [StashItem]>> {
         let unversioned_paths: Vec<String> = initial_items
             .iter()
             .filter(|i| i.status == UNVERSIONED)
@@ -287,9 +325,10 @@ fn get_stash_items(wc_root: &Path, unversioned: bool) -> Result<Vec<StashItem>>
         }
     }
 
-    match get_wc_items(wc_root, unversioned)? {
-        items if unversioned => Ok(fixup_unversioned_items(&items, wc_root)?.into_owned()),
-        items => Ok(items),
+    if unversioned {
+        Ok(fixup_unversioned_items(&wc_items, wc_root)?.into_owned())
+    } else {
+        Ok(wc_items)
     }
 }
 
@@ -309,7 +348,7 @@ fn apply_stash(stash: &StashFileEntry, wc_root: &Path, dry_run: bool) -> Result<
             let path   = &captures[3];
             let rel_path = match status {
                 ">" => path.to_string(), // Not a path
-                _   => diff_paths(&wc_root.join(path), &cwd)
+                _   => diff_paths(wc_root.join(path), &cwd)
                         .unwrap()
                         .to_string_lossy()
                         .to_string(),
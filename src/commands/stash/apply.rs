@@ -3,6 +3,7 @@ use clap::Parser;
 use super::*;
 use anyhow::Result;
 use crate::svn;
+use crate::hooks;
 
 /// Apply a stash to the working copy.
 /// 
@@ -31,8 +32,13 @@ impl Apply {
 
         if self.stash_id < stash_entries.len() {
             let stash = &stash_entries[self.stash_id];
-            apply_stash(stash, &wc_root, self.dry_run)?;
-            Ok(())
+            if self.dry_run {
+                apply_stash(stash, &wc_root, true)
+            } else {
+                hooks::with_hooks("stash-apply", &stash.hook_context(), || {
+                    apply_stash(stash, &wc_root, false)
+                })
+            }
         } else {
             let msg = format!(
                 "{} does not exist in the stash",
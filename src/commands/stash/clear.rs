@@ -4,6 +4,7 @@ use anyhow::Result;
 use crate::svn;
 use super::*;
 use std::fs::remove_file;
+use crate::hooks::{self, HookContext};
 
 /// Remove all stash entries.
 #[derive(Debug, Parser)]
@@ -19,21 +20,23 @@ impl Clear {
         let stash_entries_path = stash_entries_file()?;
         let stash_entries = load_stash_entries()?;
 
-        // Remove all of the associated patch files
-        for stash in &stash_entries {
-            let patch_file = stash_path()?.join(stash.patch_name.as_str());
-            remove_file(patch_file)?;
-        }
-        if stash_entries_path.is_file() {
-            remove_file(stash_entries_file()?)?;
-        }
-
         if stash_entries.is_empty() {
             println!("No stash entries to clear");
+            Ok(())
         } else {
-            println!("Cleared {} stash entries", stash_entries.len());
+            hooks::with_hooks("stash-clear", &HookContext::default(), || {
+                // Remove all of the associated patch files
+                let _lock = util::lock_data()?;
+                for stash in &stash_entries {
+                    let patch_file = stash_path()?.join(stash.patch_name.as_str());
+                    remove_file(patch_file)?;
+                }
+                if stash_entries_path.is_file() {
+                    remove_file(stash_entries_file()?)?;
+                }
+                println!("Cleared {} stash entries", stash_entries.len());
+                Ok(())
+            })
         }
-
-        Ok(())
     }
 }
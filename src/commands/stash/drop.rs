@@ -3,6 +3,7 @@ use clap::Parser;
 use anyhow::Result;
 use super::*;
 use std::fs::remove_file;
+use crate::hooks;
 
 
 /// Remove a stash entry.
@@ -23,11 +24,14 @@ impl Drop {
         let mut stash_entries = load_stash_entries()?;
         if self.stash_id < stash_entries.len() {
             let stash = stash_entries.remove(self.stash_id);
-            let patch_file = stash_path()?.join(stash.patch_name.as_str());
-            save_stash_entries(&stash_entries)?;
-            remove_file(patch_file)?;
-            println!("Dropped stash: {}", stash.summary_display());
-            Ok(())
+            hooks::with_hooks("stash-drop", &stash.hook_context(), || {
+                let _lock = util::lock_data()?;
+                let patch_file = stash_path()?.join(stash.patch_name.as_str());
+                save_stash_entries(&stash_entries)?;
+                remove_file(patch_file)?;
+                println!("Dropped stash: {}", stash.summary_display());
+                Ok(())
+            })
         }
         else {
             let msg = format!(
@@ -3,6 +3,7 @@ use clap::Parser;
 use super::*;
 use anyhow::Result;
 use std::fs::remove_file;
+use crate::hooks;
 
 /// Remove a stash entry and apply it to the working copy.
 #[derive(Debug, Parser)]
@@ -28,15 +29,19 @@ impl Pop {
         let mut stash_entries = load_stash_entries()?;
         if self.stash_id < stash_entries.len() {
             let stash = stash_entries.remove(self.stash_id);
-            apply_stash(&stash, &wc_root, self.dry_run)?;
-
-            if !self.dry_run {
-                let patch_file = stash_path()?.join(stash.patch_name.as_str());
-                save_stash_entries(&stash_entries)?;
-                remove_file(patch_file)?;
-                println!("Dropped stash: {}", stash.summary_display());
+            if self.dry_run {
+                apply_stash(&stash, &wc_root, true)
+            } else {
+                hooks::with_hooks("stash-pop", &stash.hook_context(), || {
+                    apply_stash(&stash, &wc_root, false)?;
+                    let _lock = util::lock_data()?;
+                    let patch_file = stash_path()?.join(stash.patch_name.as_str());
+                    save_stash_entries(&stash_entries)?;
+                    remove_file(patch_file)?;
+                    println!("Dropped stash: {}", stash.summary_display());
+                    Ok(())
+                })
             }
-            Ok(())
         } else {
             let msg = format!(
                 "{} does not exist in the stash",
@@ -5,6 +5,7 @@ use std::path::Path;
 use super::*;
 use anyhow::Result;
 use uuid::Uuid;
+use crate::hooks::{self, HookContext};
 
 /// Push the working copy to the stash and revert the working copy.
 #[derive(Debug, Args, Clone)]
@@ -47,9 +48,9 @@ impl Push {
 
         let wc_info = svn::workingcopy_info()?; // Make sure we are in a working copy.
         let wc_root = PathBuf::from(wc_info.wc_path.unwrap());
-        let items = get_stash_items(&wc_root, self.args.unversioned)?;
+        let wc_items = get_wc_items(&wc_root, self.args.unversioned)?;
 
-        if items.is_empty() {
+        if wc_items.is_empty() {
             println!("No local changes to save");
         } else {
             let (branch, revision) = svn::current_branch(&wc_root)?;
@@ -59,10 +60,35 @@ impl Push {
                 .clone()
                 .unwrap_or(get_log_message_1st(&wc_root)?);
 
+            let context = HookContext {
+                revision: Some(revision.clone()),
+                branch: Some(branch.clone()),
+                paths: wc_items.iter().map(|i| i.path.clone()).collect(),
+            };
+            hooks::with_hooks("stash-push", &context, || {
+                //  This may alter the working copy when stashing unversioned items.
+                let items = get_stash_items(&wc_root, wc_items, self.args.unversioned)?;
+                self.push_items(&wc_root, &items, branch, revision, description)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn push_items(
+        &self,
+        wc_root: &Path,
+        items: &[StashItem],
+        branch: String,
+        revision: String,
+        description: String,
+    ) -> Result<()> {
+        //  The patch and its entry are saved together while the data directory is locked.
+        let stash = {
+            let _lock = util::lock_data()?;
             let stash_path = stash_path()?;
             let patch_name = create_patch_name();
 
-            svn::create_patch(&stash_path.join(patch_name.as_str()), &wc_root)?;
+            svn::create_patch(&stash_path.join(patch_name.as_str()), wc_root)?;
 
             let stash = StashFileEntry {
                 branch,
@@ -70,35 +96,36 @@ impl Push {
                 description,
                 date: Local::now(),
                 patch_name,
-                items: items.clone(),
+                items: items.to_vec(),
             };
             add_stash_entry(&stash)?;
-
-            if !self.args.no_revert {
-                // Lastly we revert the working copy.
-                // We will explicitly revert all entries to ensure that the --remove-added flag is honored.
-                // For added/unversioned directories we do not need to revert any entries below them
-                // as these entries will be reverted recursively with their respective  directories.
-                let added_unversioned: Vec<StashItem> = items
-                    .iter()
-                    .filter(|i| i.is_dir && (i.status == ADDED || i.status == UNVERSIONED))
-                    .cloned()
-                    .collect();
-                let can_skip = |i: &StashItem| -> bool {
-                    added_unversioned
-                        .iter()
-                        .any(|p| i.path.starts_with(&p.path) && i.path != p.path)
-                };
-                let revert_paths: Vec<String> = items
+            stash
+        };
+
+        if !self.args.no_revert {
+            // Lastly we revert the working copy.
+            // We will explicitly revert all entries to ensure that the --remove-added flag is honored.
+            // For added/unversioned directories we do not need to revert any entries below them
+            // as these entries will be reverted recursively with their respective  directories.
+            let added_unversioned: Vec<StashItem> = items
+                .iter()
+                .filter(|i| i.is_dir && (i.status == ADDED || i.status == UNVERSIONED))
+                .cloned()
+                .collect();
+            let can_skip = |i: &StashItem| -> bool {
+                added_unversioned
                     .iter()
-                    .filter(|i| !can_skip(i))
-                    .map(|i| i.path.clone())
-                    .collect();
-                svn::revert(&revert_paths, "infinity", true, Some(&wc_root))?;
-            }
-
-            println!("Saved working copy state - {}", stash.summary_display());
+                    .any(|p| i.path.starts_with(&p.path) && i.path != p.path)
+            };
+            let revert_paths: Vec<String> = items
+                .iter()
+                .filter(|i| !can_skip(i))
+                .map(|i| i.path.clone())
+                .collect();
+            svn::revert(&revert_paths, "infinity", true, Some(wc_root))?;
         }
+
+        println!("Saved working copy state - {}", stash.summary_display());
         Ok(())
     }
 }
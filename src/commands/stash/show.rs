@@ -2,7 +2,7 @@
 use clap::Parser;
 use super::*;
 use anyhow::Result;
-use crate::util::{display_svn_datetime, print_diff_line};
+use crate::util::{diff_colors, display_svn_datetime, print_diff_line};
 use crate::svn;
 use std::env::current_dir;
 use std::io::{BufReader, BufRead};
@@ -62,7 +62,7 @@ impl Show {
                 let path = Path::new(pathname.as_str());
                 // First create the full path to the item relative to the working copy root.
                 // Then make that relative to the current working directory.
-                let rel_path = diff_paths(&wc_root.join(path), &cwd).unwrap();
+                let rel_path = diff_paths(wc_root.join(path), &cwd).unwrap();
                 let revision = match item.status.as_str() {
                     UNVERSIONED => "unversioned",
                     ADDED => "new",
@@ -81,7 +81,7 @@ impl Show {
                 println!();
                 let file = File::open(patch_file)?;
                 for line in BufReader::new(file).lines() {
-                    print_diff_line(line?.as_str());
+                    print_diff_line(line?.as_str(), diff_colors());
                 }
             }
 
@@ -0,0 +1,194 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use std::collections::BTreeMap;
+use crate::svn::{self, StatusEntry};
+use crate::util::SvError::*;
+
+/// Display the status of the working copy files.
+///
+/// In a large working copy use --group to see which areas have changed
+/// and then --group-path to list the changes within one of those areas.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    Groups are named by the leading directories of each path relative to PATH.\n\
+    Changes to the files directly within PATH are in the group named '.'"
+)]
+pub struct Status {
+    /// Summarize the changes by group
+    #[arg(short, long)]
+    group: bool,
+
+    /// Number of leading directories used to name each group
+    #[arg(
+        long,
+        value_name = "NUM",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..),
+    )]
+    group_depth: u32,
+
+    /// List the changes within a single group
+    ///
+    /// Implies --group.
+    #[arg(long, value_name = "GROUP")]
+    group_path: Option<String>,
+
+    /// Include the files that are ignored by the svn:ignore and
+    /// svn:global-ignores properties
+    #[arg(short, long)]
+    ignored: bool,
+
+    /// Display only the ignored files
+    #[arg(long, conflicts_with = "ignored")]
+    ignored_only: bool,
+
+    /// The working copy path [default: .]
+    #[arg(value_name = "PATH")]
+    path: Option<String>,
+}
+
+//  Totals of the changes within a group.
+#[derive(Default)]
+struct Totals {
+    chg: u32,
+    add: u32,
+    del: u32,
+    rep: u32,
+    unv: u32,
+    ign: u32,
+    other: u32,
+}
+
+impl Totals {
+    fn count(&self) -> u32 {
+        self.chg + self.add + self.del + self.rep + self.unv + self.ign + self.other
+    }
+
+    fn add_entry(&mut self, entry: &StatusEntry) {
+        match entry.item_status.as_str() {
+            "modified"    => self.chg += 1,
+            "added"       => self.add += 1,
+            "deleted"     => self.del += 1,
+            "replaced"    => self.rep += 1,
+            "unversioned" => self.unv += 1,
+            "ignored"     => self.ign += 1,
+            "normal" if entry.props_status == "modified" => self.chg += 1,
+            _             => self.other += 1,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} modified, {} added, {} deleted, {} replaced",
+            self.chg, self.add, self.del, self.rep
+        );
+        if self.unv > 0 {
+            summary.push_str(&format!(", {} unversioned", self.unv));
+        }
+        if self.ign > 0 {
+            summary.push_str(&format!(", {} ignored", self.ign));
+        }
+        if self.other > 0 {
+            summary.push_str(&format!(", {} other", self.other));
+        }
+        summary
+    }
+}
+
+impl Status {
+    pub fn run(&mut self) -> Result<()> {
+        svn::workingcopy_info()?;  // Make sure we are in a working copy.
+        let path = self.path.as_deref().unwrap_or(".");
+        let mut status = svn::status(path, None, self.ignored || self.ignored_only)?;
+        if self.ignored_only {
+            status.entries.retain(|e| e.item_status == "ignored");
+        }
+
+        if self.group || self.group_path.is_some() {
+            let mut groups: BTreeMap<String, Vec<&StatusEntry>> = BTreeMap::new();
+            for entry in &status.entries {
+                let name = self.group_name(&status.path, &entry.path);
+                groups.entry(name).or_default().push(entry);
+            }
+
+            match &self.group_path {
+                Some(group) => {
+                    let group = group.trim_end_matches('/');
+                    match groups.get(group) {
+                        Some(entries) => {
+                            let mut totals = Totals::default();
+                            entries.iter().for_each(|e| totals.add_entry(e));
+                            println!("{} {}", group.green(), totals.summary().cyan());
+                            for entry in entries {
+                                show_entry(entry);
+                            }
+                        }
+                        None => {
+                            let msg = format!("There are no changes in group '{}'", group);
+                            return Err(General(msg).into());
+                        }
+                    }
+                }
+                None => {
+                    let width = groups.keys().map(|k| k.len()).max().unwrap_or(0);
+                    for (name, entries) in &groups {
+                        let mut totals = Totals::default();
+                        entries.iter().for_each(|e| totals.add_entry(e));
+                        println!(
+                            "{:width$}  {:>5}  {}",
+                            name.green(),
+                            totals.count(),
+                            totals.summary().cyan(),
+                            width = width
+                        );
+                    }
+                }
+            }
+        } else {
+            for entry in &status.entries {
+                show_entry(entry);
+            }
+        }
+        Ok(())
+    }
+
+    //  The group is named by the leading directories of the path
+    //  relative to the status target.
+    fn group_name(&self, target: &str, path: &str) -> String {
+        let rel_path = if target == "." {
+            path
+        } else {
+            path.strip_prefix(target)
+                .map(|p| p.trim_start_matches('/'))
+                .unwrap_or(path)
+        };
+        let mut dirs: Vec<&str> = rel_path.split('/').collect();
+        dirs.pop();  // The last component is the changed item itself
+        dirs.truncate(self.group_depth as usize);
+        if dirs.is_empty() {
+            ".".to_string()
+        } else {
+            dirs.join("/")
+        }
+    }
+}
+
+//  Display an entry in the same short form used by svn status.
+fn show_entry(entry: &StatusEntry) {
+    let color = match entry.item_status.as_str() {
+        "added"       => "green",
+        "modified"    => "blue",
+        "replaced"    => "magenta",
+        "deleted" | "conflicted" | "missing" | "incomplete" | "obstructed" => "red",
+        "ignored"     => "bright black",
+        _             => "white",
+    };
+    let code = svn::item_status_code(&entry.item_status);
+    let props = svn::props_status_code(&entry.props_status);
+    println!("{}{}  {}", code.color(color), props.blue(), entry.path);
+}
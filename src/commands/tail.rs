@@ -0,0 +1,79 @@
+
+use anyhow::Result;
+use clap::Parser;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+use crate::auth::Credentials;
+use crate::svn;
+use crate::util;
+
+/// Display the most recent commits and optionally follow new ones.
+///
+/// The most recent commits are displayed oldest first, one per line.
+/// With --follow the repository is polled and new commits are displayed
+/// as they arrive.  Press Ctrl-C to stop following.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+)]
+pub struct Tail {
+    /// Number of commits to display
+    #[arg(short = 'n', long, value_name = "NUM", default_value_t = 10)]
+    lines: u32,
+
+    /// Keep polling and display new commits as they arrive
+    #[arg(short, long)]
+    follow: bool,
+
+    /// Seconds to wait between polls when following
+    #[arg(short, long, value_name = "SECS", default_value_t = 10, requires = "follow")]
+    interval: u64,
+
+    /// Limit commits to specific paths [default: .]
+    #[arg(value_name = "PATH", num_args = 0..)]
+    paths: Vec<String>,
+}
+
+impl Tail {
+    pub fn run(&mut self) -> Result<()> {
+        if self.paths.is_empty() {
+            self.paths.push(".".to_string());
+        }
+        let creds = crate::auth::get_credentials()?;
+
+        let mut entries = svn::log(&creds, &self.paths, &["HEAD:0".to_string()], true, Some(self.lines), false, false)?;
+        entries.reverse();
+        for entry in &entries {
+            println!("{}", util::oneline_commit(entry));
+        }
+
+        if self.follow {
+            let mut last_rev = self.head_revision(&creds)?;
+            loop {
+                std::io::stdout().flush()?;
+                thread::sleep(Duration::from_secs(self.interval));
+                last_rev = self.show_new_commits(&creds, last_rev)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn head_revision(&self, creds: &Option<Credentials>) -> Result<u64> {
+        Ok(svn::fresh_info(creds, &self.paths[0], Some("HEAD"))?.repo_rev.parse()?)
+    }
+
+    //  Display commits made after `last_rev` and return the new last revision.
+    fn show_new_commits(&self, creds: &Option<Credentials>, last_rev: u64) -> Result<u64> {
+        let head_rev = self.head_revision(creds)?;
+        if head_rev > last_rev {
+            let range = format!("{}:{}", last_rev + 1, head_rev);
+            let entries = svn::log(creds, &self.paths, &[range], true, None, false, false)?;
+            for entry in &entries {
+                println!("{}", util::oneline_commit(entry));
+            }
+        }
+        Ok(head_rev)
+    }
+}
@@ -0,0 +1,135 @@
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use serde::Serialize;
+use crate::svn::{self, SvnVersion};
+use crate::util;
+
+/// Display version information for svu, svn and the repository.
+///
+/// Reports the version of svu along with the version of the svn client that
+/// it is using and the repository access schemes supported by that client.
+/// If the PATH refers to a reachable repository then the details of that
+/// repository are also displayed.
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    help_template = crate::app::HELP_TEMPLATE,
+    after_help = "\
+    The svn client does not expose the version of the server.\n\
+    The repository details are omitted if the repository cannot be reached."
+)]
+pub struct Version {
+    /// Output the version information as JSON.
+    #[arg(long)]
+    json: bool,
+
+    /// Path to working copy or URL of a repository.
+    #[arg(default_value = ".")]
+    path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RepoDetails {
+    root: String,
+    uuid: String,
+    scheme: String,
+    head_rev: String,
+    scheme_supported: bool,
+    capabilities: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct VersionDetails {
+    svu: String,
+    svn: Option<SvnVersion>,
+    repository: Option<RepoDetails>,
+}
+
+impl Version {
+    pub fn run(&mut self) -> Result<()> {
+        let svn_version = svn::client_version().ok();
+        let repository = match &svn_version {
+            Some(version) => get_repo_details(&self.path, version),
+            None => None,
+        };
+        let details = VersionDetails {
+            svu: env!("CARGO_PKG_VERSION").to_string(),
+            svn: svn_version,
+            repository,
+        };
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&details)?);
+        } else {
+            show_details(&details);
+        }
+        Ok(())
+    }
+}
+
+//  The repository is optional so any failure here simply
+//  results in the repository details being omitted.
+fn get_repo_details(path: &str, version: &SvnVersion) -> Option<RepoDetails> {
+    let creds = if path.contains("://") {
+        None
+    } else {
+        crate::auth::get_credentials().ok().flatten()
+    };
+    let path_info = svn::info(&creds, path, None).ok()?;
+    let head_info = svn::info(&creds, &path_info.root_url, Some("HEAD")).ok()?;
+    let scheme = path_info
+        .root_url
+        .split("://")
+        .next()
+        .unwrap_or("")
+        .to_string();
+
+    let capabilities = svn::CAPABILITIES
+        .iter()
+        .filter(|c| svn::has_capability(&creds, path, **c).unwrap_or(false))
+        .map(|c| c.name().to_string())
+        .collect();
+
+    Some(RepoDetails {
+        capabilities,
+        root: path_info.root_url,
+        uuid: path_info.repo_uuid,
+        scheme_supported: version.schemes.contains(&scheme),
+        scheme,
+        head_rev: head_info.repo_rev,
+    })
+}
+
+fn show_details(details: &VersionDetails) {
+    const WIDTH: usize = 12;
+
+    println!("{:<WIDTH$}{}", "svu", details.svu.green());
+    match &details.svn {
+        Some(SvnVersion { version, revision, schemes }) => {
+            let rev = revision
+                .as_ref()
+                .map(|r| format!(" ({})", r))
+                .unwrap_or_default();
+            println!("{:<WIDTH$}{}{}", "svn", version.green(), rev);
+            println!("{:<WIDTH$}{}", "schemes", schemes.join(", "));
+        }
+        None => println!("{:<WIDTH$}{}", "svn", "<not found>".red()),
+    }
+
+    if let Some(repo) = &details.repository {
+        println!();
+        println!("Repository");
+        println!("{}", util::divider(40));
+        println!("{:<WIDTH$}{}", "root", repo.root.blue());
+        println!("{:<WIDTH$}{}", "uuid", repo.uuid);
+        println!("{:<WIDTH$}{}", "head", repo.head_rev.yellow());
+        if repo.scheme_supported {
+            println!("{:<WIDTH$}{}", "scheme", repo.scheme);
+        } else {
+            println!("{:<WIDTH$}{} {}", "scheme", repo.scheme, "(not supported by client)".red());
+        }
+        println!("{:<WIDTH$}{}", "capabilities", repo.capabilities.join(", "));
+    }
+}
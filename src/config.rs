@@ -0,0 +1,105 @@
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::PathBuf;
+use crate::util::{self, SvError::*};
+use crate::hooks;
+use crate::svn;
+
+//  Configuration values are saved in .svu/config.json as a flat
+//  map of dotted keys.  eg. { "hooks.pre-stash-push": "make check" }
+pub type ConfigMap = BTreeMap<String, String>;
+
+pub fn config_file() -> Result<PathBuf> {
+    util::data_directory().map(|dir| dir.join("config.json"))
+}
+
+pub fn load_config() -> Result<ConfigMap> {
+    let _lock = util::lock_data()?;
+    let path = config_file()?;
+    if path.is_file() {
+        let reader = File::open(path)?;
+        let config: ConfigMap = serde_json::from_reader(reader)?;
+        Ok(config)
+    } else {
+        Ok(ConfigMap::new())
+    }
+}
+
+pub fn save_config(config: &ConfigMap) -> Result<()> {
+    let _lock = util::lock_data()?;
+    util::write_json_atomic(&config_file()?, config)
+}
+
+//  Returns the value of a single configuration key.
+pub fn config_value(key: &str) -> Result<Option<String>> {
+    Ok(load_config()?.get(key).cloned())
+}
+
+//  Read a configuration value without running svn or locking the data directory.
+//  This is used at startup before the command runs so the working copy root is
+//  found by searching upward for the .svn directory.  Any failure results in None.
+pub fn peek_value(key: &str) -> Option<String> {
+    let reader = File::open(peek_data_file("config.json")?).ok()?;
+    let config: ConfigMap = serde_json::from_reader(reader).ok()?;
+    config.get(key).cloned()
+}
+
+//  Return the path of a file in the data directory if it exists,
+//  finding the directory in the same way as peek_value().
+pub fn peek_data_file(name: &str) -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    let wc_root = cwd.ancestors().find(|dir| dir.join(".svn").is_dir())?;
+    Some(util::existing_data_directory(wc_root)?.join(name)).filter(|path| path.is_file())
+}
+
+//  Verify that the key is one that svu understands so that
+//  a misspelled key is not silently ignored.
+pub fn validate_key(key: &str) -> Result<()> {
+    let valid = match key.split_once('.') {
+        Some(("hooks", hook)) => hooks::is_valid_hook(hook),
+        Some(("display", "timezone")) => true,
+        Some(("svn", "ignore-externals")) => true,
+        Some(("svn", "jobs")) => true,
+        Some(("svn", "timeout")) => true,
+        Some(("svn", "retries")) => true,
+        Some(("svn", "retry-delay")) => true,
+        Some(("capabilities", rest)) => rest
+            .rsplit_once('.')
+            .is_some_and(|(_, name)| svn::Capability::from_name(name).is_some()),
+        _ => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(General(format!("'{}' is not a valid configuration key", key)).into())
+    }
+}
+
+//  Verify that the value is acceptable for the key.
+pub fn validate_value(key: &str, value: &str) -> Result<()> {
+    match key {
+        "display.timezone" => util::validate_timezone(value),
+        "svn.ignore-externals" if value != "true" && value != "false" => {
+            Err(General(format!("'{}' must be true or false", key)).into())
+        }
+        "svn.jobs" if !value.parse::<u32>().is_ok_and(|n| (1..=crate::app::MAX_JOBS).contains(&n)) => {
+            Err(General(format!("'{}' must be a number from 1 to {}", key, crate::app::MAX_JOBS)).into())
+        }
+        "svn.timeout" if value.parse::<u64>().is_err() => {
+            Err(General(format!("'{}' must be a number of seconds (0 for no limit)", key)).into())
+        }
+        "svn.retries" if !value.parse::<u32>().is_ok_and(|n| n <= svn::MAX_RETRIES) => {
+            Err(General(format!("'{}' must be a number from 0 to {}", key, svn::MAX_RETRIES)).into())
+        }
+        "svn.retry-delay" if value.parse::<u64>().is_err() => {
+            Err(General(format!("'{}' must be a number of milliseconds", key)).into())
+        }
+        _ if key.starts_with("capabilities.") && value != "true" && value != "false" => {
+            Err(General(format!("'{}' must be true or false", key)).into())
+        }
+        _ => Ok(()),
+    }
+}
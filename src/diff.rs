@@ -0,0 +1,347 @@
+
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+//  The algorithm used to compare two sequences.
+//
+//  Myers produces the shortest possible edit script and is fast, but on
+//  repetitive input (blank lines, braces, common words) it will happily
+//  match unrelated occurrences which makes the output noisy.
+//
+//  Patience first anchors on elements that occur exactly once in both
+//  sequences and only then fills in the gaps using Myers.  The edit script
+//  may be slightly longer but it tends to follow the structure of the
+//  input and is easier to read when content has been moved or reordered.
+//
+//  The algorithm is selected with --diff-algorithm where svu compares
+//  content itself: `filerevs --by-content` and `--word-diff` in show and
+//  compare.  The line diffs of those commands are produced by `svn diff`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Algorithm {
+    #[default]
+    Myers,
+    Patience,
+}
+
+//  A single step in an edit script.
+//  Indexes refer to the old and new sequences respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+//  Return the edit script that transforms `old` into `new`.
+pub fn diff<T: Eq + Hash>(old: &[T], new: &[T], algorithm: Algorithm) -> Vec<DiffOp> {
+    let mut ops = Vec::new();
+    match algorithm {
+        Algorithm::Myers => myers(old, new, 0, 0, &mut ops),
+        Algorithm::Patience => patience(old, new, 0, 0, &mut ops),
+    }
+    ops
+}
+
+//  Return the number of (deleted, inserted) elements in an edit script.
+pub fn change_counts(ops: &[DiffOp]) -> (usize, usize) {
+    ops.iter().fold((0, 0), |(del, ins), op| match op {
+        DiffOp::Delete(_) => (del + 1, ins),
+        DiffOp::Insert(_) => (del, ins + 1),
+        DiffOp::Equal(..) => (del, ins),
+    })
+}
+
+//  Common prefix and suffix are matched directly which keeps
+//  the work done by both algorithms proportional to the changes.
+fn trim_common<T: Eq>(old: &[T], new: &[T]) -> (usize, usize) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix, suffix)
+}
+
+//  The classic O(ND) algorithm from Eugene Myers' paper
+//  "An O(ND) Difference Algorithm and Its Variations".
+//  Each `v` array from the forward pass is saved so that the
+//  path can be recovered by walking the trace backwards.
+fn myers<T: Eq>(old: &[T], new: &[T], old_base: usize, new_base: usize, ops: &mut Vec<DiffOp>) {
+    let (prefix, suffix) = trim_common(old, new);
+    for i in 0..prefix {
+        ops.push(DiffOp::Equal(old_base + i, new_base + i));
+    }
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    //  Walk backwards through the trace collecting the edits.
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let idx = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) { k + 1 } else { k - 1 };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(DiffOp::Equal(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(DiffOp::Insert(prev_y as usize));
+            } else {
+                edits.push(DiffOp::Delete(prev_x as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    let base_a = old_base + prefix;
+    let base_b = new_base + prefix;
+    ops.extend(edits.into_iter().rev().map(|op| match op {
+        DiffOp::Equal(i, j) => DiffOp::Equal(base_a + i, base_b + j),
+        DiffOp::Delete(i) => DiffOp::Delete(base_a + i),
+        DiffOp::Insert(j) => DiffOp::Insert(base_b + j),
+    }));
+
+    for i in 0..suffix {
+        ops.push(DiffOp::Equal(old_base + old.len() - suffix + i, new_base + new.len() - suffix + i));
+    }
+}
+
+//  Patience diff: match the elements that are unique in both sequences,
+//  keep the longest increasing subsequence of those matches as anchors
+//  and recurse on the regions between anchors.  Regions without any
+//  unique elements are handed to Myers.
+fn patience<T: Eq + Hash>(old: &[T], new: &[T], old_base: usize, new_base: usize, ops: &mut Vec<DiffOp>) {
+    let (prefix, suffix) = trim_common(old, new);
+    for i in 0..prefix {
+        ops.push(DiffOp::Equal(old_base + i, new_base + i));
+    }
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let anchors = unique_anchors(a, b);
+
+    if anchors.is_empty() {
+        myers(a, b, old_base + prefix, new_base + prefix, ops);
+    } else {
+        let (mut last_a, mut last_b) = (0, 0);
+        for (i, j) in anchors.into_iter().chain(std::iter::once((a.len(), b.len()))) {
+            patience(&a[last_a..i], &b[last_b..j], old_base + prefix + last_a, new_base + prefix + last_b, ops);
+            if i < a.len() {
+                ops.push(DiffOp::Equal(old_base + prefix + i, new_base + prefix + j));
+            }
+            last_a = i + 1;
+            last_b = j + 1;
+        }
+    }
+
+    for i in 0..suffix {
+        ops.push(DiffOp::Equal(old_base + old.len() - suffix + i, new_base + new.len() - suffix + i));
+    }
+}
+
+//  Returns the pairs of indexes of elements that occur exactly once in each
+//  sequence, reduced to the longest run that is increasing in both.
+fn unique_anchors<T: Eq + Hash>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let mut counts: HashMap<&T, (usize, usize, usize)> = HashMap::new();
+    for (i, item) in a.iter().enumerate() {
+        let entry = counts.entry(item).or_insert((0, 0, 0));
+        entry.0 += 1;
+        entry.2 = i;
+    }
+    for item in b {
+        if let Some(entry) = counts.get_mut(item) {
+            entry.1 += 1;
+        }
+    }
+
+    //  Unique pairs in the order they appear in `b`.
+    let pairs: Vec<(usize, usize)> = b
+        .iter()
+        .enumerate()
+        .filter_map(|(j, item)| match counts.get(item) {
+            Some((1, 1, i)) => Some((*i, j)),
+            _ => None,
+        })
+        .collect();
+
+    longest_increasing(&pairs)
+}
+
+//  Patience sorting to find the longest subsequence of pairs
+//  whose first element is increasing.  (The second is already sorted.)
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut piles: Vec<usize> = Vec::new();          // index into pairs of each pile top
+    let mut prev: Vec<Option<usize>> = vec![None; pairs.len()];
+
+    for (index, (i, _)) in pairs.iter().enumerate() {
+        let pile = piles.partition_point(|&top| pairs[top].0 < *i);
+        prev[index] = if pile > 0 { Some(piles[pile - 1]) } else { None };
+        if pile == piles.len() {
+            piles.push(index);
+        } else {
+            piles[pile] = index;
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut current = piles.last().copied();
+    while let Some(index) = current {
+        result.push(pairs[index]);
+        current = prev[index];
+    }
+    result.reverse();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [Algorithm; 2] = [Algorithm::Myers, Algorithm::Patience];
+
+    //  Check that the edit script visits every element of both sequences in
+    //  order, only pairs equal elements and so rebuilds `new` from `old`.
+    fn check_script(old: &[&str], new: &[&str], ops: &[DiffOp]) {
+        let (mut next_old, mut next_new) = (0, 0);
+        let mut rebuilt = Vec::new();
+        for op in ops {
+            match *op {
+                DiffOp::Equal(i, j) => {
+                    assert_eq!((i, j), (next_old, next_new), "out of order: {:?}", ops);
+                    assert_eq!(old[i], new[j]);
+                    rebuilt.push(old[i]);
+                    next_old += 1;
+                    next_new += 1;
+                }
+                DiffOp::Delete(i) => {
+                    assert_eq!(i, next_old, "out of order: {:?}", ops);
+                    next_old += 1;
+                }
+                DiffOp::Insert(j) => {
+                    assert_eq!(j, next_new, "out of order: {:?}", ops);
+                    rebuilt.push(new[j]);
+                    next_new += 1;
+                }
+            }
+        }
+        assert_eq!((next_old, next_new), (old.len(), new.len()));
+        assert_eq!(rebuilt, new);
+    }
+
+    fn counts(old: &[&str], new: &[&str], algorithm: Algorithm) -> (usize, usize) {
+        let ops = diff(old, new, algorithm);
+        check_script(old, new, &ops);
+        change_counts(&ops)
+    }
+
+    #[test]
+    fn empty_sides() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(diff::<&str>(&[], &[], algorithm), vec![]);
+            assert_eq!(counts(&[], &["a", "b"], algorithm), (0, 2));
+            assert_eq!(counts(&["a", "b"], &[], algorithm), (2, 0));
+        }
+    }
+
+    #[test]
+    fn identical_sequences() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(counts(&["a", "b", "c"], &["a", "b", "c"], algorithm), (0, 0));
+        }
+    }
+
+    #[test]
+    fn all_unique_lines() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(counts(&["a", "b", "c"], &["x", "y", "z"], algorithm), (3, 3));
+            assert_eq!(counts(&["a", "b", "c", "d"], &["a", "x", "c", "d"], algorithm), (1, 1));
+        }
+    }
+
+    #[test]
+    fn repeated_lines() {
+        let old = ["a", "b", "a", "b", "a"];
+        let new = ["b", "a", "b", "a", "b"];
+        //  Myers always finds the shortest edit script.
+        assert_eq!(counts(&old, &new, Algorithm::Myers), (1, 1));
+        //  With nothing unique to anchor on patience falls back to Myers.
+        assert_eq!(counts(&old, &new, Algorithm::Patience), (1, 1));
+
+        let old = ["{", "}", "{", "}"];
+        let new = ["{", "}", "{", "x", "}", "{", "}"];
+        for algorithm in ALGORITHMS {
+            assert_eq!(counts(&old, &new, algorithm), (0, 3));
+        }
+    }
+
+    #[test]
+    fn lines_moved_between_hunks() {
+        let old = ["fn foo() {", "    a();", "}", "", "fn bar() {", "    b();", "}"];
+        let new = ["fn bar() {", "    b();", "}", "", "fn foo() {", "    a();", "}"];
+        for algorithm in ALGORITHMS {
+            counts(&old, &new, algorithm);
+        }
+
+        //  Patience anchors on the unique lines so one of the functions is
+        //  kept whole rather than matching the braces of the other.
+        let ops = diff(&old, &new, Algorithm::Patience);
+        let kept: Vec<&str> = ops
+            .iter()
+            .filter_map(|op| match op {
+                DiffOp::Equal(i, _) => Some(old[*i]),
+                _ => None,
+            })
+            .collect();
+        assert!(
+            kept.starts_with(&["fn foo() {", "    a();", "}"]) || kept.starts_with(&["fn bar() {", "    b();", "}"]),
+            "unexpected matches: {:?}",
+            kept
+        );
+    }
+
+    #[test]
+    fn longest_increasing_anchors() {
+        assert_eq!(longest_increasing(&[]), vec![]);
+        assert_eq!(longest_increasing(&[(2, 0), (0, 1), (1, 2), (3, 3)]), vec![(0, 1), (1, 2), (3, 3)]);
+    }
+}
@@ -0,0 +1,110 @@
+
+use anyhow::Result;
+use std::process::Command;
+use colored::*;
+use crate::util::SvError::*;
+use crate::{config, svn};
+
+//  Operations that modify the working copy or the svu configuration.
+//  Each operation may have both a `pre-` hook and a `post-` hook
+//  configured with the keys `hooks.pre-<operation>` and `hooks.post-<operation>`.
+//  `doctor --fix` has no hooks because it must be able to repair a corrupt
+//  configuration file, which is where the hooks themselves are configured.
+pub const HOOK_OPERATIONS: &[&str] = &[
+    "stash-push",
+    "stash-pop",
+    "stash-apply",
+    "stash-drop",
+    "stash-clear",
+    "update",
+    "prefix",
+    "config",
+    "ignore",
+    "migrate",
+];
+
+pub fn is_valid_hook(name: &str) -> bool {
+    name.strip_prefix("pre-")
+        .or(name.strip_prefix("post-"))
+        .map(|operation| HOOK_OPERATIONS.contains(&operation))
+        .unwrap_or(false)
+}
+
+//  Details of the operation that are passed to the
+//  hook command via environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub revision: Option<String>,
+    pub branch: Option<String>,
+    pub paths: Vec<String>,
+}
+
+//  Run the action surrounded by the pre and post hooks of the operation.
+//  If the pre hook fails then the action is not run.
+//  A failing post hook is only reported since by then the
+//  operation has already been performed.
+pub fn with_hooks<T, F>(operation: &str, context: &HookContext, action: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T>,
+{
+    let enabled = !crate::app::global_options().no_hooks && in_working_copy();
+
+    if enabled {
+        run_hook(&format!("pre-{}", operation), context)?;
+    }
+
+    let result = action()?;
+
+    if enabled {
+        if let Err(e) = run_hook(&format!("post-{}", operation), context) {
+            eprintln!("{} {}", "warning:".yellow(), e);
+        }
+    }
+    Ok(result)
+}
+
+//  Hooks are configured in the .svu directory of a working copy so
+//  there are none when svu is run outside of one (eg. by migrate).
+fn in_working_copy() -> bool {
+    std::env::current_dir().is_ok_and(|cwd| cwd.ancestors().any(|dir| dir.join(".svn").is_dir()))
+}
+
+//  Hooks are run from the working copy root directory using the shell.
+fn run_hook(name: &str, context: &HookContext) -> Result<()> {
+    if let Some(command) = config::config_value(&format!("hooks.{}", name))? {
+        let wc_info = svn::workingcopy_info()?;
+        let wc_root = wc_info.wc_path.unwrap();
+        let mut cmd = shell_command(&command);
+
+        cmd.current_dir(&wc_root)
+            .env("SVU_HOOK", name)
+            .env("SVU_WC_ROOT", &wc_root)
+            .env("SVU_REVISION", context.revision.as_deref().unwrap_or(""))
+            .env("SVU_BRANCH", context.branch.as_deref().unwrap_or(""))
+            .env("SVU_PATHS", context.paths.join("\n"));
+
+        let status = cmd.status()?;
+        svn::clear_info_cache();
+        if !status.success() {
+            let code = status
+                .code()
+                .map(|c| c.to_string())
+                .unwrap_or("none".to_string());
+            let msg = format!("The {} hook failed (exit code {}): {}", name, code, command);
+            return Err(General(msg).into());
+        }
+    }
+    Ok(())
+}
+
+fn shell_command(command: &str) -> Command {
+    let mut cmd;
+    if cfg!(windows) {
+        cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+    } else {
+        cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+    }
+    cmd
+}
@@ -1,17 +1,20 @@
 
 use clap::Parser;
 use std::process;
-use crate::app::{Commands, Run};
+use crate::app::{App, Run};
 
 mod app;
 mod util;
 mod commands;
 mod svn;
 mod auth;
+mod config;
+mod hooks;
+mod diff;
 
 
 fn main() {
-    match Commands::parse().run() {
+    match App::parse().run() {
         Ok(_) => {
             process::exit(0);
         }
@@ -1,17 +1,23 @@
 
 use std::env;
-use std::io::Write;
-use std::sync::OnceLock;
-use std::process::{Command, Output};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::{Mutex, OnceLock};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::fs::OpenOptions;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::path::{Path, PathBuf};
 use std::fs::File;
+use std::collections::HashMap;
 use chrono::{DateTime, Local};
 use roxmltree::{Document, Node};
 use anyhow::Result;
 use crate::auth::Credentials;
 use crate::util::SvError::*;
-use crate::util::{parse_svn_date_opt, null_date, data_directory};
+use crate::util::{self, parse_svn_date_opt, null_date, data_directory, join_paths, is_binary_mime_type};
 use regex::Regex;
+use colored::*;
 use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 //  Get the name of the svn command to run
@@ -20,7 +26,6 @@ fn svn_cmd() -> &'static String {
     static SVN_CMD: OnceLock<String> = OnceLock::new();
     SVN_CMD.get_or_init(|| {
         env::var("SVU_SVN")
-            .map(|s| s.clone())
             .unwrap_or("svn".to_string())
     })
 }
@@ -62,7 +67,6 @@ impl LogEntry {
 
 #[derive(Debug, Clone)]
 pub struct SvnInfo {
-    pub path: String,
     pub repo_rev: String,
     pub kind: String,
     pub size: Option<u64>,
@@ -88,7 +92,6 @@ pub struct ListEntry {
 
 #[derive(Debug, Clone)]
 pub struct SvnList {
-    pub path: String,
     pub entries: Vec<ListEntry>
 }
 
@@ -106,12 +109,65 @@ pub struct SvnStatus {
     pub entries: Vec<StatusEntry>,
 }
 
+//  A path listed by `svn diff --summarize`.
+#[derive(Debug, Clone)]
+pub struct DiffSummary {
+    pub url: String,
+    pub item_status: String,
+    pub props_status: String,
+}
+
+//  The status words used in the xml output are the same in every locale.
+//  These map them to the single letter codes that svn displays.
+pub fn item_status_code(item_status: &str) -> &'static str {
+    match item_status {
+        "added"       => "A",
+        "deleted"     => "D",
+        "modified"    => "M",
+        "replaced"    => "R",
+        "conflicted"  => "C",
+        "unversioned" => "?",
+        "missing"     => "!",
+        "incomplete"  => "!",
+        "obstructed"  => "~",
+        "ignored"     => "I",
+        "external"    => "X",
+        _             => " ",
+    }
+}
+
+pub fn props_status_code(props_status: &str) -> &'static str {
+    match props_status {
+        "modified"   => "M",
+        "conflicted" => "C",
+        _            => " ",
+    }
+}
+
+//  Version details reported by the svn client
+#[derive(Debug, Clone, Serialize)]
+pub struct SvnVersion {
+    pub version: String,
+    pub revision: Option<String>,
+    pub schemes: Vec<String>,
+}
+
+//  Displayed for commits that have no svn:author property such as revision 0.
+//  This matches the text used by svn itself.
+pub const NO_AUTHOR: &str = "(no author)";
+
+//  The svn subcommands that accept --ignore-externals.
+//  It is added to these when the global --ignore-externals option is in effect.
+pub const IGNORE_EXTERNALS_COMMANDS: &[&str] = &["update", "switch", "checkout", "export", "status"];
+
 // Object used to simplify running svn commands
 #[derive(Debug, Clone)]
 pub struct SvnCmd {
     cwd: Option<PathBuf>,
     name: String,
     args: Vec<String>,
+    creds: Option<Credentials>,
+    timeout: Option<Duration>,
 }
 
 impl SvnCmd {
@@ -123,6 +179,8 @@ impl SvnCmd {
             cwd: None,
             name: name.as_ref().to_string(),
             args: vec![],
+            creds: None,
+            timeout: None,
         }
     }
 
@@ -135,10 +193,14 @@ impl SvnCmd {
 
 
     pub fn with_creds(&mut self, creds: &Option<Credentials>) -> &mut Self {
-        if let Some(Credentials(username, password)) = creds {
-            self.arg(format!("--username={}", username));
-            self.arg(format!("--password={}", password));
-        }
+        self.creds = creds.clone();
+        self
+    }
+
+    //  Apply the configured timeout (see svn_timeout()) to a command that
+    //  contacts the repository so that an unreachable server cannot hang svu.
+    pub fn with_timeout(&mut self) -> &mut Self {
+        self.timeout = svn_timeout();
         self
     }
 
@@ -180,16 +242,265 @@ impl SvnCmd {
         self
     }
 
+    //  If svn rejects the credentials part way through a long running
+    //  operation (they may have expired) we re-acquire them once and
+    //  retry the command before giving up.
+    //
+    //  Commands that only read from the repository are retried with
+    //  an increasing delay when they fail with a network error.
     pub fn run(&mut self) -> Result<Output> {
+        let creds = crate::auth::renewed_credentials().unwrap_or_else(|| self.creds.clone());
+        let mut output = self.execute(&creds)?;
+        if RETRY_COMMANDS.contains(&self.name.as_str()) {
+            let (retries, base_delay) = svn_retries();
+            for attempt in 0..retries {
+                if output.status.success() || !is_transient_failure(&output) {
+                    break;
+                }
+                let delay = base_delay * 2u32.pow(attempt);
+                eprintln!(
+                    "{} svn {} failed with a network error, retrying in {:?}",
+                    "warning:".yellow(),
+                    self.name,
+                    delay
+                );
+                thread::sleep(delay);
+                output = self.execute(&creds)?;
+            }
+        }
+        if !output.status.success() && is_auth_failure(&output) && crate::auth::reacquire_credentials()? {
+            let creds = crate::auth::renewed_credentials().unwrap_or_else(|| self.creds.clone());
+            self.execute(&creds)
+        } else {
+            Ok(output)
+        }
+    }
+
+    //  Start the command with its output piped back to us so that it can
+    //  be processed as it is produced.  Unlike run() the credentials are
+    //  not re-acquired if they are rejected.
+    pub fn spawn(&mut self) -> Result<Child> {
+        let creds = crate::auth::renewed_credentials().unwrap_or_else(|| self.creds.clone());
+        let child = self.command(&creds)?
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        Ok(child)
+    }
+
+    fn execute(&self, creds: &Option<Credentials>) -> Result<Output> {
+        //  Any command that is not known to be read only may change the
+        //  working copy so the cached info can no longer be trusted.
+        if !RETRY_COMMANDS.contains(&self.name.as_str()) && self.name != "status" {
+            clear_info_cache();
+        }
+        let mut cmd = self.command(creds)?;
+        match self.timeout {
+            Some(timeout) => output_with_timeout(&mut cmd, &self.name, timeout),
+            None => Ok(cmd.output()?),
+        }
+    }
+
+    fn command(&self, creds: &Option<Credentials>) -> Result<Command> {
+        let options = crate::app::global_options();
+        let mut args = self.args.clone();
+        if options.ignore_externals && IGNORE_EXTERNALS_COMMANDS.contains(&self.name.as_str()) {
+            args.push("--ignore-externals".to_string());
+        }
+        if options.non_interactive {
+            args.push("--non-interactive".to_string());
+        }
+        if let Some(dir) = svn_config_dir() {
+            args.push(format!("--config-dir={}", dir.display()));
+        }
+        if let Some(path) = &options.record {
+            record_command(path, self.cwd.as_deref(), &self.name, creds, &args)?;
+        }
+
         let mut cmd = Command::new(svn_cmd());
         if let Some(dir) = &self.cwd {
             cmd.current_dir(dir);
         }
         cmd.arg(&self.name);
-        cmd.args(&self.args);
+        if let Some(Credentials(username, password)) = creds {
+            cmd.arg(format!("--username={}", username));
+            cmd.arg(format!("--password={}", password));
+        }
+        cmd.args(&args);
+        Ok(cmd)
+    }
+}
+
+//  The number of seconds that commands contacting the repository may run is
+//  taken from the SVU_SVN_TIMEOUT environment variable or the svn.timeout
+//  configuration key.  Zero or no setting means that there is no limit.
+fn svn_timeout() -> Option<Duration> {
+    static TIMEOUT: OnceLock<Option<Duration>> = OnceLock::new();
+    *TIMEOUT.get_or_init(|| {
+        env::var("SVU_SVN_TIMEOUT")
+            .ok()
+            .filter(|t| !t.is_empty())
+            .or_else(|| crate::config::peek_value("svn.timeout"))
+            .and_then(|t| t.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    })
+}
+
+//  Run the command and collect its output, killing it if it
+//  has not finished within the timeout.
+fn output_with_timeout(cmd: &mut Command, name: &str, timeout: Duration) -> Result<Output> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    //  Read the output on separate threads so that svn can never block writing to a full pipe.
+    let read_all = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut text = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut text);
+            }
+            text
+        })
+    };
+    let stdout = read_all(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = read_all(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timeout_error(name, timeout));
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn timeout_error(name: &str, timeout: Duration) -> anyhow::Error {
+    let msg = format!(
+        "svn {} did not finish within {} seconds (see svn.timeout and SVU_SVN_TIMEOUT)",
+        name,
+        timeout.as_secs()
+    );
+    General(msg).into()
+}
+
+//  The svn subcommands that only read from the repository and
+//  can safely be run again after a network error.
+const RETRY_COMMANDS: &[&str] = &["info", "log", "list", "cat", "diff", "blame", "pget", "propget", "proplist", "mergeinfo"];
+
+//  Errors that indicate a problem reaching the repository rather than a problem
+//  with the request itself (such as a path that does not exist).
+const TRANSIENT_ERRORS: &[&str] = &[
+    "E170013",  // Unable to connect to a repository
+    "E175002",  // Connection failure (http)
+    "E175012",  // Connection timed out (http)
+    "E210002",  // Network connection closed unexpectedly (svn://)
+    "E670008",  // Unknown hostname (DNS failure)
+    "E730054",  // Connection reset by peer
+    "E730060",  // Connection timed out
+    "E730061",  // Connection refused
+    "E731001",  // Host not found
+];
+
+//  Returns true if svn failed because the repository could not be reached.
+pub fn is_transient_failure(output: &Output) -> bool {
+    let text = String::from_utf8_lossy(&output.stderr);
+    TRANSIENT_ERRORS.iter().any(|code| text.contains(code))
+}
+
+//  The number of times to retry a command that failed with a network error
+//  and the delay before the first retry, which doubles with each attempt.
+//  Taken from SVU_SVN_RETRIES and SVU_SVN_RETRY_DELAY (milliseconds) or the
+//  svn.retries and svn.retry-delay configuration keys.
+fn svn_retries() -> (u32, Duration) {
+    const DEFAULT_RETRIES: u32 = 2;
+    const DEFAULT_DELAY_MILLIS: u64 = 500;
+    static RETRIES: OnceLock<(u32, Duration)> = OnceLock::new();
+    *RETRIES.get_or_init(|| {
+        let setting = |env_name: &str, key: &str| {
+            env::var(env_name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .or_else(|| crate::config::peek_value(key))
+        };
+        let retries = setting("SVU_SVN_RETRIES", "svn.retries")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_RETRIES)
+            .min(MAX_RETRIES);
+        let delay = setting("SVU_SVN_RETRY_DELAY", "svn.retry-delay")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_DELAY_MILLIS);
+        (retries, Duration::from_millis(delay))
+    })
+}
+
+pub const MAX_RETRIES: u32 = 10;
 
-        Ok(cmd.output()?)
+//  The config dir from the command line takes precedence over the environment.
+fn svn_config_dir() -> Option<PathBuf> {
+    crate::app::global_options()
+        .svn_config_dir
+        .clone()
+        .or_else(|| std::env::var_os("SVU_SVN_CONFIG_DIR").filter(|d| !d.is_empty()).map(PathBuf::from))
+}
+
+//  Append an svn command to the script given with the global --record option.
+fn record_command(path: &Path, cwd: Option<&Path>, name: &str, creds: &Option<Credentials>, args: &[String]) -> Result<()> {
+    static RECORD: Mutex<Option<File>> = Mutex::new(None);
+    let mut record = RECORD.lock().unwrap();
+
+    if record.is_none() {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            //  Some commands are expected to fail (eg. probing for a working copy)
+            //  so the script does not stop on errors.
+            writeln!(file, "#! /usr/bin/env sh")?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(std::fs::Permissions::from_mode(0o755))?;
+            }
+        }
+        writeln!(file, "\n# {}", env::args().map(|a| util::shell_quote(&a)).collect::<Vec<String>>().join(" "))?;
+        *record = Some(file);
     }
+
+    if let Some(file) = record.as_mut() {
+        writeln!(file, "{}", recorded_line(cwd, name, creds, args))?;
+    }
+    Ok(())
+}
+
+//  The line of the --record script that runs an svn command.
+//  The credentials are replaced by the SVU_USERNAME and SVU_PASSWORD
+//  environment variables so that they are not written to the file.
+fn recorded_line(cwd: Option<&Path>, name: &str, creds: &Option<Credentials>, args: &[String]) -> String {
+    let mut line = vec![svn_cmd().clone(), name.to_string()];
+    if creds.is_some() {
+        line.push("--username=\"$SVU_USERNAME\"".to_string());
+        line.push("--password=\"$SVU_PASSWORD\"".to_string());
+    }
+    line.extend(args.iter().map(|a| util::shell_quote(a)));
+    let line = line.join(" ");
+
+    match cwd {
+        Some(dir) => format!("(cd {} && {})", util::shell_quote(&dir.to_string_lossy()), line),
+        None => line,
+    }
+}
+
+//  Returns true if svn failed because the repository rejected our credentials.
+pub fn is_auth_failure(output: &Output) -> bool {
+    let text = String::from_utf8_lossy(&output.stderr);
+    text.contains("Authentication failed") || text.contains("E215004")
 }
 
 
@@ -363,7 +674,7 @@ fn parse_svn_info(text: &str) -> Result<Vec<SvnInfo>> {
             if let Some(commit) = get_child(&entry, "commit") {
                 (
                     get_attr(&commit, "revision"),
-                    get_child_text_or(&commit, "author", "n/a"),
+                    get_child_text_or(&commit, "author", NO_AUTHOR),
                     parse_svn_date_opt(get_child_text(&commit, "date")),
                 )
             } else {
@@ -373,7 +684,6 @@ fn parse_svn_info(text: &str) -> Result<Vec<SvnInfo>> {
         let wc_info = get_child(&entry, "wc-info");
 
         let entry = SvnInfo {
-            path: get_attr(&entry, "path"),
             repo_rev: get_attr(&entry, "revision"),
             kind: get_attr(&entry, "kind"),
             size: get_attr(&entry, "size").parse::<u64>().ok(),
@@ -391,13 +701,67 @@ fn parse_svn_info(text: &str) -> Result<Vec<SvnInfo>> {
     Ok(entries)
 }
 
+//  The results of svn::info() keyed by path and revision.  Many commands
+//  look up the same path several times so the results are kept for the
+//  life of the process.  The cache is cleared whenever an svn command that
+//  may modify the working copy is run.  Lookups that can return a different
+//  result each time, such as the HEAD revision, are never cached.
+type InfoCache = HashMap<(String, Option<String>), SvnInfo>;
+static INFO_CACHE: Mutex<Option<InfoCache>> = Mutex::new(None);
+
+//  This must also be called after running anything (such as a hook or
+//  a bisect run command) that may have changed the working copy.
+pub fn clear_info_cache() {
+    if let Some(cache) = INFO_CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        cache.clear();
+    }
+}
+
+//  Return true if the info for the path and revision can change while
+//  svu is running.  A url without a revision refers to HEAD.
+fn is_floating_info(path: &str, revision: Option<&str>) -> bool {
+    let is_head = |rev: &str| rev.to_uppercase().starts_with("HEAD");
+    let peg_rev = path.rsplit_once('@').map(|(_, peg)| peg).filter(|peg| !peg.contains('/'));
+    match revision.or(peg_rev) {
+        Some(rev) => is_head(rev),
+        None => path.contains("://") || path.starts_with("^/"),
+    }
+}
+
 pub fn info<'a>(
     creds: &Option<Credentials>,
     path: &'a str,
     revision: Option<&'a str>,
 ) -> Result<SvnInfo> {
+    cached_info(&INFO_CACHE, path, revision, || fresh_info(creds, path, revision))
+}
+
+fn cached_info<F>(cache: &Mutex<Option<InfoCache>>, path: &str, revision: Option<&str>, fetch: F) -> Result<SvnInfo>
+where
+    F: FnOnce() -> Result<SvnInfo>,
+{
+    if is_floating_info(path, revision) {
+        return fetch();
+    }
+    let key = (path.to_string(), revision.map(|r| r.to_string()));
+    if let Some(info) = cache.lock().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|c| c.get(&key)) {
+        return Ok(info.clone());
+    }
+    let info = fetch()?;
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(key, info.clone());
+    Ok(info)
+}
+
+//  Run `svn info` without consulting the cache.
+//  Use this when polling for changes.
+pub fn fresh_info(creds: &Option<Credentials>, path: &str, revision: Option<&str>) -> Result<SvnInfo> {
     let output = SvnCmd::new("info")
         .with_creds(creds)
+        .with_timeout()
         .arg("--xml")
         .opt_arg(&revision.map(|r| format!("--revision={}", r)))
         .arg(path)
@@ -422,6 +786,7 @@ where
 {
     let output = SvnCmd::new("info")
         .with_creds(creds)
+        .with_timeout()
         .arg("--xml")
         .opt_arg(&revision.map(|r| format!("--revision={}", r)))
         .args(paths)
@@ -463,21 +828,204 @@ fn get_log_entry_paths(log_entry: &Node) -> Vec<LogPath> {
     paths
 }
 
-fn parse_svn_log(text: &str) -> Result<Vec<LogEntry>> {
-    let mut entries = vec![];
+fn parse_log_entry(log_entry: &Node) -> LogEntry {
+    LogEntry {
+        revision: get_attr(log_entry, "revision"),
+        author: get_child_text_or(log_entry, "author", NO_AUTHOR),
+        date: parse_svn_date_opt(get_child_text(log_entry, "date")),
+        msg: get_child_text_or(log_entry, "msg", "").split('\n').map(|s| s.to_owned()).collect(),
+        paths: get_log_entry_paths(log_entry)
+    }
+}
+
+pub(crate) fn parse_svn_log(text: &str) -> Result<Vec<LogEntry>> {
     let doc = Document::parse(text)?;
-    for log_entry in doc.descendants().filter(|n| n.has_tag_name("logentry")) {
-
-        let entry = LogEntry {
-            revision: get_attr(&log_entry, "revision"),
-            author: get_child_text_or(&log_entry, "author", "n/a"),
-            date: parse_svn_date_opt(get_child_text(&log_entry, "date")),
-            msg: get_child_text_or(&log_entry, "msg", "").split('\n').map(|s| s.to_owned()).collect(),
-            paths: get_log_entry_paths(&log_entry)
+    let entries = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("logentry"))
+        .map(|n| parse_log_entry(&n))
+        .collect();
+    Ok(entries)
+}
+
+//  The entries of a running `svn log --xml` command.
+//  Each entry is parsed as soon as its closing tag has been read so that
+//  the first entries of a large range can be displayed right away and the
+//  whole of the output is never held in memory.
+//
+//  With a timeout (see svn_timeout()) svn is killed if it produces no output
+//  for that long.  A network failure before the first entry is retried as
+//  by SvnCmd::run().  Dropping the stream before the end stops svn.
+pub struct LogStream {
+    cmd: SvnCmd,
+    child: Child,
+    lines: Receiver<std::io::Result<String>>,
+    stderr: Option<thread::JoinHandle<Vec<u8>>>,
+    buffer: String,
+    attempt: u32,
+    started: bool,
+    done: bool,
+}
+
+impl LogStream {
+    fn start(mut cmd: SvnCmd) -> Result<Self> {
+        let (child, lines, stderr) = Self::spawn(&mut cmd)?;
+        Ok(LogStream { cmd, child, lines, stderr, buffer: String::new(), attempt: 0, started: false, done: false })
+    }
+
+    //  Start svn reading its output on separate threads so that it can never
+    //  block writing to a full pipe and so that reads can time out.
+    #[allow(clippy::type_complexity)]
+    fn spawn(cmd: &mut SvnCmd) -> Result<(Child, Receiver<std::io::Result<String>>, Option<thread::JoinHandle<Vec<u8>>>)> {
+        let mut child = cmd.spawn()?;
+        let stdout = child.stdout.take().ok_or_else(|| General("Cannot read the output of svn log".to_string()))?;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                let mut line = String::new();
+                match reader.read_line(&mut line) {
+                    Ok(0) => break,
+                    //  The stream has been dropped.
+                    Ok(_) if sender.send(Ok(line)).is_err() => break,
+                    Ok(_) => (),
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        break;
+                    }
+                }
+            }
+        });
+        let stderr = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut text = Vec::new();
+                let _ = stderr.read_to_end(&mut text);
+                text
+            })
+        });
+        Ok((child, lines, stderr))
+    }
+
+    //  Remove the next complete <logentry> element from the buffer.
+    fn next_element(&mut self) -> Option<String> {
+        const START: &str = "<logentry";
+        const END: &str = "</logentry>";
+        let start = self.buffer.find(START)?;
+        let end = self.buffer[start..].find(END)? + start + END.len();
+        let element = self.buffer[start..end].to_string();
+        self.buffer.drain(..end);
+        Some(element)
+    }
+
+    //  Wait for the next line of output.
+    //  Returns None once svn has closed its output.
+    fn next_line(&mut self) -> Result<Option<String>> {
+        let received = match self.cmd.timeout {
+            Some(timeout) => self.lines.recv_timeout(timeout),
+            None => self.lines.recv().map_err(|_| RecvTimeoutError::Disconnected),
         };
-        entries.push(entry);
+        match received {
+            Ok(line) => Ok(Some(line?)),
+            Err(RecvTimeoutError::Disconnected) => Ok(None),
+            Err(RecvTimeoutError::Timeout) => Err(timeout_error(&self.cmd.name, self.cmd.timeout.unwrap_or_default())),
+        }
     }
-    Ok(entries)
+
+    //  Wait for svn to exit and return its status and stderr.
+    fn finish(&mut self) -> Result<(ExitStatus, Vec<u8>)> {
+        let status = self.child.wait()?;
+        let stderr = self.stderr.take().and_then(|h| h.join().ok()).unwrap_or_default();
+        Ok((status, stderr))
+    }
+
+    //  Run svn again after a network failure if nothing has been returned yet.
+    fn retry(&mut self, output: &Output) -> Result<bool> {
+        let (retries, base_delay) = svn_retries();
+        if self.started || self.attempt >= retries || !is_transient_failure(output) {
+            return Ok(false);
+        }
+        let delay = base_delay * 2u32.pow(self.attempt);
+        eprintln!("{} svn {} failed with a network error, retrying in {:?}", "warning:".yellow(), self.cmd.name, delay);
+        thread::sleep(delay);
+        self.attempt += 1;
+        (self.child, self.lines, self.stderr) = Self::spawn(&mut self.cmd)?;
+        self.buffer.clear();
+        Ok(true)
+    }
+
+    fn next_entry(&mut self) -> Result<Option<LogEntry>> {
+        loop {
+            if let Some(element) = self.next_element() {
+                let doc = Document::parse(&element)?;
+                self.started = true;
+                return Ok(Some(parse_log_entry(&doc.root_element())));
+            }
+            match self.next_line()? {
+                Some(line) => self.buffer.push_str(&line),
+                None => {
+                    let (status, stderr) = self.finish()?;
+                    if status.success() {
+                        return Ok(None);
+                    }
+                    let output = Output { status, stdout: Vec::new(), stderr };
+                    if !self.retry(&output)? {
+                        return Err(SvnError(output).into());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for LogStream {
+    type Item = Result<LogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_entry();
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+        result.transpose()
+    }
+}
+
+impl Drop for LogStream {
+    //  Stop svn if the entries were not all read and reap the process.
+    fn drop(&mut self) {
+        if self.child.try_wait().ok().flatten().is_none() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+//  Run the svn log command and return its entries as they are produced.
+pub fn log_stream<S>(
+    creds: &Option<Credentials>,
+    paths: &[S],
+    revisions: &[S],
+    include_msg: bool,
+    limit: Option<u32>,
+    stop_on_copy: bool,
+    include_paths: bool,
+) -> Result<LogStream>
+where
+    S: AsRef<str> + Display,
+{
+    let mut cmd = SvnCmd::new("log");
+    cmd.with_creds(creds)
+        .with_timeout()
+        .arg("--xml")
+        .arg_if(!include_msg, "--quiet")
+        .arg_if(stop_on_copy, "--stop-on-copy")
+        .arg_if(include_paths, "--verbose")
+        .opt_arg(&limit.map(|l| format!("--limit={}", l)))
+        .args(revisions.iter().map(|r| format!("--revision={}", r)))
+        .args(paths);
+    LogStream::start(cmd)
 }
 
 //  Run the svn log command
@@ -495,6 +1043,7 @@ where
 {
     let output = SvnCmd::new("log")
         .with_creds(creds)
+        .with_timeout()
         .arg("--xml")
         .arg_if(!include_msg, "--quiet")
         .arg_if(stop_on_copy, "--stop-on-copy")
@@ -516,7 +1065,6 @@ fn parse_svn_list(text: &str) -> Result<Vec<SvnList>> {
     let mut path_lists = vec![];
     let doc = Document::parse(text)?;
     for list_node in doc.descendants().filter(|n| n.has_tag_name("list")) {
-        let path = get_attr(&list_node, "path");
         let mut entries: Vec<ListEntry> = vec![];
 
         for entry_node in list_node.children().filter(|n| n.has_tag_name("entry")) {
@@ -524,7 +1072,7 @@ fn parse_svn_list(text: &str) -> Result<Vec<SvnList>> {
                 if let Some(commit_node) = get_child(&entry_node, "commit") {
                     (
                         get_attr(&commit_node, "revision"),
-                        get_child_text_or(&commit_node, "author", "n/a"),
+                        get_child_text_or(&commit_node, "author", NO_AUTHOR),
                         parse_svn_date_opt(get_child_text(&commit_node, "date")),
                     )
 
@@ -541,7 +1089,7 @@ fn parse_svn_list(text: &str) -> Result<Vec<SvnList>> {
             };
             entries.push(entry);
         }
-        path_lists.push(SvnList { path, entries });
+        path_lists.push(SvnList { entries });
     }
     Ok(path_lists)
 }
@@ -559,6 +1107,7 @@ where
 
         let output = SvnCmd::new("list")
             .with_creds(creds)
+            .with_timeout()
             .arg("--xml")
             .args(paths)
             .run()?;
@@ -578,10 +1127,35 @@ pub fn path_list(creds: &Option<Credentials>, path: &str) -> Result<SvnList> {
     Ok(list.remove(0))
 }
 
-pub fn change_diff(creds: &Option<Credentials>, path: &str, commit_rev: &str) -> Result<Vec<String>> {
+//  Get the recursive svn list for a single path.
+//  Entry names are relative to the path.
+pub fn path_list_recursive(creds: &Option<Credentials>, path: &str, revision: Option<&str>) -> Result<SvnList> {
+    let output = SvnCmd::new("list")
+        .with_creds(creds)
+        .with_timeout()
+        .arg("--xml")
+        .arg("--depth=infinity")
+        .opt_arg(&revision.map(|r| format!("--revision={}", r)))
+        .arg(path)
+        .run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        let mut list = parse_svn_list(&text)?;
+        Ok(list.remove(0))
+    } else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  With `context` the diff includes that many lines of context
+//  instead of the default of 3.
+pub fn change_diff(creds: &Option<Credentials>, path: &str, commit_rev: &str, context: Option<u32>) -> Result<Vec<String>> {
 
     let output = SvnCmd::new("diff")
         .with_creds(creds)
+        .with_timeout()
+        .opt_arg(&context_extension(context))
         .arg("--change")
         .arg(commit_rev)
         .arg(path)
@@ -596,7 +1170,379 @@ pub fn change_diff(creds: &Option<Credentials>, path: &str, commit_rev: &str) ->
     }
 }
 
-fn prefixes_file() -> Result<PathBuf> {
+//  Return the contents of a file in the repository.
+pub fn cat(creds: &Option<Credentials>, path: &str, revision: Option<&str>) -> Result<Vec<u8>> {
+    let output = SvnCmd::new("cat")
+        .with_creds(creds)
+        .with_timeout()
+        .opt_arg(&revision.map(|r| format!("--revision={}", r)))
+        .arg(path)
+        .run()?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  Return the svn:mime-type property of a file if it has one.
+pub fn mime_type(creds: &Option<Credentials>, path: &str, revision: Option<&str>) -> Result<Option<String>> {
+    let output = SvnCmd::new("propget")
+        .with_creds(creds)
+        .with_timeout()
+        .opt_arg(&revision.map(|r| format!("--revision={}", r)))
+        .arg("svn:mime-type")
+        .arg(path)
+        .run()?;
+
+    //  svn reports a warning and exits with an error if the property is not set.
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() || String::from_utf8_lossy(&output.stderr).contains("W200017") {
+        Ok(Some(text).filter(|t| !t.is_empty()))
+    } else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  Return true if the file is binary according to its svn:mime-type.
+pub fn is_binary(creds: &Option<Credentials>, path: &str, revision: Option<&str>) -> Result<bool> {
+    Ok(mime_type(creds, path, revision)?.is_some_and(|m| is_binary_mime_type(&m)))
+}
+
+//  Return the (realm, username) pairs from the svn credential cache.
+pub fn cached_usernames() -> Result<Vec<(String, String)>> {
+    let output = SvnCmd::new("auth").run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        let mut realm = String::new();
+        let mut users = Vec::new();
+        for line in text.lines() {
+            if let Some(r) = line.strip_prefix("Authentication realm:") {
+                realm = r.trim().to_string();
+            } else if let Some(u) = line.strip_prefix("Username:") {
+                users.push((realm.clone(), u.trim().to_string()));
+            }
+        }
+        Ok(users)
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  A source of revisions that were merged by a commit.
+//  Each range is inclusive and is stored with the lower revision first.
+#[derive(Debug, Clone)]
+pub struct MergeSource {
+    pub path: String,
+    pub ranges: Vec<(u64, u64)>,
+}
+
+fn merged_line_re() -> &'static Regex {
+    static MERGED_RE: OnceLock<Regex> = OnceLock::new();
+    MERGED_RE.get_or_init(|| Regex::new(r"^\s+Merged\s+(/\S*):(r\S+)$").expect("Error parsing MERGED regex"))
+}
+
+//  Parse a revision list from svn:mergeinfo such as: r50-55,57*
+//  The trailing `*` marks non-inheritable revisions and is ignored.
+fn parse_merge_ranges(text: &str) -> Vec<(u64, u64)> {
+    text.split(',')
+        .filter_map(|range| {
+            let range = range.trim_start_matches('r').trim_end_matches('*');
+            match range.split_once('-') {
+                Some((start, end)) => Some((start.parse().ok()?, end.parse().ok()?)),
+                None => range.parse().ok().map(|rev| (rev, rev)),
+            }
+        })
+        .collect()
+}
+
+//  Parse the output of `svn diff --properties-only` and return the
+//  sources that were added to svn:mergeinfo.  Reverse merges are ignored.
+fn parse_merge_sources(text: &str) -> Vec<MergeSource> {
+    let mut sources: Vec<MergeSource> = Vec::new();
+    for caps in text.lines().filter_map(|line| merged_line_re().captures(line)) {
+        let ranges = parse_merge_ranges(&caps[2]);
+        match sources.iter_mut().find(|s| s.path == caps[1]) {
+            Some(source) => source.ranges.extend(ranges),
+            None => sources.push(MergeSource { path: caps[1].to_string(), ranges }),
+        }
+    }
+    sources
+}
+
+//  Return the revisions that were merged by the given commit.
+//  The url should be the repository root so that mergeinfo changes
+//  anywhere in the commit are found.
+pub fn merged_revisions(creds: &Option<Credentials>, url: &str, commit_rev: &str) -> Result<Vec<MergeSource>> {
+    let output = SvnCmd::new("diff")
+        .with_creds(creds)
+        .with_timeout()
+        .arg("--properties-only")
+        .arg("--change")
+        .arg(commit_rev)
+        .arg(url)
+        .run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(parse_merge_sources(&text))
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  One link in the copy history of a path.
+//  `path` was created in `revision`, copied from `from` if present.
+#[derive(Debug, Clone)]
+pub struct LineageStep {
+    pub path: String,
+    pub revision: String,
+    pub from: Option<FromPath>,
+}
+
+//  Walk back through the copies (including renames) that produced a repository
+//  path such as /trunk/file.c as it existed in `peg_rev`.
+//  The first step is the path itself and the last step is where it was originally added.
+//  The copy may have been of one of the path's parent directories.
+pub fn path_lineage(creds: &Option<Credentials>, root_url: &str, path: &str, peg_rev: &str) -> Result<Vec<LineageStep>> {
+    const MAX_STEPS: usize = 100;
+    let mut steps = Vec::new();
+    let mut path = path.to_string();
+    let mut peg_rev = peg_rev.to_string();
+
+    while steps.len() < MAX_STEPS {
+        let url = format!("{}{}@{}", root_url, path, peg_rev);
+        let range = format!("1:{}", peg_rev);
+        //  With an ascending range the oldest revision since the last copy is returned.
+        let entries = log(creds, &[url.as_str()], &[range.as_str()], false, Some(1), true, true)?;
+        let Some(first) = entries.first() else { break };
+
+        let copy = copied_from(first, &path);
+        steps.push(LineageStep { path: path.clone(), revision: first.revision.clone(), from: copy.clone() });
+        match copy {
+            Some(from) => {
+                path = from.path;
+                peg_rev = from.revision;
+            }
+            None => break,
+        }
+    }
+    Ok(steps)
+}
+
+//  If the log entry copied `path`, or one of its parent directories,
+//  return where `path` was copied from.
+fn copied_from(entry: &LogEntry, path: &str) -> Option<FromPath> {
+    entry.paths.iter().find_map(|p| {
+        let from = p.from_path.as_ref()?;
+        let rest = if path == p.path {
+            ""
+        } else {
+            path.strip_prefix(&p.path).filter(|r| r.starts_with('/'))?
+        };
+        Some(FromPath { path: format!("{}{}", from.path, rest), revision: from.revision.clone() })
+    })
+}
+
+//  Return the log of a repository path as it existed in `peg_rev`, newest first,
+//  continuing across each copy or rename with the log of the path it was copied from.
+//  Each segment is fetched with --stop-on-copy so the copies are found explicitly.
+pub fn follow_log(
+    creds: &Option<Credentials>,
+    root_url: &str,
+    path: &str,
+    peg_rev: &str,
+    limit: Option<u32>,
+    include_paths: bool,
+) -> Result<Vec<LogEntry>> {
+    const MAX_SEGMENTS: usize = 100;
+    let mut entries: Vec<LogEntry> = Vec::new();
+    let mut path = path.to_string();
+    let mut peg_rev = peg_rev.to_string();
+
+    for _ in 0..MAX_SEGMENTS {
+        let remaining = match limit {
+            Some(limit) if entries.len() >= limit as usize => break,
+            Some(limit) => Some(limit - entries.len() as u32),
+            None => None,
+        };
+        let url = format!("{}{}@{}", root_url, path, peg_rev);
+        let range = format!("{}:1", peg_rev);
+        let segment = log(creds, &[url.as_str()], &[range.as_str()], true, remaining, true, true)?;
+        let copy = match (remaining, segment.last()) {
+            //  A truncated segment does not reach the copy.
+            (Some(remaining), _) if segment.len() >= remaining as usize => None,
+            (_, Some(oldest)) => copied_from(oldest, &path),
+            (_, None) => None,
+        };
+        entries.extend(segment);
+        match copy {
+            Some(from) => {
+                path = from.path;
+                peg_rev = from.revision;
+            }
+            None => break,
+        }
+    }
+
+    if !include_paths {
+        for entry in entries.iter_mut() {
+            entry.paths.clear();
+        }
+    }
+    Ok(entries)
+}
+
+//  Return the diff between two revisions of a path.
+//  If `from_rev` is greater than `to_rev` the result is a reverse diff.
+pub fn range_diff(creds: &Option<Credentials>, path: &str, from_rev: &str, to_rev: &str) -> Result<Vec<String>> {
+    let output = SvnCmd::new("diff")
+        .with_creds(creds)
+        .with_timeout()
+        .arg(format!("--revision={}:{}", from_rev, to_rev))
+        .arg(path)
+        .run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text.split('\n').map(|l| l.to_string()).collect())
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  Return the differences between two urls.
+pub fn url_diff(creds: &Option<Credentials>, old_url: &str, new_url: &str, context: Option<u32>) -> Result<Vec<String>> {
+    let output = SvnCmd::new("diff")
+        .with_creds(creds)
+        .with_timeout()
+        .opt_arg(&context_extension(context))
+        .arg(old_url)
+        .arg(new_url)
+        .run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text.lines().map(|l| l.to_string()).collect())
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  The number of context lines is passed to svn's internal diff.
+//  It has no effect when svn is configured to use an external diff-cmd.
+fn context_extension(context: Option<u32>) -> Option<String> {
+    context.map(|lines| format!("--extensions=-U{}", lines))
+}
+
+//  Return the paths that differ between two urls.
+pub fn url_diff_summary(creds: &Option<Credentials>, old_url: &str, new_url: &str) -> Result<Vec<DiffSummary>> {
+    let output = SvnCmd::new("diff")
+        .with_creds(creds)
+        .with_timeout()
+        .arg("--summarize")
+        .arg("--xml")
+        .arg(old_url)
+        .arg(new_url)
+        .run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        let doc = Document::parse(&text)?;
+        let summary = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("path"))
+            .map(|n| DiffSummary {
+                url: get_text(&n),
+                item_status: get_attr(&n, "item"),
+                props_status: get_attr(&n, "props"),
+            })
+            .collect();
+        Ok(summary)
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  Return the revisions of `source_url` that have not been merged into `target_url`.
+pub fn eligible_revisions(creds: &Option<Credentials>, source_url: &str, target_url: &str) -> Result<Vec<String>> {
+    let output = SvnCmd::new("mergeinfo")
+        .with_creds(creds)
+        .with_timeout()
+        .arg("--show-revs=eligible")
+        .arg(source_url)
+        .arg(target_url)
+        .run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .lines()
+            .map(|l| l.trim().trim_start_matches('r').to_string())
+            .filter(|l| !l.is_empty())
+            .collect())
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  One line of `svn blame` output.
+//  Lines with local modifications have no commit.
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    pub line_number: usize,
+    pub revision: Option<String>,
+    pub author: String,
+    pub date: DateTime<Local>,
+}
+
+fn parse_svn_blame(text: &str) -> Result<Vec<BlameLine>> {
+    let doc = Document::parse(text)?;
+    let lines = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("entry"))
+        .map(|entry| {
+            let commit = get_child(&entry, "commit");
+            BlameLine {
+                line_number: get_attr(&entry, "line-number").parse().unwrap_or(0),
+                revision: commit.map(|c| get_attr(&c, "revision")),
+                author: commit.map(|c| get_child_text_or(&c, "author", NO_AUTHOR)).unwrap_or("-".to_string()),
+                date: parse_svn_date_opt(commit.and_then(|c| get_child_text(&c, "date"))),
+            }
+        })
+        .collect();
+    Ok(lines)
+}
+
+//  Return the revision that last changed each line of a file.
+pub fn blame(creds: &Option<Credentials>, path: &str, revision: Option<&str>) -> Result<Vec<BlameLine>> {
+    let output = SvnCmd::new("blame")
+        .with_creds(creds)
+        .with_timeout()
+        .arg("--xml")
+        .opt_arg(&revision.map(|r| format!("--revision={}", r)))
+        .arg(path)
+        .run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        parse_svn_blame(&text)
+    }
+    else {
+        Err(SvnError(output).into())
+    }
+}
+
+pub fn prefixes_file() -> Result<PathBuf> {
     data_directory().map(|dir| dir.join("prefixes.json"))
 }
 #[derive(Serialize, Deserialize)]
@@ -610,7 +1556,16 @@ pub struct Prefixes {
 }
 
 pub fn load_prefixes() -> Result<Prefixes> {
-    let path = prefixes_file()?;
+    let _lock = util::lock_data()?;
+    read_prefixes(&prefixes_file()?)
+}
+
+pub fn save_prefixes(prefixes: &Prefixes) -> Result<()> {
+    let _lock = util::lock_data()?;
+    util::write_json_atomic(&prefixes_file()?, prefixes)
+}
+
+fn read_prefixes(path: &Path) -> Result<Prefixes> {
     if path.is_file() {
         let reader = File::open(path)?;
         let prefixes: Prefixes = serde_json::from_reader(reader)?;
@@ -625,19 +1580,18 @@ pub fn load_prefixes() -> Result<Prefixes> {
     }
 }
 
-pub fn save_prefixes(prefixes: &Prefixes) -> Result<()> {
-    let writer = File::create(prefixes_file()?)?;
-    Ok(serde_json::to_writer_pretty(writer, prefixes)?)
-}
-
 //  Verify that the current working directory is within
 //  a subversion working copy.
 //  Returns the info for the current directory or
 //  and Error if not withing a working copy.
 pub fn workingcopy_info() -> Result<SvnInfo> {
-    info(&None, ".", None).map_err(|_| {
-        General("This command must be run in a serversion working copy directory.".to_string())
-        .into()
+    //  Errors other than svn itself failing (such as a timeout) are passed on.
+    info(&None, ".", None).map_err(|e| match e.downcast_ref::<util::SvError>() {
+        Some(SvnError(_)) => {
+            General("This command must be run in a serversion working copy directory.".to_string())
+            .into()
+        }
+        _ => e,
     })
 }
 
@@ -663,13 +1617,15 @@ fn parse_svn_status(text: &str) -> Result<SvnStatus> {
     }
 }
 
-pub fn status<S>(path: S, cwd: Option<&Path>) -> Result<SvnStatus>
+//  With `no_ignore` the ignored entries are included.
+pub fn status<S>(path: S, cwd: Option<&Path>, no_ignore: bool) -> Result<SvnStatus>
 where
     S: AsRef<str> + Display,
 {
     let output = SvnCmd::new("status")
         .with_cwd(cwd)
         .arg("--xml")
+        .arg_if(no_ignore, "--no-ignore")
         .arg(path)
         .run()?;
 
@@ -754,6 +1710,7 @@ pub fn apply_patch(patch_file: &Path, dry_run: bool, cwd: Option<&Path>) -> Resu
 pub fn update(revision: &str, depth: &str, cwd: Option<&Path>) -> Result<Vec<u8>> {
     let output = SvnCmd::new("update")
         .with_cwd(cwd)
+        .with_timeout()
         .arg(format!("--depth={}", depth))
         .arg(format!("--revision={}", revision))
         .run()?;
@@ -764,3 +1721,401 @@ pub fn update(revision: &str, depth: &str, cwd: Option<&Path>) -> Result<Vec<u8>
         Err(SvnError(output).into())
     }
 }
+
+//  Remove any locks left in the working copy by an interrupted operation.
+pub fn cleanup(cwd: Option<&Path>) -> Result<()> {
+    let output = SvnCmd::new("cleanup")
+        .with_cwd(cwd)
+        .run()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(SvnError(output).into())
+    }
+}
+
+//  Resolve the name of a branch or tag to its url.
+//  The name may be a url, a path relative to the repository root (^/branches/8.1),
+//  a path starting with a prefix (branches/8.1), the trunk prefix, or simply the
+//  name of a branch or tag (8.1) which is searched for under each prefix.
+pub fn resolve_branch_url(creds: &Option<Credentials>, root_url: &str, name: &str) -> Result<String> {
+    if name.contains("://") {
+        return Ok(name.to_string());
+    }
+    if let Some(path) = name.strip_prefix("^/") {
+        return Ok(join_paths(root_url, path));
+    }
+
+    let prefixes = load_prefixes()?;
+    let all_prefixes = prefixes.branch_prefixes.iter().chain(prefixes.tag_prefixes.iter());
+    if name == prefixes.trunk_prefix
+        || all_prefixes.clone().any(|p| name.starts_with(&format!("{}/", p))) {
+        return Ok(join_paths(root_url, name));
+    }
+
+    let mut matches = Vec::new();
+    for prefix in all_prefixes {
+        let list = path_list(creds, &join_paths(root_url, prefix))?;
+        if list.entries.iter().any(|e| e.name == name) {
+            matches.push(join_paths(prefix, name));
+        }
+    }
+
+    match matches.as_slice() {
+        [path] => Ok(join_paths(root_url, path)),
+        [] => Err(General(format!("No branch or tag named '{}' was found", name)).into()),
+        _ => {
+            let choices: Vec<String> = matches.iter().map(|m| format!("^/{}", m)).collect();
+            let msg = format!("'{}' is ambiguous, it could be any of: {}", name, choices.join(", "));
+            Err(General(msg).into())
+        }
+    }
+}
+
+//  Export a clean directory tree and return the exported revision.
+pub fn export(creds: &Option<Credentials>, url: &str, dest: &Path, revision: Option<&str>, force: bool) -> Result<String> {
+    let output = SvnCmd::new("export")
+        .with_creds(creds)
+        .with_timeout()
+        .opt_arg(&revision.map(|r| format!("--revision={}", r)))
+        .arg_if(force, "--force")
+        .arg(url)
+        .arg(dest.to_string_lossy())
+        .run()?;
+
+    if output.status.success() {
+        let re = Regex::new(r"Exported revision (\d+)")?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(re.captures(&text).map(|c| c[1].to_string()).unwrap_or_default())
+    } else {
+        Err(SvnError(output).into())
+    }
+}
+
+fn parse_svn_version(text: &str) -> Result<SvnVersion> {
+    let version_re = Regex::new(r"version\s+(\S+)(?:\s+\((r\d+)\))?")?;
+    let scheme_re = Regex::new(r"handles '([^']+)' scheme")?;
+    let first_line = text.lines().next().unwrap_or("");
+
+    match version_re.captures(first_line) {
+        Some(caps) => {
+            let mut schemes: Vec<String> = scheme_re
+                .captures_iter(text)
+                .map(|c| c[1].to_string())
+                .collect();
+            schemes.dedup();
+            Ok(SvnVersion {
+                version: caps[1].to_string(),
+                revision: caps.get(2).map(|r| r.as_str().to_string()),
+                schemes,
+            })
+        }
+        None => Err(General(format!("Cannot parse svn version from: {}", first_line)).into())
+    }
+}
+
+//  Optional features of the repository server that some commands depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Mergeinfo,
+    LogRevprops,
+}
+
+pub const CAPABILITIES: [Capability; 2] = [Capability::Mergeinfo, Capability::LogRevprops];
+
+impl Capability {
+    //  The name used in the configuration key.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Mergeinfo   => "mergeinfo",
+            Capability::LogRevprops => "log-revprops",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Capability> {
+        CAPABILITIES.iter().find(|c| c.name() == name).copied()
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Capability::Mergeinfo   => "merge tracking",
+            Capability::LogRevprops => "retrieving revision properties with log",
+        }
+    }
+
+    //  A harmless log command that fails if the server lacks the capability.
+    fn probe_args(&self) -> &'static [&'static str] {
+        match self {
+            Capability::Mergeinfo   => &["--quiet", "--use-merge-history"],
+            Capability::LogRevprops => &["--xml", "--with-revprop=svn:author"],
+        }
+    }
+}
+
+//  Determine whether the server hosting the repository of `path` supports a capability.
+//  The svn client does not report server capabilities so we probe for them and save
+//  the result in the configuration keyed by the repository UUID as
+//  `capabilities.<uuid>.<name>`.  The saved value may be changed with the config command.
+pub fn has_capability(creds: &Option<Credentials>, path: &str, capability: Capability) -> Result<bool> {
+    let path_info = info(creds, path, None)?;
+    let key = format!("capabilities.{}.{}", path_info.repo_uuid, capability.name());
+    //  The configuration is only available within a working copy.
+    let in_wc = path_info.wc_path.is_some();
+
+    if in_wc {
+        if let Some(value) = crate::config::config_value(&key)? {
+            return Ok(value == "true");
+        }
+    }
+
+    let output = SvnCmd::new("log")
+        .with_creds(creds)
+        .with_timeout()
+        .arg("--limit=1")
+        .args(capability.probe_args())
+        .arg(&path_info.root_url)
+        .run()?;
+
+    //  E200007 is the error reported for an unsupported feature.
+    let supported = if output.status.success() {
+        true
+    } else if String::from_utf8_lossy(&output.stderr).contains("E200007") {
+        false
+    } else {
+        return Err(SvnError(output).into());
+    };
+
+    if in_wc {
+        let mut config = crate::config::load_config()?;
+        config.insert(key, supported.to_string());
+        crate::config::save_config(&config)?;
+    }
+    Ok(supported)
+}
+
+//  Fail with a clear message if the server does not support a capability.
+pub fn require_capability(creds: &Option<Credentials>, path: &str, capability: Capability) -> Result<()> {
+    if has_capability(creds, path, capability)? {
+        Ok(())
+    } else {
+        let msg = format!("The subversion server does not support {}", capability.description());
+        Err(General(msg).into())
+    }
+}
+
+//  Get the version of the svn client along with the
+//  repository access schemes that it supports.
+pub fn client_version() -> Result<SvnVersion> {
+    let output = SvnCmd::new("--version").run()?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        parse_svn_version(&text)
+    } else {
+        Err(SvnError(output).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{display_svn_date, display_svn_time, display_svn_datetime, display_svn_relative};
+
+    fn test_info(repo_rev: &str) -> SvnInfo {
+        SvnInfo {
+            repo_rev: repo_rev.to_string(),
+            kind: "dir".to_string(),
+            size: None,
+            url: "svn://host/repo/trunk".to_string(),
+            rel_url: "^/trunk".to_string(),
+            root_url: "svn://host/repo".to_string(),
+            repo_uuid: String::new(),
+            commit_rev: repo_rev.to_string(),
+            commit_author: String::new(),
+            commit_date: *null_date(),
+            wc_path: None,
+        }
+    }
+
+    //  Look up the info twice returning the number of times svn was run
+    //  and the revision returned by the second lookup.
+    fn lookup_twice(path: &str, revision: Option<&str>) -> (u32, String) {
+        let cache = Mutex::new(None);
+        let mut calls = 0;
+        let mut last = String::new();
+        for _ in 0..2 {
+            let info = cached_info(&cache, path, revision, || {
+                calls += 1;
+                Ok(test_info(&(100 + calls).to_string()))
+            })
+            .unwrap();
+            last = info.repo_rev;
+        }
+        (calls, last)
+    }
+
+    #[test]
+    fn head_lookup_reaches_svn_each_time() {
+        assert_eq!(lookup_twice(".", Some("HEAD")), (2, "102".to_string()));
+        assert_eq!(lookup_twice(".", Some("head")), (2, "102".to_string()));
+        assert_eq!(lookup_twice("file.c@HEAD", None), (2, "102".to_string()));
+    }
+
+    #[test]
+    fn url_without_revision_is_not_cached() {
+        assert_eq!(lookup_twice("svn://host/repo/trunk", None), (2, "102".to_string()));
+        assert_eq!(lookup_twice("^/trunk", None), (2, "102".to_string()));
+    }
+
+    #[test]
+    fn working_copy_and_fixed_revisions_are_cached() {
+        assert_eq!(lookup_twice(".", None), (1, "101".to_string()));
+        assert_eq!(lookup_twice("svn://host/repo/trunk", Some("1234")), (1, "101".to_string()));
+        assert_eq!(lookup_twice("svn://user@host/repo/trunk", Some("BASE")), (1, "101".to_string()));
+    }
+
+    //  Revision 0 of a repository has neither an author nor a date.
+    const REVISION_ZERO_LOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<log>
+<logentry revision="1">
+<author>joe</author>
+<date>2024-03-01T12:30:45.123456Z</date>
+<msg>Initial import</msg>
+</logentry>
+<logentry revision="0">
+</logentry>
+</log>
+"#;
+
+    #[test]
+    fn revision_zero_has_no_author_or_date() {
+        let entries = parse_svn_log(REVISION_ZERO_LOG).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let first = &entries[0];
+        assert_eq!(first.author, "joe");
+        assert_ne!(&first.date, null_date());
+
+        let zero = &entries[1];
+        assert_eq!(zero.revision, "0");
+        assert_eq!(zero.author, NO_AUTHOR);
+        assert_eq!(&zero.date, null_date());
+        assert_eq!(zero.msg, vec![String::new()]);
+        assert!(zero.paths.is_empty());
+    }
+
+    #[test]
+    fn missing_date_is_displayed_as_not_available() {
+        let entries = parse_svn_log(REVISION_ZERO_LOG).unwrap();
+        let zero = &entries[1];
+        assert_eq!(display_svn_date(&zero.date), "n/a");
+        assert_eq!(display_svn_time(&zero.date), "n/a");
+        assert_eq!(display_svn_datetime(&zero.date), "n/a");
+        assert_eq!(display_svn_relative(&zero.date), "n/a");
+        assert_ne!(display_svn_date(&entries[0].date), "n/a");
+    }
+
+    //  Output of `LANG=de_DE.UTF-8 svn status --xml`.  The plain output
+    //  is translated but the xml status words are not.
+    const GERMAN_STATUS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<status>
+<target path=".">
+<entry path="neu.c"><wc-status item="added" props="none" revision="-1"></wc-status></entry>
+<entry path="geändert.c"><wc-status item="modified" props="modified" revision="12"></wc-status></entry>
+<entry path="alt.c"><wc-status item="deleted" props="none" revision="12"></wc-status></entry>
+<entry path="fremd.c"><wc-status item="unversioned" props="none"></wc-status></entry>
+</target>
+</status>
+"#;
+
+    #[test]
+    fn status_codes_do_not_depend_on_the_locale() {
+        let status = parse_svn_status(GERMAN_STATUS).unwrap();
+        let codes: Vec<(&str, &str, &str)> = status.entries
+            .iter()
+            .map(|e| (e.path.as_str(), item_status_code(&e.item_status), props_status_code(&e.props_status)))
+            .collect();
+        assert_eq!(codes, vec![
+            ("neu.c",      "A", " "),
+            ("geändert.c", "M", "M"),
+            ("alt.c",      "D", " "),
+            ("fremd.c",    "?", " "),
+        ]);
+    }
+
+    fn prefixes_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("svu-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn saved_prefixes() -> Prefixes {
+        Prefixes {
+            trunk_prefix: "main".to_string(),
+            branch_prefixes: vec!["branches".to_string(), "releases".to_string()],
+            tag_prefixes: vec!["tags".to_string()],
+        }
+    }
+
+    fn assert_saved_prefixes(prefixes: &Prefixes) {
+        assert_eq!(prefixes.trunk_prefix, "main");
+        assert_eq!(prefixes.branch_prefixes, vec!["branches", "releases"]);
+        assert_eq!(prefixes.tag_prefixes, vec!["tags"]);
+    }
+
+    #[test]
+    fn partial_write_leaves_the_previous_prefixes() {
+        let dir = prefixes_test_dir("prefixes-partial");
+        let path = dir.join("prefixes.json");
+        util::write_json_atomic(&path, &saved_prefixes()).unwrap();
+
+        //  An interrupted save leaves a truncated temporary file behind.
+        std::fs::write(dir.join("prefixes.json.tmp"), br#"{ "trunkPrefix": "tru"#).unwrap();
+        assert_saved_prefixes(&read_prefixes(&path).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_save_leaves_the_previous_prefixes() {
+        let dir = prefixes_test_dir("prefixes-failed");
+        let path = dir.join("prefixes.json");
+        util::write_json_atomic(&path, &saved_prefixes()).unwrap();
+
+        //  A directory in place of the temporary file makes the save fail.
+        std::fs::create_dir(dir.join("prefixes.json.tmp")).unwrap();
+        let mut changed = saved_prefixes();
+        changed.trunk_prefix = "trunk".to_string();
+        assert!(util::write_json_atomic(&path, &changed).is_err());
+        assert_saved_prefixes(&read_prefixes(&path).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recorded_line_quotes_the_arguments() {
+        let args = vec!["--revision=12".to_string(), "my file.txt".to_string(), "$HOME's".to_string()];
+        assert_eq!(
+            recorded_line(None, "cat", &None, &args),
+            format!(r"{} cat --revision=12 'my file.txt' '$HOME'\''s'", svn_cmd())
+        );
+        assert_eq!(
+            recorded_line(Some(Path::new("/work/my wc")), "update", &None, &[]),
+            format!("(cd '/work/my wc' && {} update)", svn_cmd())
+        );
+    }
+
+    #[test]
+    fn recorded_line_never_contains_the_credentials() {
+        let creds = Some(Credentials("joe".to_string(), "s3cret pass".to_string()));
+        let line = recorded_line(None, "log", &creds, &["^/trunk".to_string()]);
+        assert_eq!(
+            line,
+            format!(r#"{} log --username="$SVU_USERNAME" --password="$SVU_PASSWORD" ^/trunk"#, svn_cmd())
+        );
+        assert!(!line.contains("joe"));
+        assert!(!line.contains("s3cret"));
+    }
+}
@@ -1,12 +1,19 @@
 
 use thiserror::Error;
 use crate::svn::{self, LogPath, FromPath, LogEntry};
+use crate::auth::Credentials;
 use colored::*;
-use chrono::{DateTime, Local, NaiveDateTime, Utc};
-use std::sync::OnceLock;
-use std::path::PathBuf;
-use std::fs::{create_dir, rename};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::fs::{create_dir, rename, File, TryLockError};
+use std::io::{ErrorKind, Write};
 use anyhow::Result;
+use pathdiff::diff_paths;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Serialize};
 
 #[derive(Error, Debug)]
 pub enum SvError {
@@ -37,56 +44,371 @@ T: AsRef<str>,
 pub fn data_directory() -> Result<PathBuf> {
     let wc_info = svn::workingcopy_info()?; // Make sure we are in a working copy.
     let wc_root = PathBuf::from(wc_info.wc_path.unwrap());
-    let path = wc_root.join(".svu");
-    if !path.is_dir() {
-        let prev_path = wc_root.join(".sv");
-        if prev_path.is_dir() {
-            rename(&prev_path, &path)?;
-        } else {
-            create_dir(&path)?
+    let mut path = wc_root.join(".svu");
+    if !migrate_data_directory(&wc_root)? && !path.is_dir() {
+        match create_dir(&path) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                path = create_fallback_data_directory(&wc_root, &path, e)?;
+            }
+            Err(e) => return Err(e.into()),
         }
     }
     Ok(path)
 }
 
-pub fn formatted_log_path(log_path: &LogPath) -> String {
-    let color = match log_path.action.as_str() {
+//  Returns the data directory of a working copy if it has already been created.
+//  This does not run svn or create anything.
+pub fn existing_data_directory(wc_root: &Path) -> Option<PathBuf> {
+    let path = wc_root.join(".svu");
+    if path.is_dir() {
+        Some(path)
+    } else {
+        fallback_data_directory(wc_root).filter(|p| p.is_dir())
+    }
+}
+
+//  When we are not permitted to create the .svu directory in the
+//  working copy root (for example a shared working copy owned by
+//  another user) we store the data in the user's cache directory
+//  in a subdirectory named after the working copy root path.
+fn fallback_data_directory(wc_root: &Path) -> Option<PathBuf> {
+    let cache_dir = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    }?;
+
+    let wc_root = wc_root.canonicalize().unwrap_or_else(|_| wc_root.to_path_buf());
+    let key: String = wc_root
+        .to_string_lossy()
+        .trim_start_matches(['/', '\\'])
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':') { '%' } else { c })
+        .collect();
+    Some(cache_dir.join("svu").join(key))
+}
+
+fn create_fallback_data_directory(wc_root: &Path, primary: &Path, primary_err: std::io::Error) -> Result<PathBuf> {
+    let failure = |reason: String| {
+        let msg = format!(
+            "Cannot create {}: {}\nCannot use a fallback data directory: {}",
+            primary.display(), primary_err, reason
+        );
+        SvError::General(msg)
+    };
+    let path = fallback_data_directory(wc_root)
+        .ok_or_else(|| failure("the user cache directory is unknown".to_string()))?;
+    std::fs::create_dir_all(&path).map_err(|e| failure(format!("{}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+//  Rename the legacy .sv directory of a working copy to .svu.
+//  Returns true if the directory was renamed.
+pub fn migrate_data_directory(wc_root: &Path) -> Result<bool> {
+    let path = wc_root.join(".svu");
+    let prev_path = wc_root.join(".sv");
+    if !path.is_dir() && prev_path.is_dir() {
+        rename(&prev_path, &path)?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+//  Write a value as json to a file atomically.
+pub fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    write_file_atomic(path, &serde_json::to_vec_pretty(value)?)
+}
+
+//  Write the contents to a temporary file in the same directory and
+//  then rename it over the target so that an interrupted write never
+//  leaves a truncated file behind.
+pub fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = File::create(&tmp_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut writer| {
+            writer.write_all(contents)?;
+            writer.sync_all()?;
+            Ok(())
+        })
+        .and_then(|_| Ok(rename(&tmp_path, path)?));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+//  Load a json file and verify that its contents survive being
+//  written and read back again.  Used to detect corrupt state files.
+pub fn check_json_file<T>(path: &Path) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let value: T = serde_json::from_reader(File::open(path)?)?;
+    let text = serde_json::to_string(&value)?;
+    let _: T = serde_json::from_str(&text)?;
+    Ok(value)
+}
+
+const DATA_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+//  The open lock file and the number of DataLock guards that share it.
+struct LockState {
+    file: Option<File>,
+    holders: usize,
+}
+
+static DATA_LOCK: Mutex<LockState> = Mutex::new(LockState { file: None, holders: 0 });
+
+//  An advisory lock on .svu/lock that is held while the files in the
+//  data directory are read or written.  The lock is released when the
+//  last guard is dropped.  The operating system also releases it when
+//  the process exits so it is not left behind after a panic or Ctrl-C.
+//
+//  Guards can be nested within a process.  Only the first one takes
+//  the lock, so a function holding a guard may call others that lock.
+pub struct DataLock {
+    _private: (),
+}
+
+impl Drop for DataLock {
+    fn drop(&mut self) {
+        let mut state = DATA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        state.holders -= 1;
+        if state.holders == 0 {
+            if let Some(file) = state.file.take() {
+                let _ = file.unlock();
+            }
+        }
+    }
+}
+
+//  Lock the data directory of the current working copy.
+pub fn lock_data() -> Result<DataLock> {
+    lock_data_directory(&data_directory()?)
+}
+
+//  Lock the data directory, waiting for up to DATA_LOCK_TIMEOUT if
+//  another svu process holds the lock.
+pub fn lock_data_directory(data_dir: &Path) -> Result<DataLock> {
+    let mut state = DATA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if state.holders == 0 {
+        let file = File::create(data_dir.join("lock"))?;
+        let start = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) if start.elapsed() < DATA_LOCK_TIMEOUT => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(TryLockError::WouldBlock) => {
+                    let msg = "another svu operation is in progress in this working copy".to_string();
+                    return Err(SvError::General(msg).into());
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+        state.file = Some(file);
+    }
+    state.holders += 1;
+    Ok(DataLock { _private: () })
+}
+
+//  Decode the %XX escapes found in the urls reported by svn.
+pub fn decode_url_path(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+//  Returns the repository path (eg. /trunk/src) of the
+//  current working copy directory.
+//  This is used as the base when displaying relative paths.
+pub fn working_copy_repo_path(creds: &Option<Credentials>) -> Result<String> {
+    let info = svn::info(creds, ".", None)?;
+    let rel_url = decode_url_path(&info.rel_url);
+    Ok(format!("/{}", rel_url.trim_start_matches('^').trim_matches('/')))
+}
+
+//  Make a repository path relative to the given base
+//  repository path.
+pub fn relative_repo_path(path: &str, base: &str) -> String {
+    match diff_paths(Path::new(path), Path::new(base)) {
+        Some(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+        Some(rel) => rel.to_string_lossy().into_owned(),
+        None => path.to_string(),
+    }
+}
+
+//  How revision numbers are padded when displayed.
+#[derive(Debug, Clone, Copy)]
+pub enum RevPadding {
+    Spaces(usize),
+    //  Zero padded to the number of digits in the HEAD revision.
+    Head,
+}
+
+static REV_PADDING: OnceLock<RevPadding> = OnceLock::new();
+
+pub fn set_revision_padding(padding: RevPadding) {
+    let _ = REV_PADDING.set(padding);
+}
+
+//  The HEAD revision is looked up the first time that a revision is
+//  padded so that commands which never display a revision do not
+//  contact the repository.  If it cannot be found the revisions
+//  are left unpadded.
+fn head_revision_width() -> usize {
+    static HEAD_WIDTH: OnceLock<usize> = OnceLock::new();
+    *HEAD_WIDTH.get_or_init(|| {
+        let head = crate::auth::get_credentials()
+            .and_then(|creds| svn::info(&creds, ".", Some("HEAD")));
+        match head {
+            Ok(info) => info.repo_rev.len(),
+            Err(e) => {
+                eprintln!("{} --abbrev head: {}", "warning:".yellow(), e);
+                0
+            }
+        }
+    })
+}
+
+//  Revisions are displayed unpadded unless the --abbrev option was given.
+pub fn display_revision(revision: &str) -> String {
+    let (zeros, width) = match REV_PADDING.get() {
+        None => return revision.to_string(),
+        Some(RevPadding::Spaces(width)) => (false, *width),
+        Some(RevPadding::Head) => (true, head_revision_width()),
+    };
+    if zeros && revision.chars().all(|c| c.is_ascii_digit()) {
+        format!("{:0>width$}", revision)
+    } else {
+        format!("{:>width$}", revision)
+    }
+}
+
+//  If `rel_base` is given then paths are displayed relative to it.
+//  The color of a changed path is chosen by its action code.
+//  The codes come from the action attribute of `svn log --xml` which
+//  is the same in every locale.
+fn action_color(action: &str) -> &'static str {
+    match action {
         "D"  => "red",
         "A"  => "green",
         "M"  => "blue",
         _    => "white"
+    }
+}
+
+//  If `merged` is true then the commit was a merge and the path is marked
+//  as either having been brought in by the merge or as only recording
+//  the merge (a property change with no text change).
+pub fn formatted_log_path(log_path: &LogPath, rel_base: Option<&str>, merged: bool) -> String {
+    let color = action_color(&log_path.action);
+    let display_path = |path: &str| -> String {
+        match rel_base {
+            Some(base) => relative_repo_path(path, base),
+            None => path.to_string(),
+        }
     };
 
     let base = format!(
         "  {} {}",
         log_path.action.color(color),
-        log_path.path.color(color),
+        display_path(&log_path.path).color(color),
     );
 
-    match &log_path.from_path {
+    let formatted = match &log_path.from_path {
         Some(FromPath { path, revision }) => {
-            format!("{} (from {} {})", base, path.magenta(), revision.yellow())
+            format!("{} (from {} {})", base, display_path(path).magenta(), display_revision(revision).yellow())
         }
         None => base
+    };
+
+    if !merged {
+        formatted
+    } else if log_path.action == "M" && log_path.prop_mods && !log_path.text_mods {
+        format!("{} {}", formatted, "(mergeinfo)".dimmed())
+    } else {
+        format!("{} {}", formatted, "(merged)".cyan())
     }
 }
 
+//  Mon Jan 01 1900 14:38:45 GMT+0000
+const NULL_DATE_MILLIS: i64 = -2208936075000;
+
 //  Create a `null` date value to use when an
 //  entry has no date.
 pub fn null_date() -> &'static DateTime<Local> {
     static NULL_DATE: OnceLock<DateTime<Local>> = OnceLock::new();
     NULL_DATE.get_or_init(|| {
-        let timestamp_millis: i64 = -2208936075000; //Mon Jan 01 1900 14:38:45 GMT+0000
-        let naive_datetime = NaiveDateTime::from_timestamp_millis(timestamp_millis).unwrap();
+        let secs = NULL_DATE_MILLIS.div_euclid(1000);
+        let nanos = (NULL_DATE_MILLIS.rem_euclid(1000) * 1_000_000) as u32;
+        let utc_datetime = DateTime::<Utc>::from_timestamp(secs, nanos).unwrap();
         let offset = *Local::now().offset();
-        DateTime::<Local>::from_naive_utc_and_offset(naive_datetime, offset)
+        DateTime::<Local>::from_naive_utc_and_offset(utc_datetime.naive_utc(), offset)
     })
 }
 
-pub fn parse_svn_date(date_str: &str) -> DateTime<Local> {
+//  Dates in hand edited revision properties may be malformed.
+pub fn parse_svn_date(date_str: &str) -> Result<DateTime<Local>, SvError> {
     DateTime::parse_from_rfc3339(date_str)
-        .unwrap()  // We assume all svn dates are well formed!
-        .with_timezone(&Local)
+        .map(|date| date.with_timezone(&Local))
+        .map_err(|e| SvError::General(format!("Invalid svn date '{}': {}", date_str, e)))
+}
+
+//  Parse a date entered by the user.  In addition to RFC3339 this accepts
+//  a local date and time such as 2024-01-31 or 2024-01-31 14:30, and a
+//  duration before now such as 30d.  A date without a time is midnight.
+pub fn parse_date_arg(arg: &str) -> Result<DateTime<Local>, SvError> {
+    if let Ok(date) = parse_svn_date(arg) {
+        return Ok(date);
+    }
+    let local_time = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(arg, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(arg, "%Y-%m-%d").ok().and_then(|day| day.and_hms_opt(0, 0, 0)));
+    if let Some(local_time) = local_time {
+        return Local
+            .from_local_datetime(&local_time)
+            .earliest()
+            .ok_or_else(|| SvError::General(format!("Invalid date '{}'", arg)));
+    }
+    match parse_duration(arg).map(|duration| Local::now().checked_sub_signed(duration)) {
+        Ok(Some(date)) => Ok(date),
+        _ => Err(SvError::General(format!("Invalid date '{}' (eg. 2024-01-31, 2024-01-31 14:30, 30d, 6w)", arg))),
+    }
+}
+
+//  Return the date as a revision that svn will accept.  eg. {2024-01-31T12:00:00Z}
+pub fn svn_revision_date(date: &DateTime<Local>) -> String {
+    format!("{{{}}}", date.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ"))
 }
 
 pub fn svn_date_to_rfc3339_string(date: &DateTime<Local>) -> String {
@@ -94,30 +416,171 @@ pub fn svn_date_to_rfc3339_string(date: &DateTime<Local>) -> String {
     utc_date.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
 }
 
+//  A malformed date is treated as a missing date so that a single
+//  bad revision does not abort the entire command.
 pub fn parse_svn_date_opt(opt_date_str: Option<String>) -> DateTime<Local> {
-    if let Some(date_str) = opt_date_str {
-        parse_svn_date(date_str.as_str())
+    match opt_date_str.map(|s| parse_svn_date(&s)) {
+        Some(Ok(date)) => date,
+        Some(Err(e)) => {
+            eprintln!("{} {}", "warning:".yellow(), e);
+            *null_date()
+        }
+        None => *null_date(),
+    }
+}
+
+//  The time zone used when displaying dates.
+#[derive(Debug, Clone, Copy)]
+pub enum DisplayZone {
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+}
+
+static DISPLAY_ZONE: OnceLock<DisplayZone> = OnceLock::new();
+
+//  Parse a time zone specification: `local`, `UTC`, a fixed offset
+//  such as +05:30 or -0800, or a named zone such as Europe/Paris.
+//  Named zones are returned as Err(name) so that the caller can
+//  install them through the TZ environment variable.
+fn parse_timezone(spec: &str) -> Result<std::result::Result<DisplayZone, String>> {
+    let offset_re = Regex::new(r"^([+-])(\d{2}):?(\d{2})?$")?;
+    if spec.eq_ignore_ascii_case("local") {
+        Ok(Ok(DisplayZone::Local))
+    } else if spec.eq_ignore_ascii_case("utc") || spec == "Z" {
+        Ok(Ok(DisplayZone::Utc))
+    } else if let Some(caps) = offset_re.captures(spec) {
+        let hours: i32 = caps[2].parse()?;
+        let minutes: i32 = caps.get(3).map(|m| m.as_str().parse()).transpose()?.unwrap_or(0);
+        let secs = (hours * 3600 + minutes * 60) * if &caps[1] == "-" { -1 } else { 1 };
+        FixedOffset::east_opt(secs)
+            .map(|offset| Ok(DisplayZone::Fixed(offset)))
+            .ok_or(SvError::General(format!("Invalid time zone offset: {}", spec)).into())
+    } else if Path::new("/usr/share/zoneinfo").join(spec).is_file() {
+        Ok(Err(spec.to_string()))
     } else {
-        *null_date()
+        Err(SvError::General(format!("Unknown time zone: {}", spec)).into())
+    }
+}
+
+//  Verify a time zone specification without installing it.
+pub fn validate_timezone(spec: &str) -> Result<()> {
+    parse_timezone(spec).map(|_| ())
+}
+
+//  Set the time zone used to display dates.
+//  This must be called before any dates are displayed.
+//  A named zone is installed by setting the TZ environment variable
+//  which chrono consults when converting to Local time.
+pub fn set_display_timezone(spec: &str) -> Result<()> {
+    let zone = match parse_timezone(spec)? {
+        Ok(zone) => zone,
+        Err(name) => {
+            std::env::set_var("TZ", name);
+            DisplayZone::Local
+        }
+    };
+    let _ = DISPLAY_ZONE.set(zone);
+    Ok(())
+}
+
+fn display_zone() -> DisplayZone {
+    *DISPLAY_ZONE.get_or_init(|| DisplayZone::Local)
+}
+
+//  Convert a date to the display time zone.
+fn zoned_date(date: &DateTime<Local>) -> DateTime<FixedOffset> {
+    match display_zone() {
+        DisplayZone::Local => date.with_timezone(&Local).fixed_offset(),
+        DisplayZone::Utc => date.with_timezone(&Utc).fixed_offset(),
+        DisplayZone::Fixed(offset) => date.with_timezone(&offset),
     }
 }
 
+//  RFC3339 timestamp in the display time zone.
+pub fn display_svn_rfc3339(date: &DateTime<Local>) -> String {
+    zoned_date(date).to_rfc3339_opts(chrono::SecondsFormat::Micros, false)
+}
+
+//  Commits without a date (such as revision 0 of an empty repository)
+//  have the null date which is displayed as n/a.
+//  With --machine-date the full RFC3339 timestamp is displayed.
 pub fn display_svn_date(date: &DateTime<Local>) -> String {
-    date.format("%Y-%m-%d").to_string()
+    if date == null_date() {
+        "n/a".to_owned()
+    } else if crate::app::global_options().machine_date {
+        svn_date_to_rfc3339_string(date)
+    } else {
+        zoned_date(date).format("%Y-%m-%d").to_string()
+    }
 }
 
 pub fn display_svn_time(date: &DateTime<Local>) -> String {
-    date.format("%H:%M:%S").to_string()
+    if date == null_date() {
+        "n/a".to_owned()
+    } else {
+        zoned_date(date).format("%H:%M:%S").to_string()
+    }
 }
 
 pub fn display_svn_datetime(date: &DateTime<Local>) -> String {
     if date == null_date() {
         "n/a".to_owned()
+    } else if crate::app::global_options().machine_date {
+        svn_date_to_rfc3339_string(date)
     } else {
         format!("{} {}", display_svn_date(date), display_svn_time(date))
     }
 }
 
+//  Display the time elapsed since the date. eg. "3 weeks ago"
+pub fn display_svn_relative(date: &DateTime<Local>) -> String {
+    if date == null_date() {
+        return "n/a".to_owned();
+    }
+    let secs = (Local::now() - *date).num_seconds();
+    let (count, unit) = match secs {
+        s if s < 0              => return "in the future".to_owned(),
+        s if s < 60             => return "just now".to_owned(),
+        s if s < 3600           => (s / 60, "minute"),
+        s if s < 86400          => (s / 3600, "hour"),
+        s if s < 86400 * 14     => (s / 86400, "day"),
+        s if s < 86400 * 60     => (s / (86400 * 7), "week"),
+        s if s < 86400 * 365    => (s / (86400 * 30), "month"),
+        s                       => (s / (86400 * 365), "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
+
+//  Parse a duration such as 12h, 30d, 6w, 3m or 1y.
+//  Months are treated as 30 days and years as 365 days.
+pub fn parse_duration(text: &str) -> Result<chrono::Duration> {
+    let invalid = || SvError::General(format!("Invalid duration '{}' (eg. 12h, 30d, 6w, 3m, 1y)", text));
+    let re = Regex::new(r"^(\d+)([hdwmy])$")?;
+    let caps = re.captures(text).ok_or_else(invalid)?;
+    let unit_hours = match &caps[2] {
+        "h" => 1,
+        "d" => 24,
+        "w" => 24 * 7,
+        "m" => 24 * 30,
+        _   => 24 * 365,
+    };
+    //  Reject counts too large for a chrono::Duration rather than panic.
+    let hours = caps[1]
+        .parse::<i64>()
+        .ok()
+        .and_then(|count| count.checked_mul(unit_hours))
+        .filter(|hours| *hours <= i64::MAX / (3600 * 1000))
+        .ok_or_else(invalid)?;
+    Ok(chrono::Duration::hours(hours))
+}
+
+//  Date in the format used by email headers.
+pub fn display_svn_rfc2822(date: &DateTime<Local>) -> String {
+    zoned_date(date).to_rfc2822()
+}
+
 pub(crate) mod datetime_serializer {
     use chrono::{DateTime, Local};
     use serde::{self, Deserialize, Serializer, Deserializer};
@@ -143,77 +606,955 @@ pub(crate) mod datetime_serializer {
         D: Deserializer<'de>
     {
         let s = String::deserialize(deserializer)?;
-        Ok(parse_svn_date(&s))
+        parse_svn_date(&s).map_err(serde::de::Error::custom)
+    }
+
+}
+
+//  Quote an argument for the shell if it contains anything
+//  other than characters that are safe to use unquoted.
+pub fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-+=:,./@%^".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
     }
+}
 
+//  Quote a value for a CSV file when it contains a separator,
+//  a quote or a line break.  Embedded quotes are doubled.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+//  Format a number of bytes using binary units. eg. 1.5 MiB
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
 }
 
 pub fn divider(len: usize) -> String {
     "-".repeat(len)
 }
 
+//  The summary line of a commit for use in changelogs.
+//  Only the first non blank line of the message is used.
+pub fn subject_line(log_entry: &LogEntry, show_rev: bool) -> String {
+    let subject = log_entry.msg
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or("");
+    if show_rev {
+        format!("r{} {}", log_entry.revision, subject)
+    } else {
+        subject.to_string()
+    }
+}
+
+//  The number of columns used to display a character.
+//  Combining marks take no space and East Asian wide characters
+//  and emoji take two.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F | 0xFE20..=0xFE2F | 0x200B..=0x200F => 0,
+        0x1100..=0x115F | 0x2E80..=0x303E | 0x3041..=0x33FF | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF | 0xA000..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+//  The number of columns used to display text ignoring any color codes.
+pub fn display_width(text: &str) -> usize {
+    static ANSI_RE: OnceLock<Regex> = OnceLock::new();
+    let ansi_re = ANSI_RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*m").expect("Error parsing ansi regex"));
+    ansi_re.replace_all(text, "").chars().map(char_width).sum()
+}
+
+//  Truncate text to fit within `width` columns, marking
+//  the truncation with an ellipsis.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = char_width(c);
+        if used + w + 1 > width {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    if width > 0 {
+        result.push('…');
+    }
+    result
+}
+
+//  The width of the terminal or None if stdout is not a terminal.
+pub fn terminal_width() -> Option<usize> {
+    use std::io::IsTerminal;
+    if std::io::stdout().is_terminal() {
+        terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize)
+    } else {
+        None
+    }
+}
+
+//  Colors used for authors with --color-authors.
+//  Dark and gray colors are omitted so that names remain readable.
+const AUTHOR_PALETTE: &[&str] = &[
+    "cyan",
+    "green",
+    "yellow",
+    "magenta",
+    "blue",
+    "red",
+    "bright cyan",
+    "bright green",
+    "bright yellow",
+    "bright magenta",
+    "bright blue",
+    "bright red",
+];
+
+//  Choose a color from the palette by a hash of the text.
+//  FNV-1a is used so the color is the same on every run.
+pub fn palette_color(text: &str) -> &'static str {
+    let hash = text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    AUTHOR_PALETTE[(hash % AUTHOR_PALETTE.len() as u64) as usize]
+}
+
+//  The color used to display an author's name.
+//  With --color-authors each author is given a color chosen by a hash of
+//  their name.
+pub fn author_color(author: &str) -> &'static str {
+    if !crate::app::global_options().color_authors {
+        return "cyan";
+    }
+    palette_color(author)
+}
+
+//  Format a commit on a single line.
+//  eg.  7601 joe 2024-03-01 12:00:00 Fix the widget
+pub fn oneline_commit(log_entry: &LogEntry) -> String {
+    format!(
+        "{} {} {} {}",
+        display_revision(&log_entry.revision).yellow(),
+        log_entry.author.color(author_color(&log_entry.author)),
+        display_svn_datetime(&log_entry.date).magenta(),
+        log_entry.msg_1st()
+    )
+}
+
 //  Print formatted commit info to stdout.
-pub fn show_commit(log_entry: &LogEntry, show_msg: bool, show_paths: bool) {
+//  The header fields displayed for each commit.
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderFields {
+    pub rev: bool,
+    pub author: bool,
+    pub date: bool,
+    //  Display the date relative to now. eg. "3 days ago"
+    pub relative: bool,
+}
+
+impl Default for HeaderFields {
+    fn default() -> Self {
+        HeaderFields { rev: true, author: true, date: true, relative: false }
+    }
+}
+
+//  How the paths of a commit are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    Hidden,
+    Full,
+    //  One uncolored `ACTION<TAB>PATH` record per path
+    //  terminated by a newline or a NUL.
+    NameStatus { null_terminated: bool },
+}
+
+//  Print the paths of a commit in the compact --name-status form.
+pub fn print_name_status(paths: &[LogPath], rel_base: Option<&str>, null_terminated: bool) {
+    let terminator = if null_terminated { '\0' } else { '\n' };
+    for log_path in paths {
+        let path = match rel_base {
+            Some(base) => relative_repo_path(&log_path.path, base),
+            None => log_path.path.clone(),
+        };
+        print!("{}\t{}{}", log_path.action, path, terminator);
+    }
+}
+
+//  How the message of a commit is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStyle {
+    Hidden,
+    Full,
+    //  Reflow each paragraph to fit within the given width.
+    Wrap(usize),
+    //  The revision, author, date and first line of the message on one line.
+    OneLine,
+}
+
+//  Reflow a line of text at word boundaries so that no line is wider than `width`.
+//  A word that is wider than `width` is placed on a line by itself.
+pub fn wrap_to_width(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && display_width(&current) + 1 + display_width(word) > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+pub fn show_commit(log_entry: &LogEntry, fields: &HeaderFields, msg_style: MessageStyle, path_style: PathStyle, rel_base: Option<&str>, merged: bool) {
+    if msg_style == MessageStyle::OneLine {
+        println!("{}", oneline_commit(log_entry));
+        match path_style {
+            PathStyle::NameStatus { null_terminated } => print_name_status(&log_entry.paths, rel_base, null_terminated),
+            PathStyle::Full => log_entry.paths.iter().for_each(|path| println!("{}", formatted_log_path(path, rel_base, merged))),
+            PathStyle::Hidden => (),
+        }
+        return;
+    }
+
     let divider = divider(70);
     println!("{}", divider);
-    println!("Commit: {}", log_entry.revision.yellow());
-    println!("Author: {}", log_entry.author.cyan());
-    println!("Date  : {}", display_svn_datetime(&log_entry.date).magenta());
+    if fields.rev {
+        println!("Commit: {}", display_revision(&log_entry.revision).yellow());
+    }
+    if fields.author {
+        println!("Author: {}", log_entry.author.color(author_color(&log_entry.author)));
+    }
+    if fields.date {
+        let date = if fields.relative {
+            display_svn_relative(&log_entry.date)
+        } else {
+            display_svn_datetime(&log_entry.date)
+        };
+        println!("Date  : {}", date.magenta());
+    }
     println!("{}", divider);
 
-    if show_msg {
-        for line in &log_entry.msg {
-            println!(" {}", line);
+    match msg_style {
+        MessageStyle::Full => {
+            for line in &log_entry.msg {
+                println!(" {}", line);
+            }
+        }
+        MessageStyle::Wrap(width) => {
+            //  Allow for the leading space.
+            let width = width.saturating_sub(1).max(1);
+            for line in &log_entry.msg {
+                for wrapped in wrap_to_width(line, width) {
+                    println!(" {}", wrapped);
+                }
+            }
         }
+        MessageStyle::Hidden | MessageStyle::OneLine => (),
     }
     println!();
 
+    if let PathStyle::NameStatus { null_terminated } = path_style {
+        print_name_status(&log_entry.paths, rel_base, null_terminated);
+        return;
+    }
+
     if !log_entry.paths.is_empty() {
-        struct Totals{
-            chg: u16,
-            add: u16,
-            del: u16,
-            rep: u16,
-        }
-        let mut totals = Totals {
-            chg: 0,
-            add: 0,
-            del: 0,
-            rep: 0,
+        println!("{}", totals_line(&log_entry.paths).cyan());
+    }
+
+    if path_style == PathStyle::Full {
+        for path in &log_entry.paths {
+            println!("{}", formatted_log_path(path, rel_base, merged))
+        }
+    }
+}
+
+//  Summarize the number of paths changed by each action.
+fn totals_line(paths: &[LogPath]) -> String {
+    struct Totals{
+        chg: u16,
+        add: u16,
+        del: u16,
+        rep: u16,
+    }
+    let mut totals = Totals {
+        chg: 0,
+        add: 0,
+        del: 0,
+        rep: 0,
+    };
+
+    for path_entry in paths {
+        match path_entry.action.as_str() {
+            "M" => totals.chg += 1,
+            "A" => totals.add += 1,
+            "D" => totals.del += 1,
+            "R" => totals.rep += 1,
+            _   => ()
+        }
+    }
+    let label = if totals.chg == 1 { "file" } else { "files" };
+    format!(
+        "{} {} modified, {} added, {} deleted, {} replaced",
+        totals.chg, label, totals.add, totals.del, totals.rep
+    )
+}
+
+//  The SHA-1 digest of the data as 40 hexadecimal digits.
+//  This is the checksum that svn reports for the files in a working copy.
+pub fn sha1_hex(data: &[u8]) -> String {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    //  The data is padded with a 1 bit, zeros and its length in bits
+    //  to a multiple of 64 bytes.
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19  => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _       => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (value, add) in h.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+    h.iter().map(|value| format!("{:08x}", value)).collect()
+}
+
+//  svn does not show the contents of binary files in a diff.
+//  Instead it emits a notice in place of the content.
+//  Subversion treats a file as binary if its svn:mime-type is set to
+//  anything other than a text/* type or one of the X bitmap image types.
+//  Parameters such as `; charset=utf-8` are ignored.
+pub fn is_binary_mime_type(mime_type: &str) -> bool {
+    let mime_type = mime_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    !(mime_type.is_empty()
+        || mime_type.starts_with("text/")
+        || mime_type == "image/x-xbitmap"
+        || mime_type == "image/x-xpixmap")
+}
+
+pub fn is_binary_marker(line: &str) -> bool {
+    line.starts_with("Cannot display: file marked as a binary type")
+        || line.starts_with("Cannot display: file marked as binary type")
+        || line.strip_prefix("svn:mime-type = ").is_some_and(is_binary_mime_type)
+}
+
+//  The colors used to display each kind of diff line.
+//  Each can be overridden in the colors.toml file of the .svu directory.
+#[derive(Debug, PartialEq)]
+pub struct DiffColors {
+    pub header: Color,
+    pub index: Color,
+    pub property: Color,
+    pub add: Color,
+    pub context: Color,
+    pub del: Color,
+}
+
+impl Default for DiffColors {
+    fn default() -> Self {
+        DiffColors {
+            header: Color::Blue,
+            index: Color::Yellow,
+            property: Color::Magenta,
+            add: Color::Green,
+            context: Color::White,
+            del: Color::Red,
+        }
+    }
+}
+
+impl DiffColors {
+    fn color_for(&self, line: &str) -> Color {
+        if line.starts_with("---") || line.starts_with("+++") { self.header }
+        else if line.starts_with("Index:") || line.starts_with("==========") { self.index }
+        else if line.starts_with("Property changes on:") { self.property }
+        else if line.starts_with('+') { self.add }
+        else if line.starts_with("@@") { Color::White }
+        else if line.starts_with('-') { self.del }
+        else { self.context }
+    }
+
+    //  Parse the contents of a colors.toml file.  Each line maps a kind
+    //  of diff line to a quoted color name such as `add = "bright green"`.
+    //  Blank lines and # comments are skipped.  A warning is returned for
+    //  each line that cannot be used and that kind keeps its default color.
+    fn from_toml(text: &str) -> (DiffColors, Vec<String>) {
+        let mut colors = DiffColors::default();
+        let mut warnings = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let warn = |msg: String| format!("line {}: {}", number + 1, msg);
+            let Some((kind, value)) = line.split_once('=') else {
+                warnings.push(warn(format!("expected kind = \"color\" but found '{}'", line)));
+                continue;
+            };
+            let name = value
+                .trim()
+                .strip_prefix('"')
+                .and_then(|v| v.split_once('"'))
+                .filter(|(_, rest)| rest.trim().is_empty() || rest.trim().starts_with('#'))
+                .map(|(name, _)| name);
+            let Some(name) = name else {
+                warnings.push(warn(format!("the color must be a quoted string: {}", value.trim())));
+                continue;
+            };
+            let kind = kind.trim();
+            let slot = match kind {
+                "header"   => &mut colors.header,
+                "index"    => &mut colors.index,
+                "property" => &mut colors.property,
+                "add"      => &mut colors.add,
+                "context"  => &mut colors.context,
+                "del"      => &mut colors.del,
+                _ => {
+                    let msg = format!("'{}' must be one of: header, index, property, add, context or del", kind);
+                    warnings.push(warn(msg));
+                    continue;
+                }
+            };
+            match name.parse() {
+                Ok(color) => *slot = color,
+                Err(_) => {
+                    let msg = format!(
+                        "'{}' must be one of: black, red, green, yellow, blue, magenta, cyan, white or bright <color>",
+                        name
+                    );
+                    warnings.push(warn(msg));
+                }
+            }
+        }
+        (colors, warnings)
+    }
+}
+
+//  The diff colors are read from colors.toml once.  Each problem
+//  with the file is reported as a warning.
+pub fn diff_colors() -> &'static DiffColors {
+    static DIFF_COLORS: OnceLock<DiffColors> = OnceLock::new();
+    DIFF_COLORS.get_or_init(|| {
+        let Some(path) = crate::config::peek_data_file("colors.toml") else {
+            return DiffColors::default();
         };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let (colors, warnings) = DiffColors::from_toml(&text);
+                for warning in warnings {
+                    eprintln!("{} {} {}", "warning:".yellow(), path.display(), warning);
+                }
+                colors
+            }
+            Err(e) => {
+                eprintln!("{} {}: {}", "warning:".yellow(), path.display(), e);
+                DiffColors::default()
+            }
+        }
+    })
+}
+
+pub fn print_diff_line(line: &str, colors: &DiffColors) {
+    if is_binary_marker(line) {
+        println!("{}", line.dimmed());
+        return;
+    }
+    println!("{}", line.color(colors.color_for(line)));
+}
+
+//  Display each match of the regex within the text in bold red.
+pub fn highlight_matches(text: &str, regex: &Regex) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+    for m in regex.find_iter(text) {
+        result.push_str(&text[last..m.start()]);
+        result.push_str(&m.as_str().red().bold().to_string());
+        last = m.end();
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+//  Print the lines of a diff.
+//  With `word_diff` each run of removed lines that is immediately followed
+//  by a run of the same number of added lines is paired up line by line and
+//  only the words that changed are highlighted, using the given algorithm
+//  to match the words.  All other lines are printed by print_diff_line.
+pub fn print_diff_lines(lines: &[String], word_diff: Option<crate::diff::Algorithm>) {
+    let colors = diff_colors();
+    let Some(algorithm) = word_diff else {
+        lines.iter().for_each(|line| print_diff_line(line, colors));
+        return;
+    };
+
+    let is_removed = |line: &String| line.starts_with('-') && !line.starts_with("---");
+    let is_added = |line: &String| line.starts_with('+') && !line.starts_with("+++");
+    let mut index = 0;
+    while index < lines.len() {
+        let removed = lines[index..].iter().take_while(|l| is_removed(l)).count();
+        let added = lines[index + removed..].iter().take_while(|l| is_added(l)).count();
+        if removed > 0 && removed == added {
+            let (old, new) = lines[index..index + removed * 2].split_at(removed);
+            let pairs: Vec<(String, String)> = old.iter().zip(new).map(|(o, n)| word_diff_pair(o, n, algorithm, colors)).collect();
+            pairs.iter().for_each(|(o, _)| println!("{}", o));
+            pairs.iter().for_each(|(_, n)| println!("{}", n));
+            index += removed * 2;
+        } else {
+            //  Print the unpaired run (or a single line) as is.
+            let count = (removed + added).max(1);
+            lines[index..index + count].iter().for_each(|line| print_diff_line(line, colors));
+            index += count;
+        }
+    }
+}
+
+//  Split a line into words and the runs of other characters between them.
+fn diff_words(text: &str) -> Vec<&str> {
+    static WORDS: OnceLock<Regex> = OnceLock::new();
+    let re = WORDS.get_or_init(|| Regex::new(r"\w+|\s+|[^\w\s]").expect("Error parsing diff words regex"));
+    re.find_iter(text).map(|m| m.as_str()).collect()
+}
 
-        for path_entry in &log_entry.paths {
-            match path_entry.action.as_str() {
-                "M" => totals.chg += 1,
-                "A" => totals.add += 1,
-                "D" => totals.del += 1,
-                "R" => totals.rep += 1,
-                _   => ()
+//  Return the removed and added lines with the changed words highlighted.
+fn word_diff_pair(old_line: &str, new_line: &str, algorithm: crate::diff::Algorithm, colors: &DiffColors) -> (String, String) {
+    let old_words = diff_words(&old_line[1..]);
+    let new_words = diff_words(&new_line[1..]);
+    let mut old_text = "-".color(colors.del).to_string();
+    let mut new_text = "+".color(colors.add).to_string();
+    for op in crate::diff::diff(&old_words, &new_words, algorithm) {
+        match op {
+            crate::diff::DiffOp::Equal(o, n) => {
+                old_text.push_str(&old_words[o].color(colors.del).to_string());
+                new_text.push_str(&new_words[n].color(colors.add).to_string());
+            }
+            crate::diff::DiffOp::Delete(o) => {
+                old_text.push_str(&old_words[o].bright_white().on_color(colors.del).to_string());
+            }
+            crate::diff::DiffOp::Insert(n) => {
+                new_text.push_str(&new_words[n].bright_white().on_color(colors.add).to_string());
             }
         }
-        let label = if totals.chg == 1 { "file" } else { "files" };
-        let tot_line = format!(
-            "{} {} modified, {} added, {} deleted, {} replaced",
-            totals.chg, label, totals.add, totals.del, totals.rep
-        );
-        println!("{}", tot_line.cyan());
     }
+    (old_text, new_text)
+}
 
-    if show_paths {
-        for path in &log_entry.paths {
-            println!("{}", formatted_log_path(path))
+//  Lines added and removed for one file of a diff.
+pub struct FileStat {
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+    pub binary: bool,
+}
+
+//  Summarize the output of `svn diff` by file.
+pub fn diff_stat(lines: &[String]) -> Vec<FileStat> {
+    let mut stats: Vec<FileStat> = Vec::new();
+    let mut in_props = false;
+    for line in lines {
+        if let Some(path) = line.strip_prefix("Index: ") {
+            in_props = false;
+            stats.push(FileStat { path: path.to_string(), added: 0, removed: 0, binary: false });
+        } else if line.starts_with("Property changes on:") {
+            in_props = true;
+        } else if let Some(stat) = stats.last_mut() {
+            if is_binary_marker(line) {
+                stat.binary = true;
+            } else if in_props || line.starts_with("+++") || line.starts_with("---") {
+                continue;
+            } else if line.starts_with('+') {
+                stat.added += 1;
+            } else if line.starts_with('-') {
+                stat.removed += 1;
+            }
         }
     }
+    stats
 }
 
-pub fn print_diff_line(line: &str) {
-    let color = if line.starts_with("---") || line.starts_with("+++") { "blue" }
-           else if line.starts_with("Index:") || line.starts_with("==========") { "yellow" }
-           else if line.starts_with("Property changes on:") { "magenta" }
-           else if line.starts_with('+') { "green" }
-           else if line.starts_with("@@") { "gray" }
-           else if line.starts_with('-') { "red" }
-           else { "white" };
+//  Print a diff summary with a histogram of the changes for each file.
+//  eg.  src/main.rs | 12 +++++++-----
+//       logo.png    | Bin
+pub fn print_diff_stat(stats: &[FileStat]) {
+    const MAX_BAR: usize = 50;
+    let path_width = stats.iter().map(|s| s.path.len()).max().unwrap_or(0);
+    let max_changes = stats.iter().map(|s| s.added + s.removed).max().unwrap_or(0);
+    let count_width = max_changes.to_string().len();
+
+    for stat in stats {
+        if stat.binary {
+            println!(" {:path_width$} | {}", stat.path, "Bin".dimmed());
+        } else {
+            let total = stat.added + stat.removed;
+            let scale = |n: usize| if max_changes > MAX_BAR { (n * MAX_BAR).div_ceil(max_changes) } else { n };
+            println!(
+                " {:path_width$} | {:>count_width$} {}{}",
+                stat.path,
+                total,
+                "+".repeat(scale(stat.added)).green(),
+                "-".repeat(scale(stat.removed)).red()
+            );
+        }
+    }
+
+    let files = stats.len();
+    let binaries = stats.iter().filter(|s| s.binary).count();
+    let added: usize = stats.iter().map(|s| s.added).sum();
+    let removed: usize = stats.iter().map(|s| s.removed).sum();
+    let mut summary = format!(
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+        files, if files == 1 { "" } else { "s" },
+        added, if added == 1 { "" } else { "s" },
+        removed, if removed == 1 { "" } else { "s" },
+    );
+    if binaries > 0 {
+        summary += &format!(", {} binary", binaries);
+    }
+    println!("{}", summary);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  Returns true if another open file (as another process would have)
+    //  can take the lock.
+    fn lock_is_free(dir: &Path) -> bool {
+        let file = File::create(dir.join("lock")).unwrap();
+        let free = file.try_lock().is_ok();
+        if free {
+            file.unlock().unwrap();
+        }
+        free
+    }
+
+    #[test]
+    fn data_lock_is_held_only_while_guards_exist() {
+        let dir = std::env::temp_dir().join(format!("svu-lock-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let outer = lock_data_directory(&dir).unwrap();
+        let inner = lock_data_directory(&dir).unwrap();
+        assert!(!lock_is_free(&dir));
+        drop(inner);
+        assert!(!lock_is_free(&dir));
+        drop(outer);
+        assert!(lock_is_free(&dir));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn text_mime_types_are_not_binary() {
+        assert!(!is_binary_mime_type(""));
+        assert!(!is_binary_mime_type("text/plain"));
+        assert!(!is_binary_mime_type("text/x-c; charset=utf-8"));
+        assert!(!is_binary_mime_type("  TEXT/HTML  "));
+        assert!(!is_binary_mime_type("image/x-xbitmap"));
+        assert!(!is_binary_mime_type("image/x-xpixmap"));
+    }
+
+    #[test]
+    fn other_mime_types_are_binary() {
+        assert!(is_binary_mime_type("application/octet-stream"));
+        assert!(is_binary_mime_type("application/pdf"));
+        assert!(is_binary_mime_type("image/png"));
+        assert!(is_binary_mime_type("Application/Octet-Stream; charset=binary"));
+    }
+
+    #[test]
+    fn binary_markers_are_recognized() {
+        assert!(is_binary_marker("Cannot display: file marked as a binary type."));
+        assert!(is_binary_marker("Cannot display: file marked as binary type."));
+        assert!(is_binary_marker("svn:mime-type = application/octet-stream"));
+        assert!(!is_binary_marker("svn:mime-type = text/plain"));
+        assert!(!is_binary_marker("+Cannot display: file marked as a binary type."));
+    }
+
+    //  Output of `LANG=de_DE.UTF-8 svn log --xml --verbose`.  Only the
+    //  commit message is in German, the action codes are not translated.
+    const GERMAN_LOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<log>
+<logentry revision="42">
+<author>jörg</author>
+<date>2024-03-01T12:30:45.123456Z</date>
+<paths>
+<path action="M" kind="file" text-mods="true" prop-mods="false">/trunk/geändert.c</path>
+<path action="M" kind="file" text-mods="true" prop-mods="false">/trunk/haupt.c</path>
+<path action="A" kind="file" text-mods="true" prop-mods="false">/trunk/neu.c</path>
+<path action="D" kind="file" text-mods="false" prop-mods="false">/trunk/alt.c</path>
+<path action="R" kind="file" text-mods="true" prop-mods="false">/trunk/ersetzt.c</path>
+</paths>
+<msg>Fehler in der Anzeige behoben</msg>
+</logentry>
+</log>
+"#;
+
+    #[test]
+    fn actions_are_colored_and_counted_in_any_locale() {
+        let entries = crate::svn::parse_svn_log(GERMAN_LOG).unwrap();
+        let paths = &entries[0].paths;
+
+        let colors: Vec<&str> = paths.iter().map(|p| action_color(&p.action)).collect();
+        assert_eq!(colors, vec!["blue", "blue", "green", "red", "white"]);
+        assert_eq!(totals_line(paths), "2 files modified, 1 added, 1 deleted, 1 replaced");
+    }
+
+    #[test]
+    fn colors_toml_overrides_the_defaults() {
+        let text = "# Colors for a light background\n\
+                    \n\
+                    header = \"bright blue\"\n\
+                    add    = \"cyan\"   # was green\n\
+                    del=\"magenta\"\n";
+        let (colors, warnings) = DiffColors::from_toml(text);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(colors, DiffColors {
+            header: Color::BrightBlue,
+            add: Color::Cyan,
+            del: Color::Magenta,
+            ..DiffColors::default()
+        });
+        assert_eq!(DiffColors::from_toml("").0, DiffColors::default());
+    }
+
+    #[test]
+    fn colors_toml_problems_are_reported() {
+        let text = "add = \"orange\"\n\
+                    hunk = \"red\"\n\
+                    del = red\n\
+                    context\n\
+                    index = \"yellow\"\n";
+        let (colors, warnings) = DiffColors::from_toml(text);
+        assert_eq!(colors, DiffColors::default());
+        assert_eq!(warnings.len(), 4);
+        assert!(warnings[0].starts_with("line 1: 'orange' must be one of"));
+        assert!(warnings[1].starts_with("line 2: 'hunk' must be one of"));
+        assert!(warnings[2].starts_with("line 3: the color must be a quoted string"));
+        assert!(warnings[3].starts_with("line 4: expected kind"));
+    }
+
+    #[test]
+    fn sha1_matches_the_standard_test_vectors() {
+        assert_eq!(sha1_hex(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(sha1_hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            sha1_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+        assert_eq!(sha1_hex(&[b'a'; 1_000_000]), "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+    }
+
+    #[test]
+    fn sha1_pads_at_the_block_boundaries() {
+        assert_eq!(sha1_hex(&[b'x'; 55]), "cef734ba81a024479e09eb5a75b6ddae62e6abf1");
+        assert_eq!(sha1_hex(&[b'x'; 56]), "901305367c259952f4e7af8323f480d59f81335b");
+        assert_eq!(sha1_hex(&[b'x'; 64]), "bb2fa3ee7afb9f54c6dfb5d021f14b1ffe40c163");
+    }
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(year, month, day, hour, min, sec).earliest().unwrap()
+    }
+
+    #[test]
+    fn date_args_accept_rfc3339() {
+        let expected = Utc.with_ymd_and_hms(2024, 1, 31, 12, 30, 0).unwrap();
+        assert_eq!(parse_date_arg("2024-01-31T12:30:00Z").unwrap(), expected);
+        assert_eq!(parse_date_arg("2024-01-31T14:30:00+02:00").unwrap(), expected);
+        assert_eq!(parse_date_arg("2024-01-31T12:30:00.000000Z").unwrap(), expected);
+    }
+
+    #[test]
+    fn date_args_accept_a_local_date_and_time() {
+        let expected = local(2024, 1, 31, 14, 30, 15);
+        assert_eq!(parse_date_arg("2024-01-31 14:30:15").unwrap(), expected);
+        assert_eq!(parse_date_arg("2024-01-31T14:30:15").unwrap(), expected);
+    }
+
+    #[test]
+    fn date_args_accept_a_local_time_without_seconds() {
+        let expected = local(2024, 1, 31, 14, 30, 0);
+        assert_eq!(parse_date_arg("2024-01-31 14:30").unwrap(), expected);
+        assert_eq!(parse_date_arg("2024-01-31T14:30").unwrap(), expected);
+    }
+
+    #[test]
+    fn date_args_without_a_time_are_midnight() {
+        assert_eq!(parse_date_arg("2024-01-31").unwrap(), local(2024, 1, 31, 0, 0, 0));
+        assert_eq!(parse_date_arg("2024-02-29").unwrap(), local(2024, 2, 29, 0, 0, 0));
+    }
+
+    #[test]
+    fn date_args_accept_a_duration_before_now() {
+        for (arg, hours) in [("12h", 12), ("30d", 30 * 24), ("6w", 6 * 7 * 24)] {
+            let before = Local::now();
+            let date = parse_date_arg(arg).unwrap();
+            let after = Local::now();
+            let duration = chrono::Duration::hours(hours);
+            assert!(before - duration <= date && date <= after - duration, "{}", arg);
+        }
+    }
 
-    println!("{}", line.color(color));
+    #[test]
+    fn invalid_date_args_are_rejected() {
+        for arg in ["", "yesterday", "2024-13-01", "2023-02-29", "2024-01-31 25:00", "31/01/2024", "2024-01-31 14", "30", "d30"] {
+            let err = parse_date_arg(arg).unwrap_err().to_string();
+            assert!(err.starts_with(&format!("Invalid date '{}'", arg)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn durations_accept_each_unit() {
+        let cases = [
+            ("1h", 1),
+            ("12h", 12),
+            ("0d", 0),
+            ("30d", 30 * 24),
+            ("6w", 6 * 7 * 24),
+            ("3m", 3 * 30 * 24),
+            ("1y", 365 * 24),
+            ("007d", 7 * 24),
+        ];
+        for (text, hours) in cases {
+            assert_eq!(parse_duration(text).unwrap(), chrono::Duration::hours(hours), "{}", text);
+        }
+    }
+
+    #[test]
+    fn invalid_durations_are_rejected() {
+        for text in ["10", "d", "5x", "", "-5d", "5 d", " 5d", "5dd", "5D", "1.5d", "d5", "99999999999999999999h", "9999999999999999y"] {
+            let err = parse_duration(text).unwrap_err().to_string();
+            assert!(err.starts_with(&format!("Invalid duration '{}'", text)), "{}", err);
+        }
+        assert!(parse_date_arg("9999999999999999y").is_err());
+    }
+
+    #[test]
+    fn wrapping_breaks_at_word_boundaries() {
+        assert_eq!(wrap_to_width("the quick brown fox jumps", 10), vec!["the quick", "brown fox", "jumps"]);
+        assert_eq!(wrap_to_width("  spaced   out\twords  ", 20), vec!["spaced out words"]);
+        assert_eq!(wrap_to_width("exactly ten", 11), vec!["exactly ten"]);
+    }
+
+    #[test]
+    fn wrapping_an_empty_text_gives_one_empty_line() {
+        assert_eq!(wrap_to_width("", 10), vec![""]);
+        assert_eq!(wrap_to_width("   ", 10), vec![""]);
+    }
+
+    #[test]
+    fn wrapping_puts_a_long_word_on_its_own_line() {
+        assert_eq!(
+            wrap_to_width("see https://example.com/a/very/long/path for details", 12),
+            vec!["see", "https://example.com/a/very/long/path", "for details"]
+        );
+        assert_eq!(wrap_to_width("abcdefghij", 4), vec!["abcdefghij"]);
+    }
+
+    #[test]
+    fn wrapping_counts_wide_characters_as_two_columns() {
+        //  Each of these characters is two columns wide.
+        assert_eq!(wrap_to_width("日本 語の テキスト", 9), vec!["日本 語の", "テキスト"]);
+        assert_eq!(wrap_to_width("漢字 ab", 6), vec!["漢字", "ab"]);
+    }
+
+    #[test]
+    fn wrapping_ignores_color_codes() {
+        let red = "\x1b[31mred\x1b[0m";
+        let text = format!("{} green blue", red);
+        assert_eq!(wrap_to_width(&text, 9), vec![format!("{} green", red), "blue".to_string()]);
+    }
+
+    #[test]
+    fn safe_arguments_are_not_quoted() {
+        assert_eq!(shell_quote("log"), "log");
+        assert_eq!(shell_quote("--revision=HEAD:100"), "--revision=HEAD:100");
+        assert_eq!(shell_quote("^/trunk/src/main.rs@12"), "^/trunk/src/main.rs@12");
+    }
+
+    #[test]
+    fn special_characters_are_quoted() {
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("my file.txt"), "'my file.txt'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("$HOME"), "'$HOME'");
+        assert_eq!(shell_quote("`rm -rf /`"), "'`rm -rf /`'");
+        assert_eq!(shell_quote("first\nsecond"), "'first\nsecond'");
+        assert_eq!(shell_quote("a;b|c&d>e"), "'a;b|c&d>e'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quoted_arguments_survive_the_shell() {
+        for arg in ["", "my file.txt", "it's", "$HOME", "`echo no`", "first\nsecond", "'\"\\*?"] {
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("printf '%s' {}", shell_quote(arg)))
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), arg);
+        }
+    }
 }